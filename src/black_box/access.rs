@@ -0,0 +1,142 @@
+use alloc::vec::Vec;
+use core::any::{type_name, TypeId};
+
+///
+/// The types which a fetch kind borrows from a storage, split into the
+/// ones it only reads and the ones it writes to.
+///
+/// This is recorded by [`Fetch::access`] and [`FetchMultiple::access`],
+/// where taking values out of a unit, such as with `Box<T>`, counts as
+/// writing to it. Two fetches whose accesses don't conflict can be held
+/// at the same time.
+///
+/// [`Fetch::access`]: ./trait.Fetch.html#tymethod.access
+/// [`FetchMultiple::access`]: ./trait.FetchMultiple.html#tymethod.access
+///
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Access {
+    reads: Vec<TypeId>,
+    writes: Vec<TypeId>,
+    taken: Vec<TypeId>,
+    uses: Vec<(TypeId, Use)>,
+    names: Vec<(TypeId, &'static str)>,
+}
+
+// How a type is used by a fetch, in the order the uses were recorded in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Use {
+    Read,
+    Write,
+    Take,
+}
+
+impl Access {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    ///
+    /// Records that `T` is read.
+    ///
+    pub fn read<T: 'static>(&mut self) {
+        self.reads.push(TypeId::of::<T>());
+        self.uses.push((TypeId::of::<T>(), Use::Read));
+        self.name::<T>();
+    }
+
+    ///
+    /// Records that `T` is written to.
+    ///
+    pub fn write<T: 'static>(&mut self) {
+        self.writes.push(TypeId::of::<T>());
+        self.uses.push((TypeId::of::<T>(), Use::Write));
+        self.name::<T>();
+    }
+
+    fn name<T: 'static>(&mut self) {
+        let id = TypeId::of::<T>();
+        if !self.names.iter().any(|&(x, _)| x == id) {
+            self.names.push((id, type_name::<T>()));
+        }
+    }
+
+    ///
+    /// Records that values of `T` are taken out of the storage, which counts
+    /// as writing to it, although nothing stays borrowed once the fetch
+    /// returns.
+    ///
+    pub fn take<T: 'static>(&mut self) {
+        self.writes.push(TypeId::of::<T>());
+        self.taken.push(TypeId::of::<T>());
+        self.uses.push((TypeId::of::<T>(), Use::Take));
+        self.name::<T>();
+    }
+
+    ///
+    /// The types which are read, in the order they were recorded in.
+    ///
+    pub fn reads(&self) -> &[TypeId] {
+        &self.reads
+    }
+
+    ///
+    /// The types which are written to, in the order they were recorded in.
+    ///
+    pub fn writes(&self) -> &[TypeId] {
+        &self.writes
+    }
+
+    ///
+    /// The types whose values are taken out, which are also part of
+    /// [`writes`](#method.writes).
+    ///
+    pub fn taken(&self) -> &[TypeId] {
+        &self.taken
+    }
+
+    ///
+    /// Each type which is either read or written to, once.
+    ///
+    #[cfg(any(feature = "diagnostics", feature = "metrics", feature = "tracing"))]
+    pub(crate) fn used(&self) -> Vec<TypeId> {
+        self.names.iter().map(|&(id, _)| id).collect()
+    }
+
+    ///
+    /// Each type which is either read or written to, once, alongside its
+    /// name.
+    ///
+    pub(crate) fn names(&self) -> &[(TypeId, &'static str)] {
+        &self.names
+    }
+
+    ///
+    /// Checks whether `self` and `other` cannot be held at the same time,
+    /// which is the case when either writes to a type the other uses.
+    ///
+    pub fn conflicts_with(&self, other: &Access) -> bool {
+        self.writes
+            .iter()
+            .any(|x| other.reads.contains(x) || other.writes.contains(x))
+            || other.writes.iter().any(|x| self.reads.contains(x))
+    }
+
+    ///
+    /// Checks whether `self` writes to a type which it also reads or
+    /// writes to elsewhere, such as in `(&mut A, &A)`, which can never
+    /// be fetched.
+    ///
+    /// Taken values do not stay borrowed, so a take only conflicts with
+    /// the uses of the same type recorded before it. This means that
+    /// `(Box<A>, &[A])` can be fetched, while `(&[A], Box<A>)` cannot.
+    ///
+    pub fn conflicts_with_itself(&self) -> bool {
+        self.uses.iter().enumerate().any(|(i, &(id, used))| {
+            self.uses[..i].iter().any(|&(earlier, earlier_use)| {
+                earlier == id
+                    && earlier_use != Use::Take
+                    && (used != Use::Read || earlier_use == Use::Write)
+            })
+        })
+    }
+}
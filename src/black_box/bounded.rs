@@ -0,0 +1,61 @@
+use super::{BlackBox, DynamicResult, StorageUnit, Unit};
+
+///
+/// What a bounded unit does when a value is inserted while it is full.
+///
+/// The values of a bounded unit are kept from oldest to newest, or from
+/// least to most recently touched, so the value which is evicted is always
+/// the first one.
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BoundPolicy {
+    ///
+    /// Returns the inserted value alongside `UnitError::Full`.
+    ///
+    Reject,
+    ///
+    /// Drops the value which was inserted first.
+    ///
+    EvictOldest,
+    ///
+    /// Drops the value which was least recently touched, where a value is
+    /// touched by being inserted or passed to `touch`.
+    ///
+    /// Borrowing the values using `get` does not count as touching them, as
+    /// the values of a bounded unit can only be borrowed all at once, so
+    /// `touch` is how the values which are in use are marked as such.
+    ///
+    EvictLeastRecentlyTouched,
+}
+
+impl<U: ?Sized + for<'a> Unit<'a>> BlackBox<U> {
+    ///
+    /// Marks the first value of type `T` for which `f` returns `true` as
+    /// the most recently touched one, moving it to the end of the unit.
+    ///
+    /// This returns `UnitError::IsNope` in the case that `f` doesn't match
+    /// any value, and `UnitError::Unsupported` in the case that the unit
+    /// for `T` was not allocated using `allocate_bounded` with the
+    /// `BoundPolicy::EvictLeastRecentlyTouched` policy.
+    ///
+    /// # Example
+    /// ```
+    /// # fn main() {
+    /// use restor::{BoundPolicy, DynamicStorage};
+    /// let mut storage = DynamicStorage::new();
+    /// storage.allocate_bounded::<usize>(2, BoundPolicy::EvictLeastRecentlyTouched);
+    /// storage.insert_many(vec![0usize, 1]).unwrap();
+    /// storage.touch::<usize, _>(|x| *x == 0).unwrap();
+    /// storage.insert(2usize).unwrap();
+    /// assert_eq!(&*storage.get::<&[usize]>().unwrap(), &[0, 2]);
+    /// # }
+    /// ```
+    ///
+    pub fn touch<T: 'static, F: FnMut(&T) -> bool>(&self, f: F) -> DynamicResult<()> {
+        self.unit_get::<T>()?
+            .storage_mut()?
+            .downcast_mut::<StorageUnit<T>>()
+            .unwrap()
+            .touch(f)
+    }
+}
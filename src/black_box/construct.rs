@@ -0,0 +1,183 @@
+use super::{Access, BlackBox, DynamicResult, ErrorDesc, FetchMultiple, Storage, Unit, UnitError};
+use alloc::vec::Vec;
+use core::any::{type_name, TypeId};
+
+///
+/// Implemented for types which are built out of other values in a storage
+/// of type `S`, such as a service built out of its configuration and the
+/// other services it uses.
+///
+/// The values it is built out of are fetched using `Deps`, which can be
+/// any [`FetchMultiple`], such as `(&'a Config, &'a mut Database)`, where
+/// `'a` is the lifetime of the borrow of the storage they are fetched
+/// from.
+///
+/// # Example
+/// ```
+/// # fn main() {
+/// use restor::{Dependencies, FromStorage, RwLockStorage};
+/// #[derive(Debug)]
+/// struct Config {
+///     url: String,
+/// }
+/// struct Database {
+///     url: String,
+/// }
+/// struct Users {
+///     table: String,
+/// }
+///
+/// impl<'a> FromStorage<'a, RwLockStorage> for Database {
+///     type Deps = &'a Config;
+///     fn from_storage(config: Dependencies<'a, Self, RwLockStorage>) -> Self {
+///         Database {
+///             url: config.url.clone(),
+///         }
+///     }
+/// }
+///
+/// impl<'a> FromStorage<'a, RwLockStorage> for Users {
+///     type Deps = (&'a Config, &'a Database);
+///     fn from_storage((_, database): Dependencies<'a, Self, RwLockStorage>) -> Self {
+///         Users {
+///             table: format!("{}/users", database.url),
+///         }
+///     }
+/// }
+///
+/// let mut storage = RwLockStorage::new();
+/// storage.allocate_for::<Config>();
+/// storage.allocate_from_storage::<Database>();
+/// storage.allocate_from_storage::<Users>();
+/// storage
+///     .insert(Config {
+///         url: "db://localhost".to_string(),
+///     })
+///     .unwrap();
+/// // Constructing `Users` constructs the `Database` it depends on first.
+/// storage.construct::<Users>().unwrap();
+/// assert_eq!(storage.get::<&Users>().unwrap().table, "db://localhost/users");
+/// # }
+/// ```
+///
+/// [`FetchMultiple`]: ./trait.FetchMultiple.html
+///
+pub trait FromStorage<'a, S: Storage>: Sized {
+    ///
+    /// The values `Self` is built out of.
+    ///
+    type Deps: FetchMultiple<'a, S::Unit>;
+    ///
+    /// Builds `Self` out of the values fetched using `Deps`.
+    ///
+    fn from_storage(deps: Dependencies<'a, Self, S>) -> Self;
+}
+
+///
+/// The values a [`FromStorage`] type `T` is built out of, as fetched
+/// from a storage of type `S`.
+///
+/// [`FromStorage`]: ./trait.FromStorage.html
+///
+pub type Dependencies<'a, T, S> =
+    <<T as FromStorage<'a, S>>::Deps as FetchMultiple<'a, <S as Storage>::Unit>>::Output;
+
+///
+/// Constructs the value of a unit registered using `register_constructor`,
+/// given the types which are already being constructed.
+///
+pub(crate) type Constructor<U> =
+    fn(&BlackBox<U>, &mut Vec<(TypeId, &'static str)>) -> DynamicResult<()>;
+
+impl<U: ?Sized + for<'a> Unit<'a>> BlackBox<U> {
+    ///
+    /// Internal function. Registers `T` to be constructed using its
+    /// [`FromStorage`] implementation when a type constructed using
+    /// `construct` depends on it.
+    ///
+    /// [`FromStorage`]: ./trait.FromStorage.html
+    ///
+    #[doc(hidden)]
+    pub fn register_constructor<S: Storage<Unit = U>, T: for<'a> FromStorage<'a, S> + 'static>(
+        &mut self,
+    ) {
+        if let Some(info) = self.info.get_mut(&TypeId::of::<T>()) {
+            info.construct = Some(Self::construct_with::<S, T>);
+        }
+    }
+
+    ///
+    /// Builds `T` out of the values it depends on using its [`FromStorage`]
+    /// implementation, and inserts it into the storage.
+    ///
+    /// Before `T` is built, each of its dependencies whose unit is empty is
+    /// constructed the same way, in the case that its unit was allocated
+    /// using `allocate_from_storage`. Units allocated using
+    /// `allocate_with_factory` are left for their factory to fill in.
+    ///
+    /// This fails with `UnitError::AlreadyPresent` without building anything
+    /// in the case that the unit for `T` already holds a value, or after
+    /// building it in the case that another value was inserted meanwhile.
+    /// It fails with `ErrorDesc::MissingDependency` in the case that a
+    /// dependency has no unit, or is borrowed while empty and cannot be
+    /// constructed, and with `ErrorDesc::DependencyCycle` in the case that a
+    /// type depends on itself. Otherwise, it fails in the same way fetching
+    /// the dependencies using `get` or inserting `T` would. Dependencies
+    /// which were constructed before failing are kept.
+    ///
+    /// [`FromStorage`]: ./trait.FromStorage.html
+    ///
+    pub fn construct<S: Storage<Unit = U>, T: for<'a> FromStorage<'a, S> + 'static>(
+        &self,
+    ) -> DynamicResult<()> {
+        self.construct_with::<S, T>(&mut Vec::new())
+    }
+
+    // Constructs `T`, where `stack` holds the types which are waiting on it
+    // to be constructed, in order, alongside their names.
+    fn construct_with<'a, S: Storage<Unit = U>, T: for<'b> FromStorage<'b, S> + 'static>(
+        &'a self,
+        stack: &mut Vec<(TypeId, &'static str)>,
+    ) -> DynamicResult<()> {
+        let id = TypeId::of::<T>();
+        if let Some(start) = stack.iter().position(|&(x, _)| x == id) {
+            let mut cycle = stack[start..]
+                .iter()
+                .map(|&(_, name)| name)
+                .collect::<Vec<_>>();
+            cycle.push(type_name::<T>());
+            return Err(ErrorDesc::DependencyCycle(cycle));
+        }
+        if self.unit_state(&id)?.len > 0 {
+            return Err(ErrorDesc::Unit(UnitError::AlreadyPresent));
+        }
+
+        let mut access = Access::new();
+        <T as FromStorage<'a, S>>::Deps::access(&mut access);
+        stack.push((id, type_name::<T>()));
+        for &(dep, name) in access.names() {
+            let info = self
+                .info
+                .get(&dep)
+                .ok_or(ErrorDesc::MissingDependency(type_name::<T>(), name))?;
+            // A unit which cannot be read right now is left for `get` to
+            // report.
+            if !self.unit_state(&dep).is_ok_and(|x| x.len == 0) {
+                continue;
+            }
+            match info.construct {
+                Some(construct) => construct(self, stack)?,
+                // Values taken out of a unit, such as using `Drain`, need
+                // not be there.
+                None if info.factory.is_some() || access.taken().contains(&dep) => {}
+                None => return Err(ErrorDesc::MissingDependency(type_name::<T>(), name)),
+            }
+        }
+        stack.pop();
+
+        // The unit is checked again when inserting, in the case that another
+        // value was inserted while `T` was being built.
+        let value = T::from_storage(self.get::<<T as FromStorage<'a, S>>::Deps>()?);
+        self.insert_into_empty(value)
+    }
+}
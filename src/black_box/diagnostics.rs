@@ -0,0 +1,163 @@
+use super::{Access, BlackBox, DynamicResult, FetchMultiple, Unit};
+use core::any::TypeId;
+use std::collections::VecDeque;
+use std::fmt;
+use std::panic::Location;
+use std::sync::{Mutex, MutexGuard, PoisonError};
+use std::thread::{self, Thread};
+
+// The most borrows remembered for a unit, past which the oldest are forgotten.
+const MAX_HOLDERS: usize = 16;
+
+///
+/// Where, and on which thread, a value was fetched from a storage.
+///
+/// This is recorded for each fetch which keeps its unit borrowed with the
+/// `diagnostics` feature, and is returned by `BlackBox::borrow_sites` to
+/// find out which borrows a fetch failing with `BorrowedIncompatibly`
+/// conflicted with.
+///
+/// # Example
+/// ```
+/// # fn main() {
+/// use restor::{make_storage, DynamicStorage, ErrorDesc};
+/// let storage = make_storage!(DynamicStorage: usize);
+/// storage.insert(0usize).unwrap();
+/// let line = line!() + 1;
+/// let _held = storage.get::<&mut usize>().unwrap();
+/// assert_eq!(
+///     storage.get::<&usize>().map(|_| ()),
+///     Err(ErrorDesc::BorrowedIncompatibly)
+/// );
+/// let sites = storage.borrow_sites::<usize>();
+/// assert!(sites[0].is_mutable());
+/// assert_eq!(sites[0].location().line(), line);
+/// # }
+/// ```
+///
+#[derive(Debug, Clone)]
+pub struct BorrowSite {
+    location: &'static Location<'static>,
+    thread: Thread,
+    mutable: bool,
+}
+
+impl BorrowSite {
+    ///
+    /// The place the fetch was made in.
+    ///
+    pub fn location(&self) -> &'static Location<'static> {
+        self.location
+    }
+
+    ///
+    /// The thread the fetch was made on.
+    ///
+    pub fn thread(&self) -> &Thread {
+        &self.thread
+    }
+
+    ///
+    /// Whether the unit was borrowed mutably.
+    ///
+    pub fn is_mutable(&self) -> bool {
+        self.mutable
+    }
+}
+
+impl fmt::Display for BorrowSite {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let kind = if self.mutable { "mutable" } else { "shared" };
+        write!(f, "{} borrow at {} on thread ", kind, self.location)?;
+        match self.thread.name() {
+            Some(name) => write!(f, "`{}`", name),
+            None => write!(f, "{:?}", self.thread.id()),
+        }
+    }
+}
+
+///
+/// The borrows which may still be held on a unit, oldest first.
+///
+#[derive(Default)]
+pub(crate) struct Holders(Mutex<VecDeque<BorrowSite>>);
+
+impl Holders {
+    fn lock(&self) -> MutexGuard<'_, VecDeque<BorrowSite>> {
+        self.0.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl<U: ?Sized + for<'a> Unit<'a>> BlackBox<U> {
+    ///
+    /// The borrows which may still be held on the unit for `T`, oldest
+    /// first, such as to find out which borrows a fetch failing with
+    /// `ErrorDesc::BorrowedIncompatibly` conflicted with.
+    ///
+    /// This may include borrows which were already released, as releasing a
+    /// borrow is only noticed once a fetch of the unit which it would have
+    /// conflicted with succeeds.
+    ///
+    pub fn borrow_sites<T: 'static>(&self) -> Vec<BorrowSite> {
+        self.info
+            .get(&TypeId::of::<T>())
+            .map(|info| info.holders.lock().iter().cloned().collect())
+            .unwrap_or_default()
+    }
+
+    ///
+    /// Runs `fetch`, which fetches `T`, and records the borrows it keeps
+    /// as having been made at `location` in the case that it succeeds and
+    /// `record` is set.
+    ///
+    pub(crate) fn track_fetch<'a, T: FetchMultiple<'a, U>, R>(
+        &self,
+        location: &'static Location<'static>,
+        record: bool,
+        fetch: impl FnOnce() -> DynamicResult<R>,
+    ) -> DynamicResult<R> {
+        let result = fetch()?;
+        let mut access = Access::new();
+        T::access(&mut access);
+        let thread = thread::current();
+
+        // A fetch which succeeded could not have conflicted with any of the
+        // borrows held on its units, so those it would have conflicted with
+        // have been released since.
+        for id in access.reads() {
+            if let Some(info) = self.info.get(id) {
+                let mut holders = info.holders.lock();
+                holders.retain(|x| !x.mutable);
+                if record {
+                    push(&mut holders, location, &thread, false);
+                }
+            }
+        }
+        for id in access.writes() {
+            if let Some(info) = self.info.get(id) {
+                let mut holders = info.holders.lock();
+                holders.clear();
+                if record && !access.taken().contains(id) {
+                    push(&mut holders, location, &thread, true);
+                }
+            }
+        }
+        Ok(result)
+    }
+}
+
+fn push(
+    holders: &mut VecDeque<BorrowSite>,
+    location: &'static Location<'static>,
+    thread: &Thread,
+    mutable: bool,
+) {
+    if holders.len() == MAX_HOLDERS {
+        holders.pop_front();
+    }
+    holders.push_back(BorrowSite {
+        location,
+        thread: thread.clone(),
+        mutable,
+    });
+}
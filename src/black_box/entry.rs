@@ -0,0 +1,206 @@
+use super::{BlackBox, DynamicResult, MapMut, MutBorrowed, StorageUnit, Unit, Waitable};
+#[cfg(feature = "tracing")]
+use core::any::type_name;
+use core::any::Any;
+#[cfg(feature = "diagnostics")]
+use std::panic::Location;
+#[cfg(feature = "tracing")]
+use tracing::debug_span;
+
+// The lock an `Entry` holds on its unit.
+type Guard<'a, U, T> = <MutBorrowed<'a, U> as MapMut<dyn Any, StorageUnit<T>>>::Output;
+
+///
+/// The unit for `T`, locked for writing until the entry, or the guard it
+/// is turned into, is dropped. This is returned by `entry`, and is used to
+/// get the value in the unit, or to insert one in the case that it is
+/// empty, without letting go of the lock in between.
+///
+/// # Example
+/// ```
+/// # fn main() {
+/// use restor::{make_storage, RwLockStorage};
+/// let storage = make_storage!(RwLockStorage: usize);
+/// for _ in 0..3 {
+///     storage
+///         .entry::<usize>()
+///         .unwrap()
+///         .and_modify(|x| *x += 1)
+///         .or_insert(0);
+/// }
+/// assert_eq!(*storage.get::<&usize>().unwrap(), 2);
+/// # }
+/// ```
+///
+pub struct Entry<'a, U: ?Sized + for<'b> Unit<'b>, T: 'static>
+where
+    MutBorrowed<'a, U>: MapMut<dyn Any, StorageUnit<T>>,
+{
+    unit: &'a U,
+    storage: Guard<'a, U, T>,
+}
+
+impl<'a, U: ?Sized + for<'b> Unit<'b>, T: 'static> Entry<'a, U, T>
+where
+    MutBorrowed<'a, U>:
+        MapMut<dyn Any, StorageUnit<T>, Func = dyn Fn(&mut dyn Any) -> &mut StorageUnit<T>>,
+    Guard<'a, U, T>: MapMut<StorageUnit<T>, T, Func = dyn Fn(&mut StorageUnit<T>) -> &mut T>,
+{
+    // Checks that the unit `storage` was borrowed from holds at most one
+    // value.
+    fn new(unit: &'a U, storage: MutBorrowed<'a, U>) -> DynamicResult<Self> {
+        let f: &dyn Fn(&mut dyn Any) -> &mut StorageUnit<T> =
+            &|x| x.downcast_mut::<StorageUnit<T>>().unwrap();
+        let mut storage = MapMut::<dyn Any, StorageUnit<T>>::map(storage, f);
+        if !matches!(*storage, StorageUnit::Nope) {
+            storage.one_mut()?;
+        }
+        Ok(Self { unit, storage })
+    }
+
+    ///
+    /// Whether the unit holds a value.
+    ///
+    pub fn is_occupied(&self) -> bool {
+        matches!(*self.storage, StorageUnit::One(_))
+    }
+
+    ///
+    /// Runs `f` on the value in the unit, in the case that there is one.
+    ///
+    pub fn and_modify(mut self, f: impl FnOnce(&mut T)) -> Self {
+        if let StorageUnit::One(x) = &mut *self.storage {
+            f(x);
+        }
+        self
+    }
+
+    ///
+    /// Inserts the value returned by `f` in the case that the unit is
+    /// empty, and returns the lock on the value in the unit.
+    ///
+    pub fn or_insert_with(
+        mut self,
+        f: impl FnOnce() -> T,
+    ) -> <Guard<'a, U, T> as MapMut<StorageUnit<T>, T>>::Output {
+        if let StorageUnit::Nope = *self.storage {
+            self.storage.insert(f());
+            if let Some(signal) = self.unit.signal() {
+                signal.notify();
+            }
+        }
+        let f: &dyn Fn(&mut StorageUnit<T>) -> &mut T = &|x| x.one_mut().unwrap();
+        MapMut::<StorageUnit<T>, T>::map(self.storage, f)
+    }
+
+    ///
+    /// Inserts `value` in the case that the unit is empty, and returns the
+    /// lock on the value in the unit.
+    ///
+    pub fn or_insert(self, value: T) -> <Guard<'a, U, T> as MapMut<StorageUnit<T>, T>>::Output {
+        self.or_insert_with(|| value)
+    }
+
+    ///
+    /// Inserts `T::default()` in the case that the unit is empty, and
+    /// returns the lock on the value in the unit.
+    ///
+    pub fn or_default(self) -> <Guard<'a, U, T> as MapMut<StorageUnit<T>, T>>::Output
+    where
+        T: Default,
+    {
+        self.or_insert_with(T::default)
+    }
+}
+
+impl<U: ?Sized + for<'a> Unit<'a>> BlackBox<U> {
+    ///
+    /// Locks the unit for `T` for writing, returning an [`Entry`] which
+    /// inserts a value into it in the case that it is empty. This is what
+    /// makes getting or creating a single value atomic on the concurrent
+    /// storages, as the lock is only let go of once the guard returned
+    /// by the entry is dropped.
+    ///
+    /// This fails in the case that the unit is borrowed, or holds more than
+    /// one value, in which case it fails the same way `get::<&mut T>` would.
+    ///
+    /// [`Entry`]: ./struct.Entry.html
+    ///
+    #[inline(always)]
+    #[track_caller]
+    pub fn entry<'a, T: 'static>(&'a self) -> DynamicResult<Entry<'a, U, T>>
+    where
+        MutBorrowed<'a, U>:
+            MapMut<dyn Any, StorageUnit<T>, Func = dyn Fn(&mut dyn Any) -> &mut StorageUnit<T>>,
+        Guard<'a, U, T>: MapMut<StorageUnit<T>, T, Func = dyn Fn(&mut StorageUnit<T>) -> &mut T>,
+    {
+        self.fetch_entry(
+            #[cfg(feature = "diagnostics")]
+            Location::caller(),
+            #[cfg(feature = "metrics")]
+            false,
+            || {
+                let unit = self.unit_get::<T>()?;
+                Entry::new(unit, unit.storage_mut()?)
+            },
+        )
+    }
+
+    ///
+    /// Waits to lock the unit for `T` for writing, returning an [`Entry`].
+    /// Please refer to [`BlackBox::entry`] for the details.
+    ///
+    /// [`Entry`]: ./struct.Entry.html
+    /// [`BlackBox::entry`]: #method.entry
+    ///
+    #[inline(always)]
+    #[track_caller]
+    pub fn waiting_entry<'a, T: 'static>(&'a self) -> DynamicResult<Entry<'a, U, T>>
+    where
+        MutBorrowed<'a, U>: Waitable
+            + MapMut<dyn Any, StorageUnit<T>, Func = dyn Fn(&mut dyn Any) -> &mut StorageUnit<T>>,
+        Guard<'a, U, T>: MapMut<StorageUnit<T>, T, Func = dyn Fn(&mut StorageUnit<T>) -> &mut T>,
+    {
+        #[cfg(feature = "tracing")]
+        let _span =
+            debug_span!(target: "restor", "waiting_entry", unit = type_name::<T>()).entered();
+        self.fetch_entry(
+            #[cfg(feature = "diagnostics")]
+            Location::caller(),
+            #[cfg(feature = "metrics")]
+            true,
+            || {
+                let unit = self.unit_get::<T>()?;
+                Entry::new(unit, unit.waiting_storage_mut()?)
+            },
+        )
+    }
+
+    // Runs `fetch`, which locks the unit for `T`, the same way `get::<&mut T>`
+    // and `waiting_get::<&mut T>` are run.
+    fn fetch_entry<'a, T: 'static>(
+        &'a self,
+        #[cfg(feature = "diagnostics")] location: &'static Location<'static>,
+        #[cfg(feature = "metrics")] waits: bool,
+        fetch: impl FnOnce() -> DynamicResult<Entry<'a, U, T>>,
+    ) -> DynamicResult<Entry<'a, U, T>>
+    where
+        MutBorrowed<'a, U>:
+            MapMut<dyn Any, StorageUnit<T>, Func = dyn Fn(&mut dyn Any) -> &mut StorageUnit<T>>,
+        Guard<'a, U, T>: MapMut<StorageUnit<T>, T, Func = dyn Fn(&mut StorageUnit<T>) -> &mut T>,
+    {
+        let fetch = || {
+            #[cfg(feature = "diagnostics")]
+            return self.track_fetch::<&'a mut T, _>(location, true, fetch);
+            #[cfg(not(feature = "diagnostics"))]
+            fetch()
+        };
+        #[cfg(feature = "metrics")]
+        let result = self.count_fetch::<&'a mut T, _>(waits, fetch);
+        #[cfg(not(feature = "metrics"))]
+        let result = fetch();
+        #[cfg(feature = "tracing")]
+        self.trace_fetch::<&'a mut T, _>(&result);
+        result
+    }
+}
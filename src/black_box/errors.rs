@@ -1,4 +1,6 @@
-use std::ops::BitAnd;
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+use core::ops::BitAnd;
 
 pub type DynamicResult<Ok> = Result<Ok, ErrorDesc>;
 
@@ -34,6 +36,7 @@ pub type DynamicResult<Ok> = Result<Ok, ErrorDesc>;
 /// [`BitAnd`]: https://doc.rust-lang.org/std/ops/trait.BitAnd.html
 ///
 #[derive(Debug, PartialEq, Clone)]
+#[non_exhaustive]
 pub enum ErrorDesc {
     /// Returned if there is an incompatible borrow on the contents of the unit. It follows the same
     /// rules for runtime checking as a `RefCell<T>`. Usually bundled with a `Ref<T>`/`RefMut<T>` in
@@ -77,6 +80,17 @@ pub enum ErrorDesc {
     Unit(UnitError),
     /// The case where there were two errors
     Two(Box<(ErrorDesc, ErrorDesc)>),
+    /// Returned when cloning a storage which contains a unit for a type that
+    /// wasn't allocated using `allocate_for_clone`, and therefore cannot be
+    /// cloned.
+    NotCloneable,
+    /// Returned by `construct` when a dependency of the type being constructed, named first, has
+    /// no unit allocated, or is borrowed while it has no value and cannot be constructed either.
+    /// The dependency is named second.
+    MissingDependency(&'static str, &'static str),
+    /// Returned by `construct` when constructing a type requires constructing itself first,
+    /// alongside the names of the types in the cycle, which starts and ends with that type.
+    DependencyCycle(Vec<&'static str>),
 }
 
 impl BitAnd for ErrorDesc {
@@ -98,6 +112,7 @@ impl BitAnd for ErrorDesc {
 /// such as an out of bounds error, or improper accessing of data.
 ///
 #[derive(Debug, PartialEq, Clone, Copy)]
+#[non_exhaustive]
 pub enum UnitError {
     ///
     /// Created when a `One` variant of data was requested, but either
@@ -128,4 +143,27 @@ pub enum UnitError {
     /// the `Vec<T>` contained within the storage.
     ///
     OutOfBounds,
+    ///
+    /// Returned when the kind of unit allocated for the type does not
+    /// support the requested operation.
+    ///
+    /// Describes, for example, an attempt at borrowing the contents of
+    /// an atomic unit, which can only be loaded and stored.
+    ///
+    Unsupported,
+    ///
+    /// Returned when inserting a value into a set unit which already
+    /// contains an equal value. The value, or the values which were
+    /// already present when inserting many, are returned alongside this.
+    ///
+    /// Also returned when constructing a value using `construct` while
+    /// its unit already holds one.
+    ///
+    AlreadyPresent,
+    ///
+    /// Returned when inserting into a bounded unit which is full, and
+    /// whose policy is to reject values. The values are returned
+    /// alongside this.
+    ///
+    Full,
 }
@@ -0,0 +1,228 @@
+use super::construct::Constructor;
+#[cfg(feature = "diagnostics")]
+use super::diagnostics::Holders;
+#[cfg(feature = "metrics")]
+use super::metrics::Counters;
+use super::{DynamicResult, StorageUnit, Unit};
+use alloc::boxed::Box;
+use alloc::sync::Arc;
+use core::any::{type_name, Any};
+use core::fmt;
+
+///
+/// Type erased information about a unit, which is recorded when a unit
+/// is allocated for a type.
+///
+/// Besides the name of the type, this holds the functions which need to
+/// know the concrete type stored in the unit, such as the function used
+/// to create a new empty unit, or the ones the user opted into by using
+/// `allocate_for_debug`, `allocate_for_clone`, `allocate_events` or
+/// `allocate_from_storage`.
+///
+/// The factory registered using `allocate_with_factory` and the kind of
+/// units allocated using `allocate_sorted` or `allocate_bounded` are the
+/// only parts which are not function pointers, as they hold closures.
+///
+pub(crate) struct UnitInfo<U: ?Sized> {
+    pub(crate) name: &'static str,
+    pub(crate) new_unit: fn() -> Box<U>,
+    pub(crate) kind: Option<NewKind>,
+    pub(crate) append: fn(&U, &U) -> DynamicResult<()>,
+    pub(crate) debug: Option<fn(&dyn Any, &mut fmt::Formatter) -> fmt::Result>,
+    pub(crate) clone: Option<fn(&dyn Any, &mut dyn Any)>,
+    pub(crate) swap_events: Option<fn(&mut dyn Any)>,
+    pub(crate) factory: Option<Arc<dyn Any + Send + Sync>>,
+    pub(crate) construct: Option<Constructor<U>>,
+    #[cfg(feature = "diagnostics")]
+    pub(crate) holders: Holders,
+    #[cfg(feature = "metrics")]
+    pub(crate) counters: Counters,
+    pub(crate) state: fn(&dyn Any) -> UnitState,
+}
+
+impl<U: ?Sized> UnitInfo<U> {
+    pub(crate) fn new<T: 'static>(
+        new_unit: fn() -> Box<U>,
+        append: fn(&U, &U) -> DynamicResult<()>,
+    ) -> Self {
+        Self {
+            name: type_name::<T>(),
+            new_unit,
+            kind: None,
+            append,
+            debug: None,
+            clone: None,
+            swap_events: None,
+            factory: None,
+            construct: None,
+            #[cfg(feature = "diagnostics")]
+            holders: Holders::default(),
+            #[cfg(feature = "metrics")]
+            counters: Counters::default(),
+            state: storage_unit_state::<T>,
+        }
+    }
+}
+
+impl<U: ?Sized + for<'a> Unit<'a>> UnitInfo<U> {
+    ///
+    /// Creates a new empty unit of the same kind as the one this describes.
+    ///
+    pub(crate) fn create_unit(&self) -> Box<U> {
+        let unit = (self.new_unit)();
+        if let Some(kind) = &self.kind {
+            // The unit was just created, so it cannot be borrowed.
+            kind(&mut *unit.storage_mut().unwrap_or_else(|_| unreachable!()));
+        }
+        unit
+    }
+}
+
+impl<U: ?Sized> Clone for UnitInfo<U> {
+    fn clone(&self) -> Self {
+        Self {
+            name: self.name,
+            new_unit: self.new_unit,
+            kind: self.kind.clone(),
+            append: self.append,
+            debug: self.debug,
+            clone: self.clone,
+            swap_events: self.swap_events,
+            factory: self.factory.clone(),
+            construct: self.construct,
+            // The borrows are those of the original unit.
+            #[cfg(feature = "diagnostics")]
+            holders: Holders::default(),
+            // So are the counts, which start over.
+            #[cfg(feature = "metrics")]
+            counters: Counters::default(),
+            state: self.state,
+        }
+    }
+}
+
+///
+/// Moves the values in the `StorageUnit<T>` of `source` to the end of
+/// the one in `dest`, leaving the ones which do not fit in `source`.
+///
+pub(crate) fn append_storage_unit<T: 'static, U: ?Sized + for<'a> Unit<'a>>(
+    source: &U,
+    dest: &U,
+) -> DynamicResult<()> {
+    let mut source = source.storage_mut()?;
+    let mut dest = dest.storage_mut()?;
+    dest.downcast_mut::<StorageUnit<T>>()
+        .unwrap()
+        .append(source.downcast_mut::<StorageUnit<T>>().unwrap())
+}
+
+///
+/// Formats the `StorageUnit<T>` behind `unit`.
+///
+pub(crate) fn fmt_storage_unit<T: fmt::Debug + 'static>(
+    unit: &dyn Any,
+    f: &mut fmt::Formatter,
+) -> fmt::Result {
+    fmt::Debug::fmt(unit.downcast_ref::<StorageUnit<T>>().unwrap(), f)
+}
+
+///
+/// Overwrites the `StorageUnit<T>` behind `dest` with a clone of the
+/// one behind `source`.
+///
+pub(crate) fn clone_storage_unit<T: Clone + 'static>(source: &dyn Any, dest: &mut dyn Any) {
+    *dest.downcast_mut::<StorageUnit<T>>().unwrap() =
+        source.downcast_ref::<StorageUnit<T>>().unwrap().clone();
+}
+
+///
+/// Swaps the buffers of the `StorageUnit<T>` behind `unit`.
+///
+pub(crate) fn swap_storage_unit<T: 'static>(unit: &mut dyn Any) {
+    unit.downcast_mut::<StorageUnit<T>>()
+        .unwrap()
+        .swap_buffers();
+}
+
+///
+/// Replaces the `StorageUnit` behind a unit with an empty one of the kind
+/// the unit was allocated with, which `new_unit` cannot create on its own.
+///
+pub(crate) type NewKind = Arc<dyn Fn(&mut dyn Any) + Send + Sync>;
+
+///
+/// Returns the `NewKind` which creates empty units of the same kind as
+/// `unit`, keeping the order of a `Sorted` unit, and the capacity and
+/// policy of a `Bounded` one.
+///
+pub(crate) fn new_kind_of<T: 'static>(unit: &StorageUnit<T>) -> NewKind {
+    fn replace<T: 'static>(unit: &mut dyn Any, with: StorageUnit<T>) {
+        *unit.downcast_mut::<StorageUnit<T>>().unwrap() = with;
+    }
+    match unit {
+        StorageUnit::Sorted { order, .. } => {
+            let order = order.clone();
+            Arc::new(move |unit| replace(unit, StorageUnit::new_sorted(order.clone())))
+        }
+        StorageUnit::Bounded {
+            capacity, policy, ..
+        } => {
+            let (capacity, policy) = (*capacity, *policy);
+            Arc::new(move |unit| replace(unit, StorageUnit::<T>::new_bounded(capacity, policy)))
+        }
+        _ => Arc::new(|unit| replace(unit, StorageUnit::<T>::new())),
+    }
+}
+
+///
+/// Constructs the value of a unit the first time it is borrowed while the
+/// unit is empty.
+///
+pub(crate) type Factory<T> = Box<dyn Fn() -> T + Send + Sync>;
+
+///
+/// The kind of a `StorageUnit` and the number of values in it, as shown
+/// by metrics and traces, and checked when constructing dependencies.
+///
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct UnitState {
+    #[cfg(any(feature = "metrics", feature = "tracing"))]
+    pub(crate) kind: &'static str,
+    pub(crate) len: usize,
+}
+
+#[cfg(any(feature = "metrics", feature = "tracing"))]
+impl fmt::Display for UnitState {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.kind {
+            "Nope" | "One" => f.write_str(self.kind),
+            _ => write!(f, "{}({})", self.kind, self.len),
+        }
+    }
+}
+
+///
+/// Returns the `UnitState` of the `StorageUnit<T>` behind `unit`.
+///
+pub(crate) fn storage_unit_state<T: 'static>(unit: &dyn Any) -> UnitState {
+    let unit = unit.downcast_ref::<StorageUnit<T>>().unwrap();
+    UnitState {
+        #[cfg(any(feature = "metrics", feature = "tracing"))]
+        kind: unit.kind(),
+        len: unit.len(),
+    }
+}
+
+///
+/// Formats a borrowed unit using the function registered for it.
+///
+pub(crate) struct DebugContents<'a>(
+    pub(crate) &'a dyn Any,
+    pub(crate) fn(&dyn Any, &mut fmt::Formatter) -> fmt::Result,
+);
+
+impl fmt::Debug for DebugContents<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        (self.1)(self.0, f)
+    }
+}
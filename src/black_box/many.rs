@@ -1,11 +1,15 @@
+use super::info::Factory;
 use super::{
-    BlackBox, Borrowed, DynamicResult, Map, MapMut, MutBorrowed, StorageUnit, Unit, Waitable,
+    Access, BlackBox, Borrowed, DynamicResult, ErrorDesc, Map, MapMut, MutBorrowed, StorageUnit,
+    Unit, UnitError, Waitable,
 };
-use std::any::Any;
+use alloc::{boxed::Box, vec::Vec};
+use core::any::{Any, TypeId};
+use core::marker::PhantomData;
 
 ///
 /// The base "get" trait for acquiring data from storage. This is implemented on
-/// six types, each of which have a different output. The output is dependent on
+/// several types, each of which have a different output. The output is dependent on
 /// the type it is being implemented for.
 ///
 /// Note that this trait should be considered "sealed" as it is already implemented
@@ -21,6 +25,11 @@ pub trait Fetch<'a, U: Unit<'a> + ?Sized> {
     ///
     type Actual;
     ///
+    /// The lock held by [`can_get`](#tymethod.can_get), so that the kinds in
+    /// a tuple are checked against each other the same way `get` would be.
+    ///
+    type Guard: 'a;
+    ///
     /// Gets data from the [`BlackBox`](./struct.BlackBox.html) depending on `Self` and `Output`.
     ///
     fn get(boxed: &'a BlackBox<U>) -> DynamicResult<Self::Output>;
@@ -32,12 +41,157 @@ pub trait Fetch<'a, U: Unit<'a> + ?Sized> {
     where
         Borrowed<'a, U>: Waitable,
         MutBorrowed<'a, U>: Waitable;
+    ///
+    /// Records the types which `Self` reads from and writes to in `access`.
+    ///
+    fn access(access: &mut Access);
+    ///
+    /// Checks whether `get` would currently succeed, without waiting on any
+    /// locks or taking anything out of the storage.
+    ///
+    /// The values that the kinds before this one in a tuple would take out
+    /// of the storage are recorded in `taken`, which kinds that take values
+    /// add to.
+    ///
+    fn can_get(boxed: &'a BlackBox<U>, taken: &mut Taken) -> DynamicResult<Self::Guard>;
+}
+
+///
+/// The values which the kinds checked so far by [`Fetch::can_get`] would
+/// take out of the storage, so that the kinds after them in a tuple are
+/// checked against what those would leave behind.
+///
+/// [`Fetch::can_get`]: ./trait.Fetch.html#tymethod.can_get
+///
+#[derive(Default)]
+pub struct Taken {
+    shapes: Vec<(TypeId, Shape)>,
+}
+
+impl Taken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // The shape of `unit` once the values recorded for `T` are taken.
+    fn shape<T: 'static>(&self, unit: &StorageUnit<T>) -> Shape {
+        self.shapes
+            .iter()
+            .find(|&&(id, _)| id == TypeId::of::<T>())
+            .map_or_else(|| Shape::of(unit), |&(_, shape)| shape)
+    }
+
+    fn set<T: 'static>(&mut self, shape: Shape) {
+        match self
+            .shapes
+            .iter_mut()
+            .find(|&&mut (id, _)| id == TypeId::of::<T>())
+        {
+            Some((_, old)) => *old = shape,
+            None => self.shapes.push((TypeId::of::<T>(), shape)),
+        }
+    }
+}
+
+// What is left of a unit as far as fetching from it goes, which is all that
+// changes when values are taken out of it. Kinds of units other than the
+// plain ones stay the same kind when they are emptied, so only the number
+// of values they hold is kept for them.
+#[derive(Clone, Copy)]
+enum Shape {
+    Nope,
+    One,
+    Many(usize),
+    Kept(usize),
+}
+
+impl Shape {
+    fn of<T>(unit: &StorageUnit<T>) -> Self {
+        match unit {
+            StorageUnit::Nope => Shape::Nope,
+            StorageUnit::One(_) => Shape::One,
+            StorageUnit::Many(values) => Shape::Many(values.len()),
+            StorageUnit::Events { previous, .. } => Shape::Kept(previous.len()),
+            _ => Shape::Kept(unit.len()),
+        }
+    }
+
+    // `one` and `many` check the kinds which borrow the unit. The kind of
+    // a `Kept` unit is left as it was, so `unit` is checked instead.
+    fn one<T>(self, unit: &StorageUnit<T>, constructs: bool) -> DynamicResult<()> {
+        match self {
+            Shape::Nope if constructs => Ok(()),
+            Shape::One => Ok(()),
+            Shape::Nope | Shape::Many(_) => Err(ErrorDesc::Unit(UnitError::IsNotOne)),
+            Shape::Kept(_) => unit.one().map(drop),
+        }
+    }
+
+    fn many<T>(self, unit: &StorageUnit<T>) -> DynamicResult<()> {
+        match self {
+            Shape::Many(_) => Ok(()),
+            Shape::Nope | Shape::One => Err(ErrorDesc::Unit(UnitError::IsNotMany)),
+            Shape::Kept(_) => unit.many().map(drop),
+        }
+    }
+
+    fn many_mut<T>(self, unit: &mut StorageUnit<T>) -> DynamicResult<()> {
+        match self {
+            Shape::Kept(_) => unit.slice_mut().map(drop),
+            _ => self.many(unit),
+        }
+    }
+
+    // The rest return what `extract_one`, `extract_many`, `pop` and `drain`
+    // leave behind.
+    fn extract_one(self) -> DynamicResult<Self> {
+        match self {
+            Shape::Nope => Err(ErrorDesc::Unit(UnitError::IsNotOne)),
+            Shape::One => Ok(Shape::Nope),
+            Shape::Many(0) | Shape::Kept(0) => Err(ErrorDesc::Unit(UnitError::IsNope)),
+            Shape::Many(len) => Ok(Shape::Many(len - 1)),
+            Shape::Kept(len) => Ok(Shape::Kept(len - 1)),
+        }
+    }
+
+    fn extract_many(self) -> DynamicResult<Self> {
+        match self {
+            Shape::Nope | Shape::Kept(0) => Err(ErrorDesc::Unit(UnitError::IsNotMany)),
+            Shape::One | Shape::Many(_) => Ok(Shape::Nope),
+            Shape::Kept(_) => Ok(Shape::Kept(0)),
+        }
+    }
+
+    fn pop(self) -> DynamicResult<Self> {
+        match self {
+            Shape::Nope | Shape::Many(0) => Err(ErrorDesc::Unit(UnitError::IsNope)),
+            Shape::Many(1) => Ok(Shape::Nope),
+            Shape::Many(2) => Ok(Shape::One),
+            Shape::Kept(_) | Shape::One | Shape::Many(_) => self.extract_one(),
+        }
+    }
+
+    fn drain(self) -> Self {
+        match self {
+            Shape::Kept(_) => Shape::Kept(0),
+            _ => Shape::Nope,
+        }
+    }
+}
+
+// Fills `unit` using `factory` in the case that it is empty and there is
+// one, which is how `&T` and `&mut T` borrow a unit allocated using
+// `allocate_with_factory` for the first time.
+fn construct<T>(unit: &mut StorageUnit<T>, factory: Option<&Factory<T>>) {
+    if let (StorageUnit::Nope, Some(factory)) = (&*unit, factory) {
+        unit.insert(factory());
+    }
 }
 
 //Single value immutable
 impl<'a, T: Sized + Any + 'static, U: for<'b> Unit<'b> + ?Sized> Fetch<'a, U> for &T
 where
-    Borrowed<'a, U>: Map<(dyn Any), StorageUnit<T>, Func = dyn Fn(&dyn Any) -> &StorageUnit<T>>,
+    Borrowed<'a, U>: Map<dyn Any, StorageUnit<T>, Func = dyn Fn(&dyn Any) -> &StorageUnit<T>>,
     <Borrowed<'a, U> as Map<dyn Any, StorageUnit<T>>>::Output:
         Map<StorageUnit<T>, T, Func = dyn Fn(&StorageUnit<T>) -> &T>,
 {
@@ -47,12 +201,40 @@ where
     >>::Output;
 
     type Actual = T;
+    type Guard = <Borrowed<'a, U> as Map<dyn Any, StorageUnit<T>>>::Output;
     #[inline]
-    fn get(boxed: &'a BlackBox<U>) -> DynamicResult<Self::Output> {
+    fn access(access: &mut Access) {
+        access.read::<T>();
+    }
+    #[inline]
+    fn can_get(boxed: &'a BlackBox<U>, taken: &mut Taken) -> DynamicResult<Self::Guard> {
         let unit = boxed.unit_get::<T>()?;
         let f: &dyn Fn(&dyn Any) -> &StorageUnit<T> =
             &|x| x.downcast_ref::<StorageUnit<T>>().unwrap();
         let unit = Map::<dyn Any, StorageUnit<T>>::map(unit.storage()?, f);
+        taken
+            .shape(&unit)
+            .one(&unit, boxed.factory::<T>().is_some())?;
+        Ok(unit)
+    }
+    #[inline]
+    fn get(boxed: &'a BlackBox<U>) -> DynamicResult<Self::Output> {
+        let storage = boxed.unit_get::<T>()?;
+        let f: &dyn Fn(&dyn Any) -> &StorageUnit<T> =
+            &|x| x.downcast_ref::<StorageUnit<T>>().unwrap();
+        let mut unit = Map::<dyn Any, StorageUnit<T>>::map(storage.storage()?, f);
+        let factory = boxed
+            .factory::<T>()
+            .filter(|_| matches!(*unit, StorageUnit::Nope));
+        if factory.is_some() {
+            // Only one borrow may construct the value, so this takes the
+            // write lock and checks again before borrowing it once more.
+            drop(unit);
+            let mut locked = storage.storage_mut()?;
+            construct(locked.downcast_mut().unwrap(), factory);
+            drop(locked);
+            unit = Map::<dyn Any, StorageUnit<T>>::map(storage.storage()?, f);
+        }
         unit.one()?;
         let f: &dyn for<'r> Fn(&'r StorageUnit<T>) -> &'r T = &|x| x.one().unwrap();
         Ok(Map::<StorageUnit<T>, T>::map(unit, f))
@@ -63,10 +245,20 @@ where
         Borrowed<'a, U>: Waitable,
         MutBorrowed<'a, U>: Waitable,
     {
-        let unit = boxed.unit_get::<T>()?;
+        let storage = boxed.unit_get::<T>()?;
         let f: &dyn Fn(&dyn Any) -> &StorageUnit<T> =
             &|x| x.downcast_ref::<StorageUnit<T>>().unwrap();
-        let unit = Map::<dyn Any, StorageUnit<T>>::map(unit.waiting_storage(), f);
+        let mut unit = Map::<dyn Any, StorageUnit<T>>::map(storage.waiting_storage()?, f);
+        let factory = boxed
+            .factory::<T>()
+            .filter(|_| matches!(*unit, StorageUnit::Nope));
+        if factory.is_some() {
+            drop(unit);
+            let mut locked = storage.waiting_storage_mut()?;
+            construct(locked.downcast_mut().unwrap(), factory);
+            drop(locked);
+            unit = Map::<dyn Any, StorageUnit<T>>::map(storage.waiting_storage()?, f);
+        }
         unit.one()?;
         let f: &dyn for<'r> Fn(&'r StorageUnit<T>) -> &'r T = &|x| x.one().unwrap();
         Ok(Map::<StorageUnit<T>, T>::map(unit, f))
@@ -77,22 +269,39 @@ where
 impl<'a, T: Sized + Any + 'static, U: for<'b> Unit<'b> + ?Sized> Fetch<'a, U> for &mut T
 where
     MutBorrowed<'a, U>:
-        MapMut<(dyn Any), StorageUnit<T>, Func = dyn Fn(&mut dyn Any) -> &mut StorageUnit<T>>,
-    <MutBorrowed<'a, U> as MapMut<(dyn Any), StorageUnit<T>>>::Output:
+        MapMut<dyn Any, StorageUnit<T>, Func = dyn Fn(&mut dyn Any) -> &mut StorageUnit<T>>,
+    <MutBorrowed<'a, U> as MapMut<dyn Any, StorageUnit<T>>>::Output:
         MapMut<StorageUnit<T>, T, Func = dyn Fn(&mut StorageUnit<T>) -> &mut T>,
 {
-    type Output = <<MutBorrowed<'a, U> as MapMut<(dyn Any), StorageUnit<T>>>::Output as MapMut<
+    type Output = <<MutBorrowed<'a, U> as MapMut<dyn Any, StorageUnit<T>>>::Output as MapMut<
         StorageUnit<T>,
         T,
     >>::Output;
 
     type Actual = T;
+    type Guard = <MutBorrowed<'a, U> as MapMut<dyn Any, StorageUnit<T>>>::Output;
+    #[inline]
+    fn access(access: &mut Access) {
+        access.write::<T>();
+    }
+    #[inline]
+    fn can_get(boxed: &'a BlackBox<U>, taken: &mut Taken) -> DynamicResult<Self::Guard> {
+        let unit = boxed.unit_get::<T>()?;
+        let f: &dyn Fn(&mut dyn Any) -> &mut StorageUnit<T> =
+            &|x| x.downcast_mut::<StorageUnit<T>>().unwrap();
+        let unit = MapMut::<dyn Any, StorageUnit<T>>::map(unit.storage_mut()?, f);
+        taken
+            .shape(&unit)
+            .one(&unit, boxed.factory::<T>().is_some())?;
+        Ok(unit)
+    }
     #[inline]
     fn get(boxed: &'a BlackBox<U>) -> DynamicResult<Self::Output> {
         let unit = boxed.unit_get::<T>()?;
         let f: &dyn Fn(&mut dyn Any) -> &mut StorageUnit<T> =
             &|x| x.downcast_mut::<StorageUnit<T>>().unwrap();
         let mut unit = MapMut::<dyn Any, StorageUnit<T>>::map(unit.storage_mut()?, f);
+        construct(&mut unit, boxed.factory::<T>());
         unit.one_mut()?;
         let f: &dyn Fn(&mut StorageUnit<T>) -> &mut T = &|x| x.one_mut().unwrap();
         Ok(MapMut::<StorageUnit<T>, T>::map(unit, f))
@@ -106,7 +315,8 @@ where
         let unit = boxed.unit_get::<T>()?;
         let f: &dyn Fn(&mut dyn Any) -> &mut StorageUnit<T> =
             &|x| x.downcast_mut::<StorageUnit<T>>().unwrap();
-        let mut unit = MapMut::<dyn Any, StorageUnit<T>>::map(unit.waiting_storage_mut(), f);
+        let mut unit = MapMut::<dyn Any, StorageUnit<T>>::map(unit.waiting_storage_mut()?, f);
+        construct(&mut unit, boxed.factory::<T>());
         unit.one_mut()?;
         let f: &dyn Fn(&mut StorageUnit<T>) -> &mut T = &|x| x.one_mut().unwrap();
         Ok(MapMut::<StorageUnit<T>, T>::map(unit, f))
@@ -116,7 +326,7 @@ where
 //Slice immutable
 impl<'a, T: Sized + Any + 'static, U: for<'b> Unit<'b> + ?Sized> Fetch<'a, U> for &[T]
 where
-    Borrowed<'a, U>: Map<(dyn Any), StorageUnit<T>, Func = dyn Fn(&dyn Any) -> &StorageUnit<T>>,
+    Borrowed<'a, U>: Map<dyn Any, StorageUnit<T>, Func = dyn Fn(&dyn Any) -> &StorageUnit<T>>,
     <Borrowed<'a, U> as Map<dyn Any, StorageUnit<T>>>::Output:
         Map<StorageUnit<T>, [T], Func = dyn Fn(&StorageUnit<T>) -> &[T]>,
 {
@@ -126,6 +336,20 @@ where
     >>::Output;
 
     type Actual = T;
+    type Guard = <Borrowed<'a, U> as Map<dyn Any, StorageUnit<T>>>::Output;
+    #[inline]
+    fn access(access: &mut Access) {
+        access.read::<T>();
+    }
+    #[inline]
+    fn can_get(boxed: &'a BlackBox<U>, taken: &mut Taken) -> DynamicResult<Self::Guard> {
+        let unit = boxed.unit_get::<T>()?;
+        let f: &dyn Fn(&dyn Any) -> &StorageUnit<T> =
+            &|x| x.downcast_ref::<StorageUnit<T>>().unwrap();
+        let unit = Map::<dyn Any, StorageUnit<T>>::map(unit.storage()?, f);
+        taken.shape(&unit).many(&unit)?;
+        Ok(unit)
+    }
     #[inline]
     fn get(boxed: &'a BlackBox<U>) -> DynamicResult<Self::Output> {
         let unit = boxed.unit_get::<T>()?;
@@ -145,7 +369,7 @@ where
         let unit = boxed.unit_get::<T>()?;
         let f: &dyn Fn(&dyn Any) -> &StorageUnit<T> =
             &|x| x.downcast_ref::<StorageUnit<T>>().unwrap();
-        let unit = Map::<dyn Any, StorageUnit<T>>::map(unit.waiting_storage(), f);
+        let unit = Map::<dyn Any, StorageUnit<T>>::map(unit.waiting_storage()?, f);
         unit.many()?;
         let f: &dyn for<'r> Fn(&'r StorageUnit<T>) -> &'r [T] = &|x| x.many().unwrap();
         Ok(Map::<StorageUnit<T>, [T]>::map(unit, f))
@@ -156,24 +380,38 @@ where
 impl<'a, T: Sized + Any + 'static, U: for<'b> Unit<'b> + ?Sized> Fetch<'a, U> for &mut [T]
 where
     MutBorrowed<'a, U>:
-        MapMut<(dyn Any), StorageUnit<T>, Func = dyn Fn(&mut dyn Any) -> &mut StorageUnit<T>>,
-    <MutBorrowed<'a, U> as MapMut<(dyn Any), StorageUnit<T>>>::Output:
+        MapMut<dyn Any, StorageUnit<T>, Func = dyn Fn(&mut dyn Any) -> &mut StorageUnit<T>>,
+    <MutBorrowed<'a, U> as MapMut<dyn Any, StorageUnit<T>>>::Output:
         MapMut<StorageUnit<T>, [T], Func = dyn Fn(&mut StorageUnit<T>) -> &mut [T]>,
 {
-    type Output = <<MutBorrowed<'a, U> as MapMut<(dyn Any), StorageUnit<T>>>::Output as MapMut<
+    type Output = <<MutBorrowed<'a, U> as MapMut<dyn Any, StorageUnit<T>>>::Output as MapMut<
         StorageUnit<T>,
         [T],
     >>::Output;
 
     type Actual = T;
+    type Guard = <MutBorrowed<'a, U> as MapMut<dyn Any, StorageUnit<T>>>::Output;
+    #[inline]
+    fn access(access: &mut Access) {
+        access.write::<T>();
+    }
+    #[inline]
+    fn can_get(boxed: &'a BlackBox<U>, taken: &mut Taken) -> DynamicResult<Self::Guard> {
+        let unit = boxed.unit_get::<T>()?;
+        let f: &dyn Fn(&mut dyn Any) -> &mut StorageUnit<T> =
+            &|x| x.downcast_mut::<StorageUnit<T>>().unwrap();
+        let mut unit = MapMut::<dyn Any, StorageUnit<T>>::map(unit.storage_mut()?, f);
+        taken.shape(&unit).many_mut(&mut unit)?;
+        Ok(unit)
+    }
     #[inline]
     fn get(boxed: &'a BlackBox<U>) -> DynamicResult<Self::Output> {
         let unit = boxed.unit_get::<T>()?;
         let f: &dyn Fn(&mut dyn Any) -> &mut StorageUnit<T> =
             &|x| x.downcast_mut::<StorageUnit<T>>().unwrap();
         let mut unit = MapMut::<dyn Any, StorageUnit<T>>::map(unit.storage_mut()?, f);
-        unit.many_mut()?;
-        let f: &dyn Fn(&mut StorageUnit<T>) -> &mut [T] = &|x| x.many_mut().unwrap();
+        unit.slice_mut()?;
+        let f: &dyn Fn(&mut StorageUnit<T>) -> &mut [T] = &|x| x.slice_mut().unwrap();
         Ok(MapMut::<StorageUnit<T>, [T]>::map(unit, f))
     }
     #[inline]
@@ -185,9 +423,9 @@ where
         let unit = boxed.unit_get::<T>()?;
         let f: &dyn Fn(&mut dyn Any) -> &mut StorageUnit<T> =
             &|x| x.downcast_mut::<StorageUnit<T>>().unwrap();
-        let mut unit = MapMut::<dyn Any, StorageUnit<T>>::map(unit.waiting_storage_mut(), f);
-        unit.many_mut()?;
-        let f: &dyn Fn(&mut StorageUnit<T>) -> &mut [T] = &|x| x.many_mut().unwrap();
+        let mut unit = MapMut::<dyn Any, StorageUnit<T>>::map(unit.waiting_storage_mut()?, f);
+        unit.slice_mut()?;
+        let f: &dyn Fn(&mut StorageUnit<T>) -> &mut [T] = &|x| x.slice_mut().unwrap();
         Ok(MapMut::<StorageUnit<T>, [T]>::map(unit, f))
     }
 }
@@ -196,12 +434,26 @@ where
 impl<'a, T: Sized + Any + 'static, U: for<'b> Unit<'b> + ?Sized> Fetch<'a, U> for Box<T>
 where
     MutBorrowed<'a, U>:
-        MapMut<(dyn Any), StorageUnit<T>, Func = dyn Fn(&mut dyn Any) -> &mut StorageUnit<T>>,
+        MapMut<dyn Any, StorageUnit<T>, Func = dyn Fn(&mut dyn Any) -> &mut StorageUnit<T>>,
 {
     type Output = T;
 
     type Actual = T;
+    type Guard = ();
+    #[inline]
+    fn access(access: &mut Access) {
+        access.take::<T>();
+    }
 
+    #[inline]
+    fn can_get(boxed: &'a BlackBox<U>, taken: &mut Taken) -> DynamicResult<Self::Guard> {
+        let unit = boxed.unit_get::<T>()?;
+        let f: &dyn Fn(&mut dyn Any) -> &mut StorageUnit<T> =
+            &|x| x.downcast_mut::<StorageUnit<T>>().unwrap();
+        let unit = MapMut::<dyn Any, StorageUnit<T>>::map(unit.storage_mut()?, f);
+        taken.set::<T>(taken.shape(&unit).extract_one()?);
+        Ok(())
+    }
     #[inline]
     fn get(boxed: &'a BlackBox<U>) -> DynamicResult<Self::Output> {
         let unit = boxed.unit_get::<T>()?;
@@ -219,7 +471,7 @@ where
         let unit = boxed.unit_get::<T>()?;
         let f: &dyn Fn(&mut dyn Any) -> &mut StorageUnit<T> =
             &|x| x.downcast_mut::<StorageUnit<T>>().unwrap();
-        let mut unit = MapMut::<dyn Any, StorageUnit<T>>::map(unit.waiting_storage_mut(), f);
+        let mut unit = MapMut::<dyn Any, StorageUnit<T>>::map(unit.waiting_storage_mut()?, f);
         unit.extract_one()
     }
 }
@@ -228,11 +480,25 @@ where
 impl<'a, T: Sized + Any + 'static, U: for<'b> Unit<'b> + ?Sized> Fetch<'a, U> for Vec<T>
 where
     MutBorrowed<'a, U>:
-        MapMut<(dyn Any), StorageUnit<T>, Func = dyn Fn(&mut dyn Any) -> &mut StorageUnit<T>>,
+        MapMut<dyn Any, StorageUnit<T>, Func = dyn Fn(&mut dyn Any) -> &mut StorageUnit<T>>,
 {
     type Output = Vec<T>;
 
     type Actual = T;
+    type Guard = ();
+    #[inline]
+    fn access(access: &mut Access) {
+        access.take::<T>();
+    }
+    #[inline]
+    fn can_get(boxed: &'a BlackBox<U>, taken: &mut Taken) -> DynamicResult<Self::Guard> {
+        let unit = boxed.unit_get::<T>()?;
+        let f: &dyn Fn(&mut dyn Any) -> &mut StorageUnit<T> =
+            &|x| x.downcast_mut::<StorageUnit<T>>().unwrap();
+        let unit = MapMut::<dyn Any, StorageUnit<T>>::map(unit.storage_mut()?, f);
+        taken.set::<T>(taken.shape(&unit).extract_many()?);
+        Ok(())
+    }
     #[inline]
     fn get(boxed: &'a BlackBox<U>) -> DynamicResult<Self::Output> {
         let unit = boxed.unit_get::<T>()?;
@@ -250,14 +516,143 @@ where
         let unit = boxed.unit_get::<T>()?;
         let f: &dyn Fn(&mut dyn Any) -> &mut StorageUnit<T> =
             &|x| x.downcast_mut::<StorageUnit<T>>().unwrap();
-        let mut unit = MapMut::<dyn Any, StorageUnit<T>>::map(unit.waiting_storage_mut(), f);
+        let mut unit = MapMut::<dyn Any, StorageUnit<T>>::map(unit.waiting_storage_mut()?, f);
         unit.extract_many()
     }
 }
 
+///
+/// A [`Fetch`] kind which takes the oldest value of type `T` out of the
+/// storage, in constant time in the case that its unit was allocated using
+/// `allocate_queue`.
+///
+/// This returns `UnitError::IsNope` in the case that there are no values.
+///
+/// # Example
+/// ```
+/// # fn main() {
+/// use restor::{Drain, Pop, RwLockStorage};
+/// let mut mailbox = RwLockStorage::new();
+/// mailbox.allocate_queue::<String>();
+/// mailbox.insert(String::from("first")).unwrap();
+/// mailbox.insert_many(vec![String::from("second"), String::from("third")]).unwrap();
+/// assert_eq!(mailbox.get::<Pop<String>>().unwrap(), "first");
+/// assert_eq!(mailbox.get::<Drain<String>>().unwrap(), ["second", "third"]);
+/// assert!(mailbox.get::<Pop<String>>().is_err());
+/// # }
+/// ```
+///
+/// [`Fetch`]: ./trait.Fetch.html
+///
+pub struct Pop<T>(PhantomData<T>);
+
+///
+/// A [`Fetch`] kind which takes all of the values of type `T` out of the
+/// storage, oldest first. Unlike `Vec<T>`, this returns an empty `Vec`
+/// in the case that there are no values.
+///
+/// Please see [`Pop`] for an example.
+///
+/// [`Fetch`]: ./trait.Fetch.html
+/// [`Pop`]: ./struct.Pop.html
+///
+pub struct Drain<T>(PhantomData<T>);
+
+//Own oldest
+impl<'a, T: Sized + Any + 'static, U: for<'b> Unit<'b> + ?Sized> Fetch<'a, U> for Pop<T>
+where
+    MutBorrowed<'a, U>:
+        MapMut<dyn Any, StorageUnit<T>, Func = dyn Fn(&mut dyn Any) -> &mut StorageUnit<T>>,
+{
+    type Output = T;
+
+    type Actual = T;
+    type Guard = ();
+    #[inline]
+    fn access(access: &mut Access) {
+        access.take::<T>();
+    }
+
+    #[inline]
+    fn can_get(boxed: &'a BlackBox<U>, taken: &mut Taken) -> DynamicResult<Self::Guard> {
+        let unit = boxed.unit_get::<T>()?;
+        let f: &dyn Fn(&mut dyn Any) -> &mut StorageUnit<T> =
+            &|x| x.downcast_mut::<StorageUnit<T>>().unwrap();
+        let unit = MapMut::<dyn Any, StorageUnit<T>>::map(unit.storage_mut()?, f);
+        taken.set::<T>(taken.shape(&unit).pop()?);
+        Ok(())
+    }
+    #[inline]
+    fn get(boxed: &'a BlackBox<U>) -> DynamicResult<Self::Output> {
+        let unit = boxed.unit_get::<T>()?;
+        let f: &dyn Fn(&mut dyn Any) -> &mut StorageUnit<T> =
+            &|x| x.downcast_mut::<StorageUnit<T>>().unwrap();
+        let mut unit = MapMut::<dyn Any, StorageUnit<T>>::map(unit.storage_mut()?, f);
+        unit.pop()
+    }
+    #[inline]
+    fn waiting_get(boxed: &'a BlackBox<U>) -> DynamicResult<Self::Output>
+    where
+        Borrowed<'a, U>: Waitable,
+        MutBorrowed<'a, U>: Waitable,
+    {
+        let unit = boxed.unit_get::<T>()?;
+        let f: &dyn Fn(&mut dyn Any) -> &mut StorageUnit<T> =
+            &|x| x.downcast_mut::<StorageUnit<T>>().unwrap();
+        let mut unit = MapMut::<dyn Any, StorageUnit<T>>::map(unit.waiting_storage_mut()?, f);
+        unit.pop()
+    }
+}
+
+//Own all, oldest first
+impl<'a, T: Sized + Any + 'static, U: for<'b> Unit<'b> + ?Sized> Fetch<'a, U> for Drain<T>
+where
+    MutBorrowed<'a, U>:
+        MapMut<dyn Any, StorageUnit<T>, Func = dyn Fn(&mut dyn Any) -> &mut StorageUnit<T>>,
+{
+    type Output = Vec<T>;
+
+    type Actual = T;
+    type Guard = ();
+    #[inline]
+    fn access(access: &mut Access) {
+        access.take::<T>();
+    }
+
+    #[inline]
+    fn can_get(boxed: &'a BlackBox<U>, taken: &mut Taken) -> DynamicResult<Self::Guard> {
+        let unit = boxed.unit_get::<T>()?;
+        let f: &dyn Fn(&mut dyn Any) -> &mut StorageUnit<T> =
+            &|x| x.downcast_mut::<StorageUnit<T>>().unwrap();
+        let unit = MapMut::<dyn Any, StorageUnit<T>>::map(unit.storage_mut()?, f);
+        taken.set::<T>(taken.shape(&unit).drain());
+        Ok(())
+    }
+    #[inline]
+    fn get(boxed: &'a BlackBox<U>) -> DynamicResult<Self::Output> {
+        let unit = boxed.unit_get::<T>()?;
+        let f: &dyn Fn(&mut dyn Any) -> &mut StorageUnit<T> =
+            &|x| x.downcast_mut::<StorageUnit<T>>().unwrap();
+        let mut unit = MapMut::<dyn Any, StorageUnit<T>>::map(unit.storage_mut()?, f);
+        Ok(unit.drain())
+    }
+    #[inline]
+    fn waiting_get(boxed: &'a BlackBox<U>) -> DynamicResult<Self::Output>
+    where
+        Borrowed<'a, U>: Waitable,
+        MutBorrowed<'a, U>: Waitable,
+    {
+        let unit = boxed.unit_get::<T>()?;
+        let f: &dyn Fn(&mut dyn Any) -> &mut StorageUnit<T> =
+            &|x| x.downcast_mut::<StorageUnit<T>>().unwrap();
+        let mut unit = MapMut::<dyn Any, StorageUnit<T>>::map(unit.waiting_storage_mut()?, f);
+        Ok(unit.drain())
+    }
+}
+
 ///
 /// An abstraction over [`Fetch`] which works over multiple types, and the
-/// six types which have [`Fetch`] pre-implemented. This is therefore implemented
+/// types which have [`Fetch`] pre-implemented. This is therefore implemented
 /// for the following types:
 ///
 /// - `&T`
@@ -266,6 +661,9 @@ where
 /// - `&mut [T]`
 /// - `Box<T>`
 /// - `Vec<T>`
+/// - [`Pop<T>`](./struct.Pop.html)
+/// - [`Drain<T>`](./struct.Drain.html)
+/// - [`Snapshot<T>`](../struct.Snapshot.html)
 /// - `(A,)`
 /// - `(A, B)`
 /// - `(A, B, C)`
@@ -286,6 +684,15 @@ pub trait FetchMultiple<'a, U: ?Sized + Unit<'a>> {
     where
         Borrowed<'a, U>: Waitable,
         MutBorrowed<'a, U>: Waitable;
+    ///
+    /// Records the types which `Self` reads from and writes to in `access`.
+    ///
+    fn access(access: &mut Access);
+    ///
+    /// Checks whether `get_many` would currently succeed, without waiting on
+    /// any locks or taking anything out of the storage.
+    ///
+    fn can_get_many(boxed: &'a BlackBox<U>) -> DynamicResult<()>;
 }
 
 //TODO: Make this less atrocious
@@ -310,6 +717,14 @@ macro_rules! impl_single {
             where Borrowed<'a, U>: Waitable, MutBorrowed<'a, U>: Waitable {
                 <$first>::waiting_get(boxed)
             }
+            #[inline]
+            fn access(access: &mut Access) {
+                <$first as Fetch<'a, U>>::access(access)
+            }
+            #[inline]
+            fn can_get_many(boxed: &'a BlackBox<U>) -> DynamicResult<()> {
+                <$first>::can_get(boxed, &mut Taken::new()).map(drop)
+            }
         }
 
         impl_single!($(($typed, $map, ($($constraints)+))),*);
@@ -350,6 +765,16 @@ impl_single!(
         Vec<T>, MapMut, (
             MutBorrowed<'a, U>: MapMut<dyn Any, StorageUnit<T>, Func = dyn Fn(&mut dyn Any) -> &mut StorageUnit<T>>,
         )
+    ),
+    (
+        Pop<T>, MapMut, (
+            MutBorrowed<'a, U>: MapMut<dyn Any, StorageUnit<T>, Func = dyn Fn(&mut dyn Any) -> &mut StorageUnit<T>>,
+        )
+    ),
+    (
+        Drain<T>, MapMut, (
+            MutBorrowed<'a, U>: MapMut<dyn Any, StorageUnit<T>, Func = dyn Fn(&mut dyn Any) -> &mut StorageUnit<T>>,
+        )
     )
 );
 
@@ -375,6 +800,21 @@ macro_rules! impl_tuple {
             where Borrowed<'a, U>: Waitable, MutBorrowed<'a, U>: Waitable{
                 Ok(($first_type::waiting_get(boxed)?, $($typ::waiting_get(boxed)?),*))
             }
+            #[inline]
+            fn access(access: &mut Access) {
+                $first_type::access(access);
+                $($typ::access(access);)*
+            }
+            #[inline]
+            fn can_get_many(boxed: &'a BlackBox<U>) -> DynamicResult<()> {
+                // The locks are held until all of them have been checked.
+                let mut taken = Taken::new();
+                let _guards = (
+                    $first_type::can_get(boxed, &mut taken)?,
+                    $($typ::can_get(boxed, &mut taken)?),*
+                );
+                Ok(())
+            }
         }
 
         impl_tuple!($($typ),*);
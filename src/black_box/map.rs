@@ -1,6 +1,7 @@
+use core::cell::{Ref, RefMut};
+use core::ops::{Deref, DerefMut};
+#[cfg(feature = "parking_lot")]
 use parking_lot::{MappedMutexGuard, MappedRwLockReadGuard, MappedRwLockWriteGuard};
-use std::cell::{Ref, RefMut};
-use std::ops::{Deref, DerefMut};
 
 ///
 /// A trait forcing the implementor to implement a `map` function
@@ -21,6 +22,7 @@ impl<'a, I: 'static + ?Sized, O: 'static + ?Sized> Map<I, O> for Ref<'a, I> {
     }
 }
 
+#[cfg(feature = "parking_lot")]
 impl<'a, I: 'static + ?Sized, O: 'static + ?Sized> Map<I, O> for MappedRwLockReadGuard<'a, I> {
     type Output = MappedRwLockReadGuard<'a, O>;
     type Func = dyn for<'b> Fn(&'b I) -> &'b O;
@@ -48,6 +50,7 @@ impl<'a, I: 'static + ?Sized, O: 'static + ?Sized> MapMut<I, O> for RefMut<'a, I
     }
 }
 
+#[cfg(feature = "parking_lot")]
 impl<'a, I: 'static + ?Sized, O: 'static + ?Sized> MapMut<I, O> for MappedRwLockWriteGuard<'a, I> {
     type Output = MappedRwLockWriteGuard<'a, O>;
     type Func = dyn for<'b> Fn(&'b mut I) -> &'b mut O;
@@ -56,6 +59,7 @@ impl<'a, I: 'static + ?Sized, O: 'static + ?Sized> MapMut<I, O> for MappedRwLock
     }
 }
 
+#[cfg(feature = "parking_lot")]
 impl<'a, I: 'static + ?Sized, O: 'static + ?Sized> MapMut<I, O> for MappedMutexGuard<'a, I> {
     type Output = MappedMutexGuard<'a, O>;
     type Func = dyn for<'b> Fn(&'b mut I) -> &'b mut O;
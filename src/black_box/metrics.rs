@@ -0,0 +1,317 @@
+use super::{Access, BlackBox, DynamicResult, ErrorDesc, FetchMultiple, Unit};
+use core::any::TypeId;
+use std::fmt::Write;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+
+///
+/// How often a single unit was used, as recorded with the `metrics` feature.
+///
+/// A fetch counts towards every unit it uses, including ones it takes
+/// values out of. Fetches which fail, or have to wait, because of a borrow
+/// held elsewhere count towards the units which were borrowed at the time.
+///
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnitMetrics {
+    name: &'static str,
+    borrows: u64,
+    failed_borrows: u64,
+    waits: u64,
+    wait_time: Duration,
+    peak_len: usize,
+}
+
+impl UnitMetrics {
+    ///
+    /// The name of the type stored in the unit.
+    ///
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    ///
+    /// The number of successful fetches using the unit.
+    ///
+    pub fn borrows(&self) -> u64 {
+        self.borrows
+    }
+
+    ///
+    /// The number of fetches which failed because the unit was borrowed
+    /// incompatibly.
+    ///
+    pub fn failed_borrows(&self) -> u64 {
+        self.failed_borrows
+    }
+
+    ///
+    /// The number of waiting fetches which could not be made right away
+    /// because the unit was borrowed.
+    ///
+    pub fn waits(&self) -> u64 {
+        self.waits
+    }
+
+    ///
+    /// The total time spent in those waiting fetches.
+    ///
+    pub fn wait_time(&self) -> Duration {
+        self.wait_time
+    }
+
+    ///
+    /// The largest number of values seen in the unit, which is checked
+    /// after each insertion, unless the unit is already borrowed mutably
+    /// again, and when the metrics are collected.
+    ///
+    pub fn peak_len(&self) -> usize {
+        self.peak_len
+    }
+}
+
+///
+/// The [`UnitMetrics`] of each unit in a storage, sorted by name, as
+/// returned by `metrics`.
+///
+/// # Example
+/// ```
+/// # fn main() {
+/// use restor::{make_storage, RwLockStorage};
+/// let storage = make_storage!(RwLockStorage: usize);
+/// storage.insert_many(vec![0usize, 1, 2]).unwrap();
+/// let _held = storage.get::<&[usize]>().unwrap();
+/// assert!(storage.get::<&mut [usize]>().is_err());
+/// let metrics = storage.metrics();
+/// let usize = metrics.unit("usize").unwrap();
+/// assert_eq!((usize.borrows(), usize.failed_borrows()), (1, 1));
+/// assert_eq!(usize.peak_len(), 3);
+/// assert!(metrics
+///     .to_prometheus("world")
+///     .contains("restor_failed_borrows_total{storage=\"world\",unit=\"usize\"} 1\n"));
+/// # }
+/// ```
+///
+/// [`UnitMetrics`]: ./struct.UnitMetrics.html
+///
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Metrics {
+    units: Vec<UnitMetrics>,
+}
+
+impl Metrics {
+    ///
+    /// The metrics of each unit.
+    ///
+    pub fn units(&self) -> &[UnitMetrics] {
+        &self.units
+    }
+
+    ///
+    /// The metrics of the unit for the type called `name`, as given by
+    /// `std::any::type_name`.
+    ///
+    pub fn unit(&self, name: &str) -> Option<&UnitMetrics> {
+        self.units.iter().find(|x| x.name == name)
+    }
+
+    ///
+    /// Renders the metrics in the Prometheus text exposition format, with
+    /// each unit labelled by its type and by `storage`, which tells apart
+    /// the storages a program exposes.
+    ///
+    pub fn to_prometheus(&self, storage: &str) -> String {
+        let storage = escape(storage);
+        let mut text = String::new();
+        for (name, kind, help, value) in FAMILIES.iter() {
+            let _ = writeln!(text, "# HELP {} {}", name, help);
+            let _ = writeln!(text, "# TYPE {} {}", name, kind);
+            for unit in &self.units {
+                let _ = writeln!(
+                    text,
+                    "{}{{storage=\"{}\",unit=\"{}\"}} {}",
+                    name,
+                    storage,
+                    escape(unit.name),
+                    value(unit)
+                );
+            }
+        }
+        text
+    }
+}
+
+// The name, type and help text of each metric family, along with its value
+// for a unit.
+type Family = (
+    &'static str,
+    &'static str,
+    &'static str,
+    fn(&UnitMetrics) -> String,
+);
+
+const FAMILIES: [Family; 5] = [
+    (
+        "restor_borrows_total",
+        "counter",
+        "Successful fetches using a unit.",
+        |x| x.borrows.to_string(),
+    ),
+    (
+        "restor_failed_borrows_total",
+        "counter",
+        "Fetches which failed because a unit was borrowed incompatibly.",
+        |x| x.failed_borrows.to_string(),
+    ),
+    (
+        "restor_waits_total",
+        "counter",
+        "Waiting fetches which had to wait for a unit.",
+        |x| x.waits.to_string(),
+    ),
+    (
+        "restor_wait_seconds_total",
+        "counter",
+        "Time spent in waiting fetches which had to wait for a unit.",
+        |x| x.wait_time.as_secs_f64().to_string(),
+    ),
+    (
+        "restor_peak_elements",
+        "gauge",
+        "The largest number of values seen in a unit.",
+        |x| x.peak_len.to_string(),
+    ),
+];
+
+// Escapes a label value for the text exposition format.
+fn escape(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+///
+/// The counts behind the [`UnitMetrics`] of a unit.
+///
+#[derive(Default)]
+pub(crate) struct Counters {
+    borrows: AtomicU64,
+    failed_borrows: AtomicU64,
+    waits: AtomicU64,
+    wait_nanos: AtomicU64,
+    peak_len: AtomicUsize,
+}
+
+impl<U: ?Sized + for<'a> Unit<'a>> BlackBox<U> {
+    ///
+    /// Collects the [`Metrics`] of each unit, which are recorded with the
+    /// `metrics` feature.
+    ///
+    /// [`Metrics`]: ./struct.Metrics.html
+    ///
+    pub fn metrics(&self) -> Metrics {
+        let mut units = self
+            .info
+            .iter()
+            .map(|(id, info)| {
+                let counters = &info.counters;
+                self.observe_len(id);
+                UnitMetrics {
+                    name: info.name,
+                    borrows: counters.borrows.load(Ordering::Relaxed),
+                    failed_borrows: counters.failed_borrows.load(Ordering::Relaxed),
+                    waits: counters.waits.load(Ordering::Relaxed),
+                    wait_time: Duration::from_nanos(counters.wait_nanos.load(Ordering::Relaxed)),
+                    peak_len: counters.peak_len.load(Ordering::Relaxed),
+                }
+            })
+            .collect::<Vec<_>>();
+        units.sort_by_key(|x| x.name);
+        Metrics { units }
+    }
+
+    ///
+    /// Records the length of the unit for `T` after inserting into it.
+    ///
+    pub(crate) fn count_insert<T: 'static>(&self) {
+        self.observe_len(&TypeId::of::<T>());
+    }
+
+    // Checks the length of the unit for `id`, in the case that it can be
+    // read right now.
+    fn observe_len(&self, id: &TypeId) {
+        if let (Ok(state), Some(info)) = (self.unit_state(id), self.info.get(id)) {
+            info.counters
+                .peak_len
+                .fetch_max(state.len, Ordering::Relaxed);
+        }
+    }
+
+    ///
+    /// Runs `fetch`, which fetches `T`, and counts it towards the units it
+    /// uses. In the case that it `waits`, this first checks whether it will
+    /// have to, and if so, times it.
+    ///
+    pub(crate) fn count_fetch<'a, T: FetchMultiple<'a, U>, R>(
+        &'a self,
+        waits: bool,
+        fetch: impl FnOnce() -> DynamicResult<R>,
+    ) -> DynamicResult<R> {
+        let mut access = Access::new();
+        T::access(&mut access);
+        let ids = access.used();
+        let counters = |ids: &[TypeId]| {
+            ids.iter()
+                .filter_map(|id| self.info.get(id))
+                .map(|x| &x.counters)
+                .collect::<Vec<_>>()
+        };
+
+        let waited = waits && matches!(T::can_get_many(self), Err(ErrorDesc::BorrowedIncompatibly));
+        let contended = if waited {
+            self.borrowed(&ids)
+        } else {
+            Vec::new()
+        };
+        let start = Instant::now();
+        let result = fetch();
+        if waited {
+            let nanos = start.elapsed().as_nanos() as u64;
+            for x in counters(&contended) {
+                x.waits.fetch_add(1, Ordering::Relaxed);
+                x.wait_nanos.fetch_add(nanos, Ordering::Relaxed);
+            }
+        }
+
+        match &result {
+            Ok(_) => {
+                for x in counters(&ids) {
+                    x.borrows.fetch_add(1, Ordering::Relaxed);
+                }
+            }
+            Err(ErrorDesc::BorrowedIncompatibly) => {
+                for x in counters(&self.borrowed(&ids)) {
+                    x.failed_borrows.fetch_add(1, Ordering::Relaxed);
+                }
+            }
+            Err(_) => {}
+        }
+        result
+    }
+
+    // The units out of `ids` which are borrowed right now, or all of them in
+    // the case that none are anymore, such as when a fetch conflicts with
+    // itself.
+    fn borrowed(&self, ids: &[TypeId]) -> Vec<TypeId> {
+        let borrowed = ids
+            .iter()
+            .copied()
+            .filter(|id| self.data.get(id).is_some_and(|x| x.storage_mut().is_err()))
+            .collect::<Vec<_>>();
+        if borrowed.is_empty() {
+            ids.to_vec()
+        } else {
+            borrowed
+        }
+    }
+}
@@ -1,21 +1,63 @@
-use std::any::{Any, TypeId};
-use std::collections::HashMap;
+#[cfg(not(feature = "std"))]
+use alloc::collections::{btree_map::Entry as MapEntry, BTreeMap};
+use alloc::{boxed::Box, sync::Arc, vec::Vec};
+#[cfg(feature = "tracing")]
+use core::any::type_name;
+use core::any::{Any, TypeId};
+use core::fmt;
+#[cfg(feature = "std")]
+use std::collections::{hash_map::Entry as MapEntry, HashMap};
+#[cfg(feature = "diagnostics")]
+use std::panic::Location;
+#[cfg(feature = "tracing")]
+use tracing::debug_span;
 
+mod access;
+mod bounded;
+mod construct;
+#[cfg(feature = "diagnostics")]
+mod diagnostics;
+mod entry;
 mod errors;
+#[cfg(feature = "std")]
 mod hasher;
+mod info;
 mod many;
 mod map;
+#[cfg(feature = "metrics")]
+mod metrics;
+#[cfg(feature = "rayon")]
+mod parallel;
 mod refcell_unit;
+mod signal;
 mod storageunit;
+#[cfg(feature = "tracing")]
+mod trace;
 mod unit;
+mod value_set;
 
+pub use access::Access;
+pub use bounded::BoundPolicy;
+pub use construct::{Dependencies, FromStorage};
+#[cfg(feature = "diagnostics")]
+pub use diagnostics::BorrowSite;
+pub use entry::Entry;
 pub use errors::{DynamicResult, ErrorDesc, UnitError};
+#[cfg(feature = "std")]
 use hasher::PassthroughHasherBuilder;
-pub use many::{Fetch, FetchMultiple};
+use info::{
+    append_storage_unit, clone_storage_unit, fmt_storage_unit, new_kind_of, swap_storage_unit,
+    DebugContents, Factory, UnitInfo, UnitState,
+};
+pub use many::{Drain, Fetch, FetchMultiple, Pop, Taken};
 pub use map::{Map, MapMut};
+#[cfg(feature = "metrics")]
+pub use metrics::{Metrics, UnitMetrics};
 pub use refcell_unit::{DynamicStorage, RefCellUnit};
-pub use storageunit::StorageUnit;
+pub use signal::Signal;
+pub use storageunit::{Order, StorageUnit};
 pub use unit::{Unit, Waitable};
+pub use value_set::ValueSet;
 
 ///
 /// The base structure for this library, contains all of the
@@ -26,17 +68,17 @@ pub use unit::{Unit, Waitable};
 /// with the type alias at the root of this library:
 ///
 /// * `DynamicStorage`:
-/// Based on `RefCell`s, for its interior mutability.
-/// This is _NOT_ `Send`, but it is faster, because it
-/// does not use atomic operations.
+///   Based on `RefCell`s, for its interior mutability.
+///   This is _NOT_ `Send`, but it is faster, because it
+///   does not use atomic operations.
 /// * `MutexStorage`:
-/// Uses a `Mutex` for `Send` capabilities, and interior mutability
-/// This only exposes mutable getter methods, as there is only
-/// a `&mut` api available for a `MappedMutexGuard`
+///   Uses a `Mutex` for `Send` capabilities, and interior mutability
+///   This only exposes mutable getter methods, as there is only
+///   a `&mut` api available for a `MappedMutexGuard`
 /// * `RwLockStorage`:
-/// This exposes the same api as a `RefCell` but is atomically guarded
-/// and therefore guarantees a safe `Send`, while allowing multiple
-/// readers.
+///   This exposes the same api as a `RefCell` but is atomically guarded
+///   and therefore guarantees a safe `Send`, while allowing multiple
+///   readers.
 ///
 /// The type parameter `U` is the `Unit` that is going to be used to store
 /// the data that is placed into it. This type parameter should, once
@@ -45,7 +87,38 @@ pub use unit::{Unit, Waitable};
 ///
 #[derive(Default)]
 pub struct BlackBox<U: ?Sized> {
-    pub(crate) data: HashMap<TypeId, Box<U>, PassthroughHasherBuilder>,
+    pub(crate) data: TypeMap<Box<U>>,
+    pub(crate) info: TypeMap<UnitInfo<U>>,
+}
+
+// The map from the `TypeId` of a unit to its data, which is a `HashMap` with
+// a passthrough hasher when `std` is available, and a `BTreeMap` otherwise.
+#[cfg(feature = "std")]
+pub(crate) type TypeMap<V> = HashMap<TypeId, V, PassthroughHasherBuilder>;
+#[cfg(not(feature = "std"))]
+pub(crate) type TypeMap<V> = BTreeMap<TypeId, V>;
+
+// The names of the types whose units an operation failed for, alongside the
+// reason why.
+pub(crate) type Failures = Vec<(&'static str, ErrorDesc)>;
+
+///
+/// Implemented for each of the storage types provided by `restor`,
+/// exposing the [`BlackBox`](./struct.BlackBox.html) they wrap.
+///
+/// This is what allows operations between two different kinds of
+/// storages, such as cloning the contents of a `DynamicStorage` into
+/// a `RwLockStorage`.
+///
+pub trait Storage {
+    ///
+    /// The type erased `Unit` which the storage is made up of.
+    ///
+    type Unit: ?Sized + for<'a> Unit<'a>;
+    ///
+    /// Returns a reference to the internal `BlackBox`.
+    ///
+    fn black_box(&self) -> &BlackBox<Self::Unit>;
 }
 
 pub(crate) type Borrowed<'a, T> = <T as Unit<'a>>::Borrowed;
@@ -57,10 +130,410 @@ impl<U: ?Sized + for<'a> Unit<'a>> BlackBox<U> {
     ///
     pub fn new() -> Self {
         Self {
-            data: HashMap::with_hasher(PassthroughHasherBuilder),
+            data: TypeMap::default(),
+            info: TypeMap::default(),
+        }
+    }
+
+    ///
+    /// Internal function. Allocates a unit for `T` using `new_unit` in the
+    /// case that there isn't one already.
+    ///
+    #[doc(hidden)]
+    pub fn allocate<T: 'static>(&mut self, new_unit: fn() -> Box<U>) {
+        self.allocate_with::<T>(new_unit, append_storage_unit::<T, U>);
+    }
+
+    ///
+    /// Internal function. Allocates a unit for `T` using `new_unit` in the
+    /// case that there isn't one already, and replaces its contents with
+    /// `unit`, such as a `StorageUnit::Bounded` with a given capacity.
+    ///
+    /// `unit` cannot be captured by `new_unit`, so it is only moved into
+    /// the unit after it is created. Its kind is recorded alongside, so that
+    /// units created later on, such as when cloning the storage, are empty
+    /// units of the same kind.
+    ///
+    #[doc(hidden)]
+    pub fn allocate_unit<T: 'static>(&mut self, new_unit: fn() -> Box<U>, unit: StorageUnit<T>) {
+        if !self.create_unit::<T>(new_unit, append_storage_unit::<T, U>) {
+            return;
+        }
+        self.info.get_mut(&TypeId::of::<T>()).unwrap().kind = Some(new_kind_of(&unit));
+        // The unit was just created, so it cannot be borrowed.
+        *self.data[&TypeId::of::<T>()]
+            .storage_mut()
+            .unwrap_or_else(|_| unreachable!())
+            .downcast_mut::<StorageUnit<T>>()
+            .unwrap() = unit;
+        #[cfg(feature = "tracing")]
+        self.trace_allocate::<T>();
+    }
+
+    ///
+    /// Internal function. Allocates a unit for `T` using `new_unit` in the
+    /// case that there isn't one already, using `append` to move values
+    /// into it when merging storages.
+    ///
+    /// This is used for units which do not hold a `StorageUnit<T>` that
+    /// can be borrowed.
+    ///
+    pub(crate) fn allocate_with<T: 'static>(
+        &mut self,
+        new_unit: fn() -> Box<U>,
+        append: fn(&U, &U) -> DynamicResult<()>,
+    ) {
+        if self.create_unit::<T>(new_unit, append) {
+            #[cfg(feature = "tracing")]
+            self.trace_allocate::<T>();
+        }
+    }
+
+    // Adds the unit for `T` in the case that there isn't one already,
+    // returning whether it did.
+    fn create_unit<T: 'static>(
+        &mut self,
+        new_unit: fn() -> Box<U>,
+        append: fn(&U, &U) -> DynamicResult<()>,
+    ) -> bool {
+        let id = TypeId::of::<T>();
+        match self.data.entry(id) {
+            MapEntry::Vacant(entry) => {
+                entry.insert(new_unit());
+                self.info.insert(id, UnitInfo::new::<T>(new_unit, append));
+                true
+            }
+            MapEntry::Occupied(_) => false,
+        }
+    }
+
+    ///
+    /// Internal function. Registers `T` to have its contents shown when
+    /// the storage is formatted using `{:?}`.
+    ///
+    #[doc(hidden)]
+    pub fn register_debug<T: fmt::Debug + 'static>(&mut self) {
+        if let Some(info) = self.info.get_mut(&TypeId::of::<T>()) {
+            info.debug = Some(fmt_storage_unit::<T>);
+        }
+    }
+
+    ///
+    /// Internal function. Registers `T` to be cloned when cloning the
+    /// storage.
+    ///
+    #[doc(hidden)]
+    pub fn register_clone<T: Clone + 'static>(&mut self) {
+        if let Some(info) = self.info.get_mut(&TypeId::of::<T>()) {
+            info.clone = Some(clone_storage_unit::<T>);
+        }
+    }
+
+    ///
+    /// Internal function. Registers `T` to have its buffers swapped by
+    /// `swap_event_buffers`.
+    ///
+    #[doc(hidden)]
+    pub fn register_events<T: 'static>(&mut self) {
+        if let Some(info) = self.info.get_mut(&TypeId::of::<T>()) {
+            info.swap_events = Some(swap_storage_unit::<T>);
+        }
+    }
+
+    ///
+    /// Internal function. Registers `factory` to construct the value of `T`
+    /// the first time it is borrowed while its unit is empty.
+    ///
+    #[doc(hidden)]
+    pub fn register_factory<T: 'static>(
+        &mut self,
+        factory: impl Fn() -> T + Send + Sync + 'static,
+    ) {
+        if let Some(info) = self.info.get_mut(&TypeId::of::<T>()) {
+            let factory: Factory<T> = Box::new(factory);
+            info.factory = Some(Arc::new(factory));
+        }
+    }
+
+    ///
+    /// Returns the factory registered for `T`, in the case that there is one.
+    ///
+    pub(crate) fn factory<T: 'static>(&self) -> Option<&Factory<T>> {
+        self.info
+            .get(&TypeId::of::<T>())?
+            .factory
+            .as_ref()?
+            .downcast_ref::<Factory<T>>()
+    }
+
+    ///
+    /// Internal function. Formats the contents of every unit registered
+    /// with `register_debug` as a struct called `name`.
+    ///
+    /// Units which are currently borrowed incompatibly are shown as
+    /// `<borrowed>` instead of being waited on, and the presence of units
+    /// which were not registered is shown with a trailing `..`.
+    ///
+    #[doc(hidden)]
+    pub fn fmt_units(&self, name: &str, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut entries = self
+            .info
+            .iter()
+            .filter_map(|(id, info)| info.debug.map(|debug| (info.name, debug, &self.data[id])))
+            .collect::<Vec<_>>();
+        entries.sort_by_key(|&(name, _, _)| name);
+        let mut out = f.debug_struct(name);
+        for &(name, debug, unit) in entries.iter() {
+            match unit.storage() {
+                Ok(storage) => out.field(name, &DebugContents(&*storage, debug)),
+                Err(_) => out.field(name, &format_args!("<borrowed>")),
+            };
+        }
+        if entries.len() < self.data.len() {
+            out.finish_non_exhaustive()
+        } else {
+            out.finish()
+        }
+    }
+
+    ///
+    /// Internal function. Creates a new `BlackBox` with the same units
+    /// allocated, cloning the contents of each unit registered with
+    /// `register_clone`.
+    ///
+    /// Returns the names of the types whose contents could not be cloned
+    /// alongside the reason why, sorted by name.
+    ///
+    #[doc(hidden)]
+    pub fn try_clone(&self) -> (Self, Vec<(&'static str, ErrorDesc)>) {
+        let mut cloned = Self::new();
+        let mut failed = Vec::new();
+        for (id, unit) in self.data.iter() {
+            let info = &self.info[id];
+            let new_unit = info.create_unit();
+            let result = match info.clone {
+                Some(clone) => unit.storage().and_then(|source| {
+                    let mut dest = new_unit.storage_mut()?;
+                    clone(&*source, &mut *dest);
+                    Ok(())
+                }),
+                None => Err(ErrorDesc::NotCloneable),
+            };
+            if let Err(e) = result {
+                failed.push((info.name, e));
+            }
+            cloned.data.insert(*id, new_unit);
+            cloned.info.insert(*id, info.clone());
+        }
+        failed.sort_by_key(|&(name, _)| name);
+        (cloned, failed)
+    }
+
+    ///
+    /// Replaces the contents of the units in `other` with clones of the
+    /// contents of the units in `self`. This works between different kinds
+    /// of storages, as long as `other` has a unit allocated for every type
+    /// which is to be cloned.
+    ///
+    /// Only the units of types which were allocated using `allocate_for_clone`
+    /// are cloned. In the case that any unit could not be cloned, the names
+    /// of the types alongside the reason are returned, sorted by name. The
+    /// remaining units are cloned regardless.
+    ///
+    /// # Example
+    /// ```
+    /// # fn main() {
+    /// use restor::{make_storage, DynamicStorage, ErrorDesc, RwLockStorage};
+    /// let mut storage = DynamicStorage::new();
+    /// storage.allocate_for_clone::<usize>();
+    /// storage.allocate_for::<String>();
+    /// storage.insert_many(vec![1usize, 2, 3]).unwrap();
+    ///
+    /// let other = make_storage!(RwLockStorage: usize, String);
+    /// other.insert(4usize).unwrap();
+    /// let failed = storage.try_clone_into(&other).unwrap_err();
+    /// assert_eq!(failed, vec![("alloc::string::String", ErrorDesc::NotCloneable)]);
+    /// assert_eq!(&*other.get::<&[usize]>().unwrap(), &[1, 2, 3]);
+    /// # }
+    /// ```
+    ///
+    pub fn try_clone_into<V: ?Sized + for<'a> Unit<'a>>(
+        &self,
+        other: &BlackBox<V>,
+    ) -> Result<(), Vec<(&'static str, ErrorDesc)>> {
+        let mut failed = Vec::new();
+        for (id, unit) in self.data.iter() {
+            let info = &self.info[id];
+            let result = match (info.clone, other.data.get(id)) {
+                (None, _) => Err(ErrorDesc::NotCloneable),
+                (Some(_), None) => Err(ErrorDesc::NoAllocatedUnit),
+                (Some(clone), Some(other_unit)) => unit.storage().and_then(|source| {
+                    let mut dest = other_unit.storage_mut()?;
+                    clone(&*source, &mut *dest);
+                    Ok(())
+                }),
+            };
+            if let Err(e) = result {
+                failed.push((info.name, e));
+            }
+        }
+        if failed.is_empty() {
+            Ok(())
+        } else {
+            failed.sort_by_key(|&(name, _)| name);
+            Err(failed)
         }
     }
 
+    ///
+    /// Internal function. Moves every unit in `other` into `self`. In the
+    /// case that both have a unit allocated for a type, the values in the
+    /// unit from `other` are appended to the one in `self`.
+    ///
+    /// In the case that the values of any unit could not all be moved,
+    /// such as when the unit in `self` is a full bounded unit, those units
+    /// are returned holding the values which were not moved, alongside the
+    /// names of their types and the reason why, sorted by name.
+    ///
+    #[doc(hidden)]
+    pub fn merge_from(&mut self, other: Self) -> Result<(), (Self, Failures)> {
+        let BlackBox {
+            data,
+            info: mut other_info,
+        } = other;
+        let mut rest = Self::new();
+        let mut failed = Vec::new();
+        for (id, unit) in data {
+            let unit_info = other_info.remove(&id).unwrap();
+            match self.data.entry(id) {
+                MapEntry::Occupied(existing) => {
+                    // Neither can be borrowed, as we have exclusive access to both,
+                    // so the first append only fails as unsupported when the kinds
+                    // of the units differ, in which case the unit of the other kind
+                    // knows how to merge. Any other error is kept as is.
+                    let info = self.info.get_mut(&id).unwrap();
+                    let result = (info.append)(&*unit, &**existing.get()).or_else(|e| {
+                        if e == ErrorDesc::Unit(UnitError::Unsupported) {
+                            (unit_info.append)(&*unit, &**existing.get())
+                        } else {
+                            Err(e)
+                        }
+                    });
+                    info.debug = info.debug.or(unit_info.debug);
+                    info.clone = info.clone.or(unit_info.clone);
+                    info.swap_events = info.swap_events.or(unit_info.swap_events);
+                    if let Err(e) = result {
+                        failed.push((unit_info.name, e));
+                        rest.data.insert(id, unit);
+                        rest.info.insert(id, unit_info);
+                    }
+                }
+                MapEntry::Vacant(entry) => {
+                    entry.insert(unit);
+                    self.info.insert(id, unit_info);
+                }
+            }
+        }
+        if failed.is_empty() {
+            Ok(())
+        } else {
+            failed.sort_by_key(|&(name, _)| name);
+            Err((rest, failed))
+        }
+    }
+
+    ///
+    /// Moves all of the values of type `T` in `self` to the end of the unit
+    /// allocated for `T` in `to`, which may be a different kind of storage.
+    ///
+    /// This returns an error in the case that either storage lacks a unit
+    /// for `T`, or either unit cannot be borrowed mutably. In that case no
+    /// values are moved.
+    ///
+    /// In the case that the unit in `to` cannot hold all of the values, such
+    /// as when it is a bounded unit which rejects values, the values which
+    /// fit are moved, and the rest are left in `self` while this returns
+    /// `UnitError::Full`.
+    ///
+    /// # Example
+    /// ```
+    /// # fn main() {
+    /// use restor::{make_storage, DynamicStorage, RwLockStorage};
+    /// let loaded = make_storage!(DynamicStorage: String);
+    /// loaded.insert_many(vec![String::from("a"), String::from("b")]).unwrap();
+    ///
+    /// let shared = make_storage!(RwLockStorage: String);
+    /// shared.insert(String::from("z")).unwrap();
+    /// loaded.transfer::<String, _>(&shared).unwrap();
+    /// assert_eq!(&*shared.get::<&[String]>().unwrap(), &["z", "a", "b"]);
+    /// assert!(loaded.get::<&[String]>().is_err());
+    /// # }
+    /// ```
+    ///
+    pub fn transfer<T: 'static, V: ?Sized + for<'a> Unit<'a>>(
+        &self,
+        to: &BlackBox<V>,
+    ) -> DynamicResult<()> {
+        let source = self.unit_get::<T>()?;
+        let dest = to.unit_get::<T>()?;
+        let mut source_storage = source.storage_mut()?;
+        let mut dest_storage = dest.storage_mut()?;
+        let result = dest_storage
+            .downcast_mut::<StorageUnit<T>>()
+            .unwrap()
+            .append(source_storage.downcast_mut::<StorageUnit<T>>().unwrap());
+        drop(dest_storage);
+        if let Some(signal) = dest.signal() {
+            signal.notify();
+        }
+        result
+    }
+
+    ///
+    /// Swaps the buffers of every unit allocated using `allocate_events`,
+    /// making the events inserted since the last swap visible, and dropping
+    /// the ones which were visible until now.
+    ///
+    /// This is meant to be called once per frame, so that an event can be
+    /// read during the frame after the one it was sent in. This returns an
+    /// error in the case that any of the units cannot be borrowed mutably,
+    /// in which case none of the buffers are swapped.
+    ///
+    /// # Example
+    /// ```
+    /// # fn main() {
+    /// use restor::DynamicStorage;
+    /// let mut storage = DynamicStorage::new();
+    /// storage.allocate_events::<&'static str>();
+    /// storage.insert("clicked").unwrap();
+    /// assert!(storage.get::<&[&str]>().unwrap().is_empty());
+    /// storage.swap_event_buffers().unwrap();
+    /// assert_eq!(&*storage.get::<&[&str]>().unwrap(), &["clicked"]);
+    /// storage.swap_event_buffers().unwrap();
+    /// assert!(storage.get::<&[&str]>().unwrap().is_empty());
+    /// # }
+    /// ```
+    ///
+    pub fn swap_event_buffers(&self) -> DynamicResult<()> {
+        let mut units = Vec::new();
+        for (id, info) in self.info.iter() {
+            if let Some(swap) = info.swap_events {
+                let unit = &self.data[id];
+                units.push((unit, swap, unit.storage_mut()?));
+            }
+        }
+        for (_, swap, storage) in units.iter_mut() {
+            swap(&mut **storage);
+        }
+        for (unit, _, storage) in units {
+            drop(storage);
+            if let Some(signal) = unit.signal() {
+                signal.notify();
+            }
+        }
+        Ok(())
+    }
+
     ///
     /// Checks if there is an allocated unit for
     /// the type parameter in the internal hashmap.
@@ -119,13 +592,44 @@ impl<U: ?Sized + for<'a> Unit<'a>> BlackBox<U> {
     ///
     pub fn insert<T: 'static>(&self, data: T) -> Result<(), (T, ErrorDesc)> {
         let entry = self.data.get(&TypeId::of::<T>());
-        match entry {
+        let result = match entry {
             Some(x) => match x.insert_any(Box::new(data)) {
                 Some((x, e)) => Err((*x.downcast().unwrap(), e)),
-                None => Ok(()),
+                None => {
+                    #[cfg(feature = "metrics")]
+                    self.count_insert::<T>();
+                    Ok(())
+                }
             },
             None => Err((data, ErrorDesc::NoAllocatedUnit)),
+        };
+        #[cfg(feature = "tracing")]
+        self.trace_insert::<T>(1, result.as_ref().err().map(|(_, e)| e));
+        result
+    }
+
+    // Inserts `data` in the case that the unit for `T` holds no values,
+    // failing with `UnitError::AlreadyPresent` otherwise. The unit is only
+    // locked once for both, so that nothing can be inserted in between.
+    pub(crate) fn insert_into_empty<T: 'static>(&self, data: T) -> DynamicResult<()> {
+        let unit = self.unit_get::<T>()?;
+        let result = unit.storage_mut().and_then(|mut storage| {
+            let storage = storage.downcast_mut::<StorageUnit<T>>().unwrap();
+            if !storage.is_empty() {
+                return Err(ErrorDesc::Unit(UnitError::AlreadyPresent));
+            }
+            storage.try_insert(data).map_err(|(_, e)| e)
+        });
+        if result.is_ok() {
+            if let Some(signal) = unit.signal() {
+                signal.notify();
+            }
+            #[cfg(feature = "metrics")]
+            self.count_insert::<T>();
         }
+        #[cfg(feature = "tracing")]
+        self.trace_insert::<T>(1, result.as_ref().err());
+        result
     }
 
     ///
@@ -140,14 +644,24 @@ impl<U: ?Sized + for<'a> Unit<'a>> BlackBox<U> {
         Borrowed<'a, U>: Waitable,
         MutBorrowed<'a, U>: Waitable,
     {
+        #[cfg(feature = "tracing")]
+        let _span =
+            debug_span!(target: "restor", "waiting_insert", unit = type_name::<T>()).entered();
         let entry = self.data.get(&TypeId::of::<T>());
-        match entry {
+        let result = match entry {
             Some(x) => match x.waiting_insert(Box::new(data)) {
                 Some((x, e)) => Err((*x.downcast().unwrap(), e)),
-                None => Ok(()),
+                None => {
+                    #[cfg(feature = "metrics")]
+                    self.count_insert::<T>();
+                    Ok(())
+                }
             },
             None => Err((data, ErrorDesc::NoAllocatedUnit)),
-        }
+        };
+        #[cfg(feature = "tracing")]
+        self.trace_insert::<T>(1, result.as_ref().err().map(|(_, e)| e));
+        result
     }
 
     ///
@@ -175,14 +689,23 @@ impl<U: ?Sized + for<'a> Unit<'a>> BlackBox<U> {
     /// at inserting into the storage.
     ///
     pub fn insert_many<T: 'static>(&self, data: Vec<T>) -> Result<(), (Vec<T>, ErrorDesc)> {
+        #[cfg(feature = "tracing")]
+        let count = data.len();
         let entry = self.data.get(&TypeId::of::<T>());
-        match entry {
+        let result = match entry {
             Some(x) => match x.insert_any(Box::new(data)) {
                 Some((x, e)) => Err((*x.downcast().unwrap(), e)),
-                None => Ok(()),
+                None => {
+                    #[cfg(feature = "metrics")]
+                    self.count_insert::<T>();
+                    Ok(())
+                }
             },
             None => Err((data, ErrorDesc::NoAllocatedUnit)),
-        }
+        };
+        #[cfg(feature = "tracing")]
+        self.trace_insert::<T>(count, result.as_ref().err().map(|(_, e)| e));
+        result
     }
 
     ///
@@ -206,14 +729,37 @@ impl<U: ?Sized + for<'a> Unit<'a>> BlackBox<U> {
         Borrowed<'a, U>: Waitable,
         MutBorrowed<'a, U>: Waitable,
     {
+        #[cfg(feature = "tracing")]
+        let _span =
+            debug_span!(target: "restor", "waiting_insert", unit = type_name::<T>()).entered();
+        #[cfg(feature = "tracing")]
+        let count = data.len();
         let entry = self.data.get(&TypeId::of::<T>());
-        match entry {
+        let result = match entry {
             Some(x) => match x.waiting_insert(Box::new(data)) {
                 Some((x, e)) => Err((*x.downcast().unwrap(), e)),
-                None => Ok(()),
+                None => {
+                    #[cfg(feature = "metrics")]
+                    self.count_insert::<T>();
+                    Ok(())
+                }
             },
             None => Err((data, ErrorDesc::NoAllocatedUnit)),
-        }
+        };
+        #[cfg(feature = "tracing")]
+        self.trace_insert::<T>(count, result.as_ref().err().map(|(_, e)| e));
+        result
+    }
+
+    ///
+    /// Reads the state of the unit for `id`, which fails in the case that it
+    /// is borrowed mutably, or cannot be borrowed at all.
+    ///
+    pub(crate) fn unit_state(&self, id: &TypeId) -> DynamicResult<UnitState> {
+        let unit = self.data.get(id).ok_or(ErrorDesc::NoAllocatedUnit)?;
+        let state = self.info[id].state;
+        let storage = unit.storage()?;
+        Ok(state(&*storage))
     }
 
     ///
@@ -323,8 +869,11 @@ impl<U: ?Sized + for<'a> Unit<'a>> BlackBox<U> {
         Borrowed<'b, U>:
             Map<dyn Any, StorageUnit<T>, Func = dyn Fn(&dyn Any) -> &StorageUnit<T>> + Waitable,
     {
+        #[cfg(feature = "tracing")]
+        let _span =
+            debug_span!(target: "restor", "waiting_run_for", unit = type_name::<T>()).entered();
         let unit = self.unit_get::<T>()?;
-        let dynstorage = unit.waiting_storage();
+        let dynstorage = unit.waiting_storage()?;
         let conv_func: &dyn for<'r> Fn(&'r dyn Any) -> &'r StorageUnit<T> =
             &|x| x.downcast_ref::<StorageUnit<T>>().unwrap();
         let storage = Map::map(dynstorage, conv_func);
@@ -418,12 +967,14 @@ impl<U: ?Sized + for<'a> Unit<'a>> BlackBox<U> {
         mut f: F,
     ) -> DynamicResult<D>
     where
-        MutBorrowed<'b, U>:
-            MapMut<dyn Any, StorageUnit<T>, Func = dyn Fn(&mut dyn Any) -> &mut StorageUnit<T>>
-                + Waitable,
+        MutBorrowed<'b, U>: MapMut<dyn Any, StorageUnit<T>, Func = dyn Fn(&mut dyn Any) -> &mut StorageUnit<T>>
+            + Waitable,
     {
+        #[cfg(feature = "tracing")]
+        let _span =
+            debug_span!(target: "restor", "waiting_run_for", unit = type_name::<T>()).entered();
         let unit = self.unit_get::<T>()?;
-        let dynstorage = unit.waiting_storage_mut();
+        let dynstorage = unit.waiting_storage_mut()?;
         let conv_func: &dyn for<'r> Fn(&'r mut dyn Any) -> &'r mut StorageUnit<T> =
             &|x: &mut dyn Any| x.downcast_mut::<StorageUnit<T>>().unwrap();
         let mut storage = MapMut::map(dynstorage, conv_func);
@@ -526,8 +1077,23 @@ impl<U: ?Sized + for<'a> Unit<'a>> BlackBox<U> {
     /// ```
     ///
     #[inline(always)]
+    #[track_caller]
     pub fn get<'a, T: FetchMultiple<'a, U>>(&'a self) -> DynamicResult<T::Output> {
-        T::get_many(self)
+        #[cfg(feature = "diagnostics")]
+        let location = Location::caller();
+        let fetch = || {
+            #[cfg(feature = "diagnostics")]
+            return self.track_fetch::<T, _>(location, true, || T::get_many(self));
+            #[cfg(not(feature = "diagnostics"))]
+            T::get_many(self)
+        };
+        #[cfg(feature = "metrics")]
+        let result = self.count_fetch::<T, _>(false, fetch);
+        #[cfg(not(feature = "metrics"))]
+        let result = fetch();
+        #[cfg(feature = "tracing")]
+        self.trace_fetch::<T, _>(&result);
+        result
     }
     ///
     /// Waits to get a lock for each of the types instead of returning an error in the case of
@@ -538,11 +1104,105 @@ impl<U: ?Sized + for<'a> Unit<'a>> BlackBox<U> {
     /// or `MutexStorage`, because they are able to block the thread to acquire a lock.
     ///
     #[inline(always)]
+    #[track_caller]
     pub fn waiting_get<'a, T: FetchMultiple<'a, U>>(&'a self) -> DynamicResult<T::Output>
     where
         Borrowed<'a, U>: Waitable,
         MutBorrowed<'a, U>: Waitable,
     {
-        T::waiting_get_many(self)
+        #[cfg(feature = "tracing")]
+        let _span =
+            debug_span!(target: "restor", "waiting_get", fetch = type_name::<T>()).entered();
+        #[cfg(feature = "diagnostics")]
+        let location = Location::caller();
+        let fetch = || {
+            #[cfg(feature = "diagnostics")]
+            return self.track_fetch::<T, _>(location, true, || T::waiting_get_many(self));
+            #[cfg(not(feature = "diagnostics"))]
+            T::waiting_get_many(self)
+        };
+        #[cfg(feature = "metrics")]
+        let result = self.count_fetch::<T, _>(true, fetch);
+        #[cfg(not(feature = "metrics"))]
+        let result = fetch();
+        #[cfg(feature = "tracing")]
+        self.trace_fetch::<T, _>(&result);
+        result
+    }
+    ///
+    /// Checks whether [`BlackBox::get`] would currently succeed for `T`,
+    /// returning the error it would fail with otherwise. This never waits,
+    /// and neither takes anything out of the storage nor keeps anything
+    /// borrowed once it returns. The values which the kinds in a tuple would
+    /// take out are accounted for by the kinds after them, so checking
+    /// `(Box<T>, Box<T>)` fails in the case that there is only one `T`.
+    ///
+    /// Note that another thread may still change the storage before the
+    /// value is actually fetched.
+    ///
+    /// # Example
+    /// ```
+    /// # fn main() {
+    /// use restor::{make_storage, DynamicStorage, ErrorDesc, UnitError};
+    /// let storage = make_storage!(DynamicStorage: usize, String, isize);
+    /// storage.insert(0usize).unwrap();
+    /// storage.insert(String::new()).unwrap();
+    /// assert!(storage.can_get::<(&usize, Box<String>)>().is_ok());
+    /// assert_eq!(
+    ///     storage.can_get::<&isize>(),
+    ///     Err(ErrorDesc::Unit(UnitError::IsNotOne))
+    /// );
+    /// assert_eq!(
+    ///     storage.can_get::<(&mut usize, &usize)>(),
+    ///     Err(ErrorDesc::BorrowedIncompatibly)
+    /// );
+    /// // Nothing was taken out.
+    /// assert_eq!(&*storage.get::<&String>().unwrap(), "");
+    /// # }
+    /// ```
+    ///
+    #[inline(always)]
+    #[track_caller]
+    pub fn can_get<'a, T: FetchMultiple<'a, U>>(&'a self) -> DynamicResult<()> {
+        #[cfg(feature = "diagnostics")]
+        return self.track_fetch::<T, _>(Location::caller(), false, || T::can_get_many(self));
+        #[cfg(not(feature = "diagnostics"))]
+        T::can_get_many(self)
+    }
+    ///
+    /// The types which fetching `T` reads from and writes to.
+    ///
+    pub fn access<'a, T: FetchMultiple<'a, U>>() -> Access {
+        let mut access = Access::new();
+        T::access(&mut access);
+        access
+    }
+    ///
+    /// Checks whether the values fetched using `T` and `V` can never be held
+    /// at the same time, since either writes to a type the other uses.
+    ///
+    /// Whether a single fetch conflicts with itself, such as `(&mut A, &A)`,
+    /// can be checked using [`Access::conflicts_with_itself`] instead.
+    ///
+    /// # Example
+    /// ```
+    /// # fn main() {
+    /// use restor::RwLockStorage;
+    /// assert!(!RwLockStorage::conflicts::<(&usize, &mut String), &usize>());
+    /// assert!(RwLockStorage::conflicts::<&[usize], Box<usize>>());
+    /// assert!(RwLockStorage::access::<(&mut usize, &usize)>().conflicts_with_itself());
+    /// # }
+    /// ```
+    ///
+    /// [`Access::conflicts_with_itself`]: ./struct.Access.html#method.conflicts_with_itself
+    ///
+    pub fn conflicts<'a, T: FetchMultiple<'a, U>, V: FetchMultiple<'a, U>>() -> bool {
+        Self::access::<T>().conflicts_with(&Self::access::<V>())
+    }
+}
+
+impl<U: ?Sized + for<'a> Unit<'a>> fmt::Debug for BlackBox<U> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.fmt_units("BlackBox", f)
     }
 }
@@ -0,0 +1,77 @@
+use super::{BlackBox, DynamicResult, StorageUnit, Unit};
+use rayon::prelude::*;
+use rayon::slice::{Iter, IterMut};
+
+impl<U: ?Sized + for<'a> Unit<'a>> BlackBox<U> {
+    ///
+    /// Runs `f` on a parallel iterator over the values of type `T`, which
+    /// is the parallel version of [`BlackBox::run_for`].
+    ///
+    /// The unit stays locked for as long as `f` runs, so the values can be
+    /// processed on the `rayon` thread pool without being copied out of
+    /// the storage. This requires the `rayon` feature.
+    ///
+    /// # Example
+    /// ```
+    /// # fn main() {
+    /// use rayon::prelude::*;
+    /// use restor::{make_storage, RwLockStorage};
+    /// let storage = make_storage!(RwLockStorage: usize);
+    /// storage.insert_many((0..1000usize).collect()).unwrap();
+    /// let sum = storage
+    ///     .par_run_for::<usize, _, _>(|x| x.sum::<usize>())
+    ///     .unwrap();
+    /// assert_eq!(sum, 499500);
+    /// # }
+    /// ```
+    ///
+    /// [`BlackBox::run_for`]: #method.run_for
+    ///
+    pub fn par_run_for<T: 'static + Sync, D, F: FnOnce(Iter<'_, T>) -> D>(
+        &self,
+        f: F,
+    ) -> DynamicResult<D> {
+        let storage = self.unit_get::<T>()?.storage()?;
+        let values = storage.downcast_ref::<StorageUnit<T>>().unwrap().many()?;
+        Ok(f(values.par_iter()))
+    }
+
+    ///
+    /// Runs `f` on a parallel iterator over mutable references to the
+    /// values of type `T`, which is the parallel version of
+    /// [`BlackBox::run_for_mut`].
+    ///
+    /// Unlike `run_for_mut`, this cannot add or remove values. This
+    /// requires the `rayon` feature.
+    ///
+    /// # Example
+    /// ```
+    /// # fn main() {
+    /// use rayon::prelude::*;
+    /// use restor::{make_storage, MutexStorage};
+    /// let storage = make_storage!(MutexStorage: usize);
+    /// storage.insert_many(vec![0usize, 1, 2, 3]).unwrap();
+    /// storage
+    ///     .par_run_for_mut::<usize, _, _>(|x| x.for_each(|x| *x *= 2))
+    ///     .unwrap();
+    /// let doubled = storage
+    ///     .par_run_for::<usize, _, _>(|x| x.copied().collect::<Vec<_>>())
+    ///     .unwrap();
+    /// assert_eq!(doubled, vec![0, 2, 4, 6]);
+    /// # }
+    /// ```
+    ///
+    /// [`BlackBox::run_for_mut`]: #method.run_for_mut
+    ///
+    pub fn par_run_for_mut<T: 'static + Send, D, F: FnOnce(IterMut<'_, T>) -> D>(
+        &self,
+        f: F,
+    ) -> DynamicResult<D> {
+        let mut storage = self.unit_get::<T>()?.storage_mut()?;
+        let values = storage
+            .downcast_mut::<StorageUnit<T>>()
+            .unwrap()
+            .slice_mut()?;
+        Ok(f(values.par_iter_mut()))
+    }
+}
@@ -1,7 +1,8 @@
 use super::{BlackBox, Unit};
 use crate::impl_unit;
-use std::any::{Any, TypeId};
-use std::cell::{Ref, RefCell, RefMut};
+use alloc::boxed::Box;
+use core::any::{Any, TypeId};
+use core::cell::{Ref, RefCell, RefMut};
 
 use super::*;
 
@@ -15,6 +16,9 @@ impl<T> RefCellUnit<T> {
             inner: RefCell::new(data),
         }
     }
+    pub fn into_inner(self) -> T {
+        self.inner.into_inner()
+    }
 }
 
 // Any changes made to RefCell/Mutex/RwLock units are done first on this one, and then
@@ -24,23 +28,8 @@ impl<'a, T: 'static> Unit<'a> for RefCellUnit<StorageUnit<T>> {
     type MutBorrowed = RefMut<'a, dyn Any>;
 
     fn insert_any(&self, new: Box<dyn Any>) -> Option<(Box<dyn Any>, ErrorDesc)> {
-        let newtype = new.type_id();
         if let Ok(mut x) = self.inner.try_borrow_mut() {
-            if new.is::<T>() {
-                x.insert(*new.downcast::<T>().unwrap_or_else(|_| {
-                    panic!(
-                        "Tried to insert an object with type {:?} into a storage of type {:?}",
-                        newtype,
-                        TypeId::of::<T>()
-                    )
-                }));
-                None
-            } else if new.is::<Vec<T>>() {
-                x.insert_many(*new.downcast::<Vec<T>>().unwrap());
-                None
-            } else {
-                Some((new, ErrorDesc::NoMatchingType))
-            }
+            x.insert_any(new)
         } else {
             Some((new, ErrorDesc::BorrowedIncompatibly))
         }
@@ -54,7 +43,7 @@ impl<'a, T: 'static> Unit<'a> for RefCellUnit<StorageUnit<T>> {
         self.inner
             .try_borrow()
             .ok()
-            .map(|x| Ref::map::<dyn Any, _>(x, |z| &*z))
+            .map(|x| Ref::map::<dyn Any, _>(x, |z| z))
             .ok_or(ErrorDesc::BorrowedIncompatibly)
     }
     fn storage_mut(&'a self) -> DynamicResult<RefMut<'a, dyn Any>> {
@@ -65,16 +54,20 @@ impl<'a, T: 'static> Unit<'a> for RefCellUnit<StorageUnit<T>> {
             .ok_or(ErrorDesc::BorrowedIncompatibly)
     }
 
-    fn waiting_storage(&'a self) -> Ref<'a, dyn Any> {
+    fn waiting_storage(&'a self) -> DynamicResult<Ref<'a, dyn Any>> {
         unreachable!()
     }
-    fn waiting_storage_mut(&'a self) -> RefMut<'a, dyn Any> {
+    fn waiting_storage_mut(&'a self) -> DynamicResult<RefMut<'a, dyn Any>> {
         unreachable!()
     }
 
     fn id(&self) -> TypeId {
         TypeId::of::<T>()
     }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
 }
 
 type RefCellBlackBox =
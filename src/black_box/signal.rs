@@ -0,0 +1,345 @@
+use super::{
+    BlackBox, DynamicResult, ErrorDesc, MutBorrowed, StorageUnit, Unit, UnitError, Waitable,
+};
+use alloc::vec::Vec;
+#[cfg(feature = "tracing")]
+use core::any::type_name;
+#[cfg(not(feature = "std"))]
+use core::hint::spin_loop;
+use core::sync::atomic::{AtomicUsize, Ordering};
+#[cfg(feature = "std")]
+use std::sync::{Condvar, Mutex, MutexGuard, PoisonError};
+#[cfg(feature = "std")]
+use std::time::{Duration, Instant};
+#[cfg(feature = "tracing")]
+use tracing::debug_span;
+
+///
+/// Notifies the threads waiting for a value to be inserted into a unit,
+/// which is what `waiting_take` and `waiting_take_many` wait on.
+///
+/// Every notification advances a generation counter, so a waiter which
+/// records the generation before checking the unit cannot miss a value
+/// that is inserted after it checked.
+///
+/// This is built on a `Condvar`, or spins while waiting in the case that
+/// the `std` feature is disabled. Notifying a signal which nothing waits
+/// on only advances the counter, without touching the `Condvar`.
+///
+pub struct Signal {
+    generation: AtomicUsize,
+    #[cfg(feature = "std")]
+    waiters: AtomicUsize,
+    #[cfg(feature = "std")]
+    lock: Mutex<()>,
+    #[cfg(feature = "std")]
+    notified: Condvar,
+}
+
+// A waiter is counted before it checks the generation under the lock, and
+// a notifier advances the generation before it checks the count, so either
+// the notifier sees the waiter and wakes it under the lock, or the waiter
+// sees the new generation. Both need to be `SeqCst` for this to hold.
+#[cfg(feature = "std")]
+impl Signal {
+    pub fn new() -> Self {
+        Self {
+            generation: AtomicUsize::new(0),
+            waiters: AtomicUsize::new(0),
+            lock: Mutex::new(()),
+            notified: Condvar::new(),
+        }
+    }
+
+    ///
+    /// Wakes every thread waiting on this signal.
+    ///
+    pub fn notify(&self) {
+        self.generation.fetch_add(1, Ordering::SeqCst);
+        if self.waiters.load(Ordering::SeqCst) != 0 {
+            let _lock = self.lock();
+            self.notified.notify_all();
+        }
+    }
+
+    ///
+    /// Returns the current generation, which is passed to `wait`.
+    ///
+    pub fn generation(&self) -> usize {
+        self.generation.load(Ordering::SeqCst)
+    }
+
+    ///
+    /// Waits until the signal has been notified since `seen` was returned
+    /// by `generation`.
+    ///
+    pub fn wait(&self, seen: usize) {
+        self.waiters.fetch_add(1, Ordering::SeqCst);
+        let mut lock = self.lock();
+        while self.generation() == seen {
+            lock = self
+                .notified
+                .wait(lock)
+                .unwrap_or_else(PoisonError::into_inner);
+        }
+        self.waiters.fetch_sub(1, Ordering::SeqCst);
+    }
+
+    ///
+    /// The same as `wait`, except that this gives up waiting at `deadline`,
+    /// returning whether or not the signal was notified.
+    ///
+    pub fn wait_until(&self, seen: usize, deadline: Instant) -> bool {
+        self.waiters.fetch_add(1, Ordering::SeqCst);
+        let mut lock = self.lock();
+        let mut notified = true;
+        while self.generation() == seen {
+            let now = Instant::now();
+            if now >= deadline {
+                notified = false;
+                break;
+            }
+            lock = self
+                .notified
+                .wait_timeout(lock, deadline - now)
+                .unwrap_or_else(PoisonError::into_inner)
+                .0;
+        }
+        self.waiters.fetch_sub(1, Ordering::SeqCst);
+        notified
+    }
+
+    // Nothing is guarded by the lock, so a poisoned lock can safely be
+    // recovered.
+    fn lock(&self) -> MutexGuard<'_, ()> {
+        self.lock.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+#[cfg(not(feature = "std"))]
+impl Signal {
+    pub fn new() -> Self {
+        Self {
+            generation: AtomicUsize::new(0),
+        }
+    }
+
+    ///
+    /// Wakes every thread waiting on this signal.
+    ///
+    pub fn notify(&self) {
+        self.generation.fetch_add(1, Ordering::Release);
+    }
+
+    ///
+    /// Returns the current generation, which is passed to `wait`.
+    ///
+    pub fn generation(&self) -> usize {
+        self.generation.load(Ordering::Acquire)
+    }
+
+    ///
+    /// Waits until the signal has been notified since `seen` was returned
+    /// by `generation`.
+    ///
+    pub fn wait(&self, seen: usize) {
+        while self.generation() == seen {
+            spin_loop();
+        }
+    }
+}
+
+impl Default for Signal {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// Takes out of an empty unit fail with `IsNope`, which is what is waited on.
+fn pop<T>(unit: &mut StorageUnit<T>) -> DynamicResult<T> {
+    unit.pop()
+}
+
+fn drain<T>(unit: &mut StorageUnit<T>) -> DynamicResult<Vec<T>> {
+    let values = unit.drain();
+    if values.is_empty() {
+        Err(ErrorDesc::Unit(UnitError::IsNope))
+    } else {
+        Ok(values)
+    }
+}
+
+impl<U: ?Sized + for<'a> Unit<'a>> BlackBox<U> {
+    ///
+    /// Takes the oldest value of type `T` out of the storage without waiting,
+    /// returning `UnitError::IsNope` in the case that there are none.
+    ///
+    /// This is the non-blocking version of [`BlackBox::waiting_take`].
+    ///
+    /// [`BlackBox::waiting_take`]: #method.waiting_take
+    ///
+    pub fn try_take<T: 'static>(&self) -> DynamicResult<T> {
+        self.take_now(pop)
+    }
+
+    ///
+    /// Takes all of the values of type `T` out of the storage without
+    /// waiting, oldest first, returning `UnitError::IsNope` in the case
+    /// that there are none.
+    ///
+    pub fn try_take_many<T: 'static>(&self) -> DynamicResult<Vec<T>> {
+        self.take_now(drain)
+    }
+
+    ///
+    /// Takes the oldest value of type `T` out of the storage, waiting until
+    /// one is inserted in the case that there are none.
+    ///
+    /// Only values inserted using the `insert` family of functions or moved
+    /// using `transfer` wake a waiting thread, and values which are pushed
+    /// while the unit is borrowed, such as in `run_for_mut`, are only seen
+    /// once another value is inserted.
+    ///
+    /// This returns `UnitError::Unsupported` in the case that the unit for
+    /// `T` cannot be waited on, such as an atomic unit.
+    ///
+    /// # Example
+    /// ```
+    /// # fn main() {
+    /// use restor::{make_storage, MutexStorage};
+    /// use std::thread::spawn;
+    /// let storage = make_storage!(Arc MutexStorage: usize);
+    /// let producer = storage.clone();
+    /// let handle = spawn(move || producer.waiting_insert(10usize).unwrap());
+    /// assert_eq!(storage.waiting_take::<usize>().unwrap(), 10);
+    /// handle.join().unwrap();
+    /// # }
+    /// ```
+    ///
+    pub fn waiting_take<'a, T: 'static>(&'a self) -> DynamicResult<T>
+    where
+        MutBorrowed<'a, U>: Waitable,
+    {
+        self.take_with(pop, |signal, seen| {
+            signal.wait(seen);
+            true
+        })
+    }
+
+    ///
+    /// Takes all of the values of type `T` out of the storage, oldest first,
+    /// waiting until at least one is inserted in the case that there are none.
+    ///
+    /// Please refer to [`BlackBox::waiting_take`] for the details of waiting.
+    ///
+    /// [`BlackBox::waiting_take`]: #method.waiting_take
+    ///
+    pub fn waiting_take_many<'a, T: 'static>(&'a self) -> DynamicResult<Vec<T>>
+    where
+        MutBorrowed<'a, U>: Waitable,
+    {
+        self.take_with(drain, |signal, seen| {
+            signal.wait(seen);
+            true
+        })
+    }
+
+    ///
+    /// The same as [`BlackBox::waiting_take`], except that this gives up
+    /// after `timeout`, returning `UnitError::IsNope`.
+    ///
+    /// [`BlackBox::waiting_take`]: #method.waiting_take
+    ///
+    #[cfg(feature = "std")]
+    pub fn waiting_take_timeout<'a, T: 'static>(&'a self, timeout: Duration) -> DynamicResult<T>
+    where
+        MutBorrowed<'a, U>: Waitable,
+    {
+        let deadline = Instant::now() + timeout;
+        self.take_with(pop, |signal, seen| signal.wait_until(seen, deadline))
+    }
+
+    ///
+    /// The same as [`BlackBox::waiting_take_many`], except that this gives up
+    /// after `timeout`, returning `UnitError::IsNope`.
+    ///
+    /// [`BlackBox::waiting_take_many`]: #method.waiting_take_many
+    ///
+    #[cfg(feature = "std")]
+    pub fn waiting_take_many_timeout<'a, T: 'static>(
+        &'a self,
+        timeout: Duration,
+    ) -> DynamicResult<Vec<T>>
+    where
+        MutBorrowed<'a, U>: Waitable,
+    {
+        let deadline = Instant::now() + timeout;
+        self.take_with(drain, |signal, seen| signal.wait_until(seen, deadline))
+    }
+
+    // Runs `take` on the unit for `T` without waiting.
+    fn take_now<T: 'static, R>(
+        &self,
+        take: fn(&mut StorageUnit<T>) -> DynamicResult<R>,
+    ) -> DynamicResult<R> {
+        let result = self.unit_get::<T>().and_then(|unit| {
+            take(
+                unit.storage_mut()?
+                    .downcast_mut::<StorageUnit<T>>()
+                    .unwrap(),
+            )
+        });
+        #[cfg(feature = "tracing")]
+        self.trace_take::<T, _>(&result);
+        result
+    }
+
+    // Runs `take` on the unit for `T` until it finds values, using `wait`
+    // to wait for the unit's signal in between, which returns `false` to
+    // give up.
+    fn take_with<'a, T: 'static, R>(
+        &'a self,
+        take: fn(&mut StorageUnit<T>) -> DynamicResult<R>,
+        wait: impl FnMut(&Signal, usize) -> bool,
+    ) -> DynamicResult<R>
+    where
+        MutBorrowed<'a, U>: Waitable,
+    {
+        #[cfg(feature = "tracing")]
+        let _span =
+            debug_span!(target: "restor", "waiting_take", unit = type_name::<T>()).entered();
+        let result = self.take_waiting(take, wait);
+        #[cfg(feature = "tracing")]
+        self.trace_take::<T, _>(&result);
+        result
+    }
+
+    // `take_with`, without tracing it.
+    fn take_waiting<'a, T: 'static, R>(
+        &'a self,
+        take: fn(&mut StorageUnit<T>) -> DynamicResult<R>,
+        mut wait: impl FnMut(&Signal, usize) -> bool,
+    ) -> DynamicResult<R>
+    where
+        MutBorrowed<'a, U>: Waitable,
+    {
+        let unit = self.unit_get::<T>()?;
+        let signal = unit
+            .signal()
+            .ok_or(ErrorDesc::Unit(UnitError::Unsupported))?;
+        loop {
+            let seen = signal.generation();
+            match take(
+                unit.waiting_storage_mut()?
+                    .downcast_mut::<StorageUnit<T>>()
+                    .unwrap(),
+            ) {
+                Err(ErrorDesc::Unit(UnitError::IsNope)) => {}
+                result => return result,
+            }
+            if !wait(signal, seen) {
+                return Err(ErrorDesc::Unit(UnitError::IsNope));
+            }
+        }
+    }
+}
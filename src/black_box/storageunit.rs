@@ -1,5 +1,19 @@
+use super::bounded::BoundPolicy;
 use super::errors::*;
-use std::mem::swap;
+use super::value_set::ValueSet;
+use alloc::collections::VecDeque;
+use alloc::sync::Arc;
+use alloc::{boxed::Box, vec, vec::Vec};
+use core::any::Any;
+use core::cmp::Ordering;
+use core::fmt;
+use core::hash::Hash;
+use core::mem::{swap, take};
+
+///
+/// The order which a `Sorted` unit keeps its values in.
+///
+pub type Order<T> = Arc<dyn Fn(&T, &T) -> Ordering + Send + Sync>;
 
 ///
 /// The base storage unit for this library.
@@ -13,12 +27,59 @@ use std::mem::swap;
 /// the user. This is usually abstracted through a type erased
 /// `Unit`.
 ///
+/// A `Queue` is only created for units allocated using `allocate_queue`,
+/// and stays a `Queue` even when it is empty. Its values are inserted at
+/// the back and taken from the front, but cannot be borrowed.
+///
+/// Similarly, `Events` are only created for units allocated using
+/// `allocate_events`. Values are inserted into `current`, while only
+/// `previous` can be borrowed or taken, until the buffers are swapped.
+///
+/// A `Sorted` unit is only created for units allocated using
+/// `allocate_sorted` or `allocate_sorted_by_key`, and keeps its values in
+/// ascending order according to `order`, so that the value with the highest
+/// priority is the last one. Values are taken from the end, and values of
+/// equal priority are kept newest first, so that they are taken in the order
+/// they were inserted in. Its values can be borrowed immutably.
+///
+/// A `Set` is only created for units allocated using `allocate_set`, and
+/// holds at most one of each value, so inserting a value which is already
+/// present is a no-op. Its values can be borrowed immutably.
+///
+/// A `Bounded` unit is only created for units allocated using
+/// `allocate_bounded`, and holds at most `capacity` values, oldest first,
+/// handling any more according to `policy`. Its values can be borrowed
+/// immutably.
+///
+/// The `previous` buffer of `Events` and the values of a `Bounded` unit
+/// are taken from the front, so they are kept in a `VecDeque`, which is
+/// always kept contiguous so that it can be borrowed as a slice.
+///
+/// More kinds of units may be added in the future, so matching on a
+/// `StorageUnit` outside of this crate requires a wildcard arm.
+///
 /// [`SmallVec`]: https://docs.rs/smallvec/0.6.9/smallvec/
 ///
+#[non_exhaustive]
 pub enum StorageUnit<T: 'static> {
     Nope,
     One(T),
     Many(Vec<T>),
+    Queue(VecDeque<T>),
+    Events {
+        current: Vec<T>,
+        previous: VecDeque<T>,
+    },
+    Sorted {
+        values: Vec<T>,
+        order: Order<T>,
+    },
+    Set(ValueSet<T>),
+    Bounded {
+        values: VecDeque<T>,
+        capacity: usize,
+        policy: BoundPolicy,
+    },
 }
 
 impl<T: Sized> StorageUnit<T> {
@@ -27,6 +88,126 @@ impl<T: Sized> StorageUnit<T> {
         StorageUnit::Nope
     }
 
+    #[inline(always)]
+    pub fn new_queue() -> Self {
+        StorageUnit::Queue(VecDeque::new())
+    }
+
+    #[inline(always)]
+    pub fn new_events() -> Self {
+        StorageUnit::Events {
+            current: Vec::new(),
+            previous: VecDeque::new(),
+        }
+    }
+
+    #[inline(always)]
+    pub fn new_set() -> Self
+    where
+        T: Hash + Eq,
+    {
+        StorageUnit::Set(ValueSet::new())
+    }
+
+    #[inline(always)]
+    pub fn new_sorted(order: Order<T>) -> Self {
+        StorageUnit::Sorted {
+            values: Vec::new(),
+            order,
+        }
+    }
+
+    #[inline(always)]
+    pub fn new_bounded(capacity: usize, policy: BoundPolicy) -> Self {
+        StorageUnit::Bounded {
+            values: VecDeque::new(),
+            capacity,
+            policy,
+        }
+    }
+
+    ///
+    /// Returns the number of values which can be inserted before a value
+    /// would be rejected, or `None` in the case that none would be.
+    ///
+    fn room(&self) -> Option<usize> {
+        match self {
+            StorageUnit::Bounded {
+                values,
+                capacity,
+                policy: BoundPolicy::Reject,
+            } => Some(capacity.saturating_sub(values.len())),
+            _ => None,
+        }
+    }
+
+    ///
+    /// Inserts `new`, returning it in the case that it cannot be inserted,
+    /// such as when it is already present in a `Set`, or when a `Bounded`
+    /// unit which rejects values is full.
+    ///
+    pub fn try_insert(&mut self, new: T) -> Result<(), (T, ErrorDesc)> {
+        match self {
+            StorageUnit::Set(set) => set
+                .insert(new)
+                .map_err(|new| (new, ErrorDesc::Unit(UnitError::AlreadyPresent))),
+            _ if self.room() == Some(0) => Err((new, ErrorDesc::Unit(UnitError::Full))),
+            _ => {
+                self.insert(new);
+                Ok(())
+            }
+        }
+    }
+
+    ///
+    /// Inserts all of `new`, returning it in the case that a `Bounded` unit
+    /// which rejects values does not have room for all of it.
+    ///
+    /// In the case that the unit is a `Set`, the values which are not yet
+    /// present are inserted, and the rest are returned.
+    ///
+    pub fn try_insert_many(&mut self, new: Vec<T>) -> Result<(), (Vec<T>, ErrorDesc)> {
+        match self {
+            StorageUnit::Set(set) => {
+                let rejected = new
+                    .into_iter()
+                    .filter_map(|value| set.insert(value).err())
+                    .collect::<Vec<_>>();
+                if rejected.is_empty() {
+                    Ok(())
+                } else {
+                    Err((rejected, ErrorDesc::Unit(UnitError::AlreadyPresent)))
+                }
+            }
+            _ => match self.room() {
+                Some(room) if room < new.len() => Err((new, ErrorDesc::Unit(UnitError::Full))),
+                _ => {
+                    self.insert_many(new);
+                    Ok(())
+                }
+            },
+        }
+    }
+
+    ///
+    /// Inserts either a `T` or a `Vec<T>` using `try_insert` or
+    /// `try_insert_many` respectively, returning `new` in the case that it
+    /// is neither.
+    ///
+    pub fn insert_any(&mut self, new: Box<dyn Any>) -> Option<(Box<dyn Any>, ErrorDesc)> {
+        if new.is::<T>() {
+            self.try_insert(*new.downcast::<T>().unwrap())
+                .err()
+                .map(|(new, e)| (Box::new(new) as Box<dyn Any>, e))
+        } else if new.is::<Vec<T>>() {
+            self.try_insert_many(*new.downcast::<Vec<T>>().unwrap())
+                .err()
+                .map(|(new, e)| (Box::new(new) as Box<dyn Any>, e))
+        } else {
+            Some((new, ErrorDesc::NoMatchingType))
+        }
+    }
+
     pub fn insert(&mut self, new: T) {
         match self {
             StorageUnit::Nope => {
@@ -48,6 +229,25 @@ impl<T: Sized> StorageUnit<T> {
             StorageUnit::Many(many) => {
                 many.push(new);
             }
+            StorageUnit::Queue(queue) => {
+                queue.push_back(new);
+            }
+            StorageUnit::Events { current, .. } => {
+                current.push(new);
+            }
+            StorageUnit::Sorted { values, order } => {
+                // Values are popped from the end, so a value goes before
+                // the ones of equal priority, which are popped before it.
+                let index = values.partition_point(|x| order(x, &new) == Ordering::Less);
+                values.insert(index, new);
+            }
+            StorageUnit::Set(set) => {
+                let _ = set.insert(new);
+            }
+            StorageUnit::Bounded { values, .. } => {
+                values.push_back(new);
+                self.evict();
+            }
         }
     }
 
@@ -72,42 +272,244 @@ impl<T: Sized> StorageUnit<T> {
             StorageUnit::Many(arr) => {
                 arr.append(&mut new);
             }
+            StorageUnit::Queue(queue) => {
+                queue.extend(new);
+            }
+            StorageUnit::Events { current, .. } => {
+                current.append(&mut new);
+            }
+            StorageUnit::Sorted { values, order } => {
+                // The sort is stable, so this puts the newest of the values of
+                // equal priority first, the same as inserting them one by one.
+                new.reverse();
+                new.append(values);
+                new.sort_by(|a, b| order(a, b));
+                *values = new;
+            }
+            StorageUnit::Set(set) => {
+                for value in new {
+                    let _ = set.insert(value);
+                }
+            }
+            StorageUnit::Bounded { values, .. } => {
+                values.extend(new);
+                self.evict();
+            }
+        }
+    }
+
+    // Brings a `Bounded` unit back within its capacity, which drops the
+    // newest values in the case that it rejects values, and the oldest
+    // ones otherwise, and keeps its values contiguous.
+    fn evict(&mut self) {
+        if let StorageUnit::Bounded {
+            values,
+            capacity,
+            policy,
+        } = self
+        {
+            if values.len() > *capacity {
+                match policy {
+                    BoundPolicy::Reject => values.truncate(*capacity),
+                    _ => {
+                        values.drain(..values.len() - *capacity);
+                    }
+                }
+            }
+            // Once the values wrap around the end of the buffer, they are
+            // moved to its start with room for as many again after them, so
+            // this only happens once in every `capacity` inserts.
+            if !values.as_slices().1.is_empty() {
+                let mut compacted = Vec::from(take(values));
+                compacted.reserve(*capacity);
+                *values = compacted.into();
+            }
+        }
+    }
+
+    ///
+    /// Moves the first value for which `f` returns `true` to the end of a
+    /// `Bounded` unit which evicts the least recently touched values.
+    ///
+    pub fn touch<F: FnMut(&T) -> bool>(&mut self, f: F) -> DynamicResult<()> {
+        match self {
+            StorageUnit::Bounded {
+                values,
+                policy: BoundPolicy::EvictLeastRecentlyTouched,
+                ..
+            } => {
+                let index = values
+                    .iter()
+                    .position(f)
+                    .ok_or(ErrorDesc::Unit(UnitError::IsNope))?;
+                values.make_contiguous()[index..].rotate_left(1);
+                Ok(())
+            }
+            _ => Err(ErrorDesc::Unit(UnitError::Unsupported)),
+        }
+    }
+
+    ///
+    /// Moves all of the values in `other` to the end of `self`, leaving
+    /// `other` empty.
+    ///
+    /// Unlike `insert_many`, this keeps a single value as a `One` when
+    /// appending to an empty unit. Either unit stays a `Queue` or `Events`
+    /// in the case that it is one. Values appended to `Events` are only
+    /// seen once the buffers are swapped, unless both units are `Events`,
+    /// in which case each buffer is appended to its counterpart. Values
+    /// appended to a `Set` which are already present are left in `other`,
+    /// and `UnitError::AlreadyPresent` is returned. Similarly, in the case
+    /// that a `Bounded` unit rejects values, the values which do not fit
+    /// are left in `other`, and `UnitError::Full` is returned.
+    ///
+    pub fn append(&mut self, other: &mut Self) -> DynamicResult<()> {
+        match (&*self, &*other) {
+            (_, StorageUnit::Nope) => {}
+            (StorageUnit::Bounded { .. }, _) if self.room().is_some() => {
+                let mut values = other.drain();
+                let room = self.room().unwrap().min(values.len());
+                let rest = values.split_off(room);
+                self.insert_many(values);
+                if !rest.is_empty() {
+                    other.insert_many(rest);
+                    other.rearrange_if_necessary();
+                    return Err(ErrorDesc::Unit(UnitError::Full));
+                }
+            }
+            (StorageUnit::Set(_), _) => {
+                if let Err((rejected, e)) = self.try_insert_many(other.drain()) {
+                    other.insert_many(rejected);
+                    other.rearrange_if_necessary();
+                    return Err(e);
+                }
+            }
+            (StorageUnit::Nope, StorageUnit::One(_))
+            | (StorageUnit::Nope, StorageUnit::Many(_)) => swap(self, other),
+            (StorageUnit::Events { .. }, StorageUnit::Events { .. }) => {
+                if let (
+                    StorageUnit::Events { current, previous },
+                    StorageUnit::Events {
+                        current: other_current,
+                        previous: other_previous,
+                    },
+                ) = (self, other)
+                {
+                    previous.append(other_previous);
+                    previous.make_contiguous();
+                    current.append(other_current);
+                }
+            }
+            _ => self.insert_many(other.drain()),
+        }
+        Ok(())
+    }
+
+    ///
+    /// The number of values held, including both buffers of `Events`.
+    ///
+    pub fn len(&self) -> usize {
+        match self {
+            StorageUnit::Nope => 0,
+            StorageUnit::One(_) => 1,
+            StorageUnit::Many(values) | StorageUnit::Sorted { values, .. } => values.len(),
+            StorageUnit::Bounded { values, .. } => values.len(),
+            StorageUnit::Queue(queue) => queue.len(),
+            StorageUnit::Events { current, previous } => current.len() + previous.len(),
+            StorageUnit::Set(set) => set.len(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    ///
+    /// The name of the kind of unit this is.
+    ///
+    #[cfg(any(feature = "metrics", feature = "tracing"))]
+    pub(crate) fn kind(&self) -> &'static str {
+        match self {
+            StorageUnit::Nope => "Nope",
+            StorageUnit::One(_) => "One",
+            StorageUnit::Many(_) => "Many",
+            StorageUnit::Queue(_) => "Queue",
+            StorageUnit::Events { .. } => "Events",
+            StorageUnit::Sorted { .. } => "Sorted",
+            StorageUnit::Set(_) => "Set",
+            StorageUnit::Bounded { .. } => "Bounded",
         }
     }
 
     #[inline]
     pub fn one(&self) -> DynamicResult<&T> {
-        if let StorageUnit::One(x) = self {
-            Ok(x)
-        } else {
-            Err(ErrorDesc::Unit(UnitError::IsNotOne))
+        match self {
+            StorageUnit::One(x) => Ok(x),
+            StorageUnit::Queue(_)
+            | StorageUnit::Events { .. }
+            | StorageUnit::Sorted { .. }
+            | StorageUnit::Set(_)
+            | StorageUnit::Bounded { .. } => Err(ErrorDesc::Unit(UnitError::Unsupported)),
+            _ => Err(ErrorDesc::Unit(UnitError::IsNotOne)),
         }
     }
 
     #[inline]
     pub fn one_mut(&mut self) -> DynamicResult<&mut T> {
-        if let StorageUnit::One(x) = self {
-            Ok(x)
-        } else {
-            Err(ErrorDesc::Unit(UnitError::IsNotOne))
+        match self {
+            StorageUnit::One(x) => Ok(x),
+            StorageUnit::Queue(_)
+            | StorageUnit::Events { .. }
+            | StorageUnit::Sorted { .. }
+            | StorageUnit::Set(_)
+            | StorageUnit::Bounded { .. } => Err(ErrorDesc::Unit(UnitError::Unsupported)),
+            _ => Err(ErrorDesc::Unit(UnitError::IsNotOne)),
         }
     }
 
     #[inline]
     pub fn many(&self) -> DynamicResult<&[T]> {
-        if let StorageUnit::Many(x) = self {
-            Ok(x)
-        } else {
-            Err(ErrorDesc::Unit(UnitError::IsNotMany))
+        match self {
+            StorageUnit::Many(x) => Ok(x),
+            // Both are kept contiguous, so the first slice holds every value.
+            StorageUnit::Events { previous, .. } => Ok(previous.as_slices().0),
+            StorageUnit::Sorted { values, .. } => Ok(values),
+            StorageUnit::Set(set) => Ok(set.as_slice()),
+            StorageUnit::Bounded { values, .. } => Ok(values.as_slices().0),
+            StorageUnit::Queue(_) => Err(ErrorDesc::Unit(UnitError::Unsupported)),
+            _ => Err(ErrorDesc::Unit(UnitError::IsNotMany)),
         }
     }
 
+    ///
+    /// Returns the values mutably, which is not supported by `Sorted`, `Set`
+    /// or `Bounded` units, as that could break their order, index or bound,
+    /// nor by `Events`, as only the values in one of their buffers can be
+    /// borrowed as a slice, using `slice_mut`.
+    ///
     #[inline]
     pub fn many_mut(&mut self) -> DynamicResult<&mut Vec<T>> {
-        if let StorageUnit::Many(x) = self {
-            Ok(x)
-        } else {
-            Err(ErrorDesc::Unit(UnitError::IsNotMany))
+        match self {
+            StorageUnit::Many(x) => Ok(x),
+            StorageUnit::Queue(_)
+            | StorageUnit::Events { .. }
+            | StorageUnit::Sorted { .. }
+            | StorageUnit::Set(_)
+            | StorageUnit::Bounded { .. } => Err(ErrorDesc::Unit(UnitError::Unsupported)),
+            _ => Err(ErrorDesc::Unit(UnitError::IsNotMany)),
+        }
+    }
+
+    ///
+    /// Returns the values as a mutable slice, which is supported by the same
+    /// units as `many_mut`, and by `Events`, whose visible values are
+    /// returned.
+    ///
+    #[inline]
+    pub fn slice_mut(&mut self) -> DynamicResult<&mut [T]> {
+        match self {
+            StorageUnit::Events { previous, .. } => Ok(previous.make_contiguous()),
+            _ => self.many_mut().map(Vec::as_mut_slice),
         }
     }
 
@@ -115,6 +517,19 @@ impl<T: Sized> StorageUnit<T> {
         match self {
             StorageUnit::Nope => Err(ErrorDesc::Unit(UnitError::IsNotOne)),
             StorageUnit::Many(x) => Ok(x.remove(0)),
+            StorageUnit::Queue(queue) => {
+                queue.pop_front().ok_or(ErrorDesc::Unit(UnitError::IsNope))
+            }
+            StorageUnit::Events { previous, .. } => previous
+                .pop_front()
+                .ok_or(ErrorDesc::Unit(UnitError::IsNope)),
+            StorageUnit::Sorted { values, .. } => {
+                values.pop().ok_or(ErrorDesc::Unit(UnitError::IsNope))
+            }
+            StorageUnit::Set(set) => set.pop().ok_or(ErrorDesc::Unit(UnitError::IsNope)),
+            StorageUnit::Bounded { values, .. } => {
+                values.pop_front().ok_or(ErrorDesc::Unit(UnitError::IsNope))
+            }
             StorageUnit::One(_) => {
                 let mut repl = StorageUnit::Nope;
                 swap(&mut repl, self);
@@ -148,6 +563,24 @@ impl<T: Sized> StorageUnit<T> {
                     unreachable!()
                 }
             }
+            StorageUnit::Queue(queue) if queue.is_empty() => {
+                Err(ErrorDesc::Unit(UnitError::IsNotMany))
+            }
+            StorageUnit::Queue(queue) => Ok(take(queue).into()),
+            StorageUnit::Events { previous, .. } if previous.is_empty() => {
+                Err(ErrorDesc::Unit(UnitError::IsNotMany))
+            }
+            StorageUnit::Events { previous, .. } => Ok(take(previous).into()),
+            StorageUnit::Sorted { values, .. } if values.is_empty() => {
+                Err(ErrorDesc::Unit(UnitError::IsNotMany))
+            }
+            StorageUnit::Sorted { values, .. } => Ok(take(values)),
+            StorageUnit::Set(set) if set.is_empty() => Err(ErrorDesc::Unit(UnitError::IsNotMany)),
+            StorageUnit::Set(set) => Ok(set.take_all()),
+            StorageUnit::Bounded { values, .. } if values.is_empty() => {
+                Err(ErrorDesc::Unit(UnitError::IsNotMany))
+            }
+            StorageUnit::Bounded { values, .. } => Ok(take(values).into()),
         }
     }
 
@@ -155,6 +588,11 @@ impl<T: Sized> StorageUnit<T> {
         match self {
             StorageUnit::Nope => Err(ErrorDesc::Unit(UnitError::IsNotMany)),
             StorageUnit::One(_) => Err(ErrorDesc::Unit(UnitError::IsNotMany)),
+            StorageUnit::Queue(_)
+            | StorageUnit::Events { .. }
+            | StorageUnit::Sorted { .. }
+            | StorageUnit::Set(_)
+            | StorageUnit::Bounded { .. } => self.extract_many().map(Vec::into_boxed_slice),
             StorageUnit::Many(_) => {
                 let mut repl = StorageUnit::Nope;
                 swap(&mut repl, self);
@@ -166,6 +604,60 @@ impl<T: Sized> StorageUnit<T> {
             }
         }
     }
+
+    ///
+    /// Takes the oldest value out of the unit, which is done in constant
+    /// time in the case that it is a `Queue`.
+    ///
+    /// In the case that the unit is `Sorted`, this takes the value with
+    /// the highest priority instead, and in the case that it is a `Set`,
+    /// the last value.
+    ///
+    pub fn pop(&mut self) -> DynamicResult<T> {
+        match self {
+            StorageUnit::Nope => Err(ErrorDesc::Unit(UnitError::IsNope)),
+            StorageUnit::Queue(queue) => {
+                queue.pop_front().ok_or(ErrorDesc::Unit(UnitError::IsNope))
+            }
+            StorageUnit::Events { .. }
+            | StorageUnit::Sorted { .. }
+            | StorageUnit::Set(_)
+            | StorageUnit::Bounded { .. } => self.extract_one(),
+            _ => {
+                let popped = self.extract_one();
+                self.rearrange_if_necessary();
+                popped
+            }
+        }
+    }
+
+    ///
+    /// Takes all of the values out of the unit, oldest first, leaving it
+    /// empty. `Sorted` units are drained in ascending order instead, with
+    /// values of equal priority newest first.
+    ///
+    pub fn drain(&mut self) -> Vec<T> {
+        match self {
+            StorageUnit::Queue(queue) => take(queue).into(),
+            StorageUnit::Events { previous, .. } => take(previous).into(),
+            StorageUnit::Sorted { values, .. } => take(values),
+            StorageUnit::Set(set) => set.take_all(),
+            StorageUnit::Bounded { values, .. } => take(values).into(),
+            _ => self.extract_many().unwrap_or_default(),
+        }
+    }
+
+    ///
+    /// Makes the values inserted since the last swap visible, dropping the
+    /// ones which were visible until now. This does nothing in the case
+    /// that the unit is not `Events`.
+    ///
+    pub fn swap_buffers(&mut self) {
+        if let StorageUnit::Events { current, previous } = self {
+            *previous = take(current).into();
+        }
+    }
+
     #[inline]
     pub fn rearrange_if_necessary(&mut self) {
         if let StorageUnit::Many(v) = self {
@@ -198,6 +690,56 @@ impl<T: Clone> Clone for StorageUnit<T> {
             StorageUnit::Nope => StorageUnit::Nope,
             StorageUnit::One(data) => StorageUnit::One(data.clone()),
             StorageUnit::Many(data) => StorageUnit::Many(data.clone()),
+            StorageUnit::Queue(data) => StorageUnit::Queue(data.clone()),
+            StorageUnit::Events { current, previous } => StorageUnit::Events {
+                current: current.clone(),
+                previous: previous.clone(),
+            },
+            StorageUnit::Sorted { values, order } => StorageUnit::Sorted {
+                values: values.clone(),
+                order: order.clone(),
+            },
+            StorageUnit::Set(set) => StorageUnit::Set(set.clone()),
+            StorageUnit::Bounded {
+                values,
+                capacity,
+                policy,
+            } => StorageUnit::Bounded {
+                values: values.clone(),
+                capacity: *capacity,
+                policy: *policy,
+            },
+        }
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for StorageUnit<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            StorageUnit::Nope => f.write_str("Nope"),
+            StorageUnit::One(data) => f.debug_tuple("One").field(data).finish(),
+            StorageUnit::Many(data) => f.debug_tuple("Many").field(data).finish(),
+            StorageUnit::Queue(data) => f.debug_tuple("Queue").field(data).finish(),
+            StorageUnit::Events { current, previous } => f
+                .debug_struct("Events")
+                .field("current", current)
+                .field("previous", previous)
+                .finish(),
+            StorageUnit::Sorted { values, .. } => f
+                .debug_struct("Sorted")
+                .field("values", values)
+                .finish_non_exhaustive(),
+            StorageUnit::Set(set) => f.debug_tuple("Set").field(&set.as_slice()).finish(),
+            StorageUnit::Bounded {
+                values,
+                capacity,
+                policy,
+            } => f
+                .debug_struct("Bounded")
+                .field("values", values)
+                .field("capacity", capacity)
+                .field("policy", policy)
+                .finish(),
         }
     }
 }
@@ -0,0 +1,134 @@
+use super::info::UnitState;
+use super::{Access, BlackBox, DynamicResult, ErrorDesc, FetchMultiple, Unit};
+use core::any::{type_name, TypeId};
+use core::fmt;
+use tracing::{debug, trace};
+
+// The state of a unit as shown in traces, which cannot be read while the
+// unit is borrowed mutably, or in the case that it has no `StorageUnit`.
+struct State(DynamicResult<UnitState>);
+
+impl fmt::Display for State {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self.0 {
+            Ok(state) => state.fmt(f),
+            Err(ErrorDesc::BorrowedIncompatibly) => f.write_str("borrowed"),
+            Err(_) => f.write_str("opaque"),
+        }
+    }
+}
+
+// The names and states of the units a fetch uses.
+struct Units<'a, U: ?Sized>(&'a BlackBox<U>, Vec<TypeId>);
+
+impl<U: ?Sized + for<'a> Unit<'a>> fmt::Display for Units<'_, U> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut units = self
+            .1
+            .iter()
+            .filter_map(|id| Some((self.0.info.get(id)?.name, self.0.state(id))));
+        if let Some((name, state)) = units.next() {
+            write!(f, "{}: {}", name, state)?;
+        }
+        for (name, state) in units {
+            write!(f, ", {}: {}", name, state)?;
+        }
+        Ok(())
+    }
+}
+
+impl<U: ?Sized + for<'a> Unit<'a>> BlackBox<U> {
+    fn state(&self, id: &TypeId) -> State {
+        State(self.unit_state(id))
+    }
+
+    ///
+    /// Emits an event for the allocation of the unit for `T`.
+    ///
+    pub(crate) fn trace_allocate<T: 'static>(&self) {
+        debug!(
+            target: "restor",
+            unit = type_name::<T>(),
+            state = %self.state(&TypeId::of::<T>()),
+            "allocated a unit"
+        );
+    }
+
+    ///
+    /// Emits an event for inserting `count` values of `T`, which failed with
+    /// `error` in the case that there is one.
+    ///
+    pub(crate) fn trace_insert<T: 'static>(&self, count: usize, error: Option<&ErrorDesc>) {
+        let id = TypeId::of::<T>();
+        match error {
+            None => trace!(
+                target: "restor",
+                unit = type_name::<T>(),
+                count,
+                state = %self.state(&id),
+                "inserted values"
+            ),
+            Some(error) => debug!(
+                target: "restor",
+                unit = type_name::<T>(),
+                count,
+                state = %self.state(&id),
+                ?error,
+                "failed to insert values"
+            ),
+        }
+    }
+
+    ///
+    /// Emits an event for each unit values were taken out of by fetching
+    /// `T`, or one for the fetch in the case that it failed.
+    ///
+    pub(crate) fn trace_fetch<'a, T: FetchMultiple<'a, U>, R>(&self, result: &DynamicResult<R>) {
+        let mut access = Access::new();
+        T::access(&mut access);
+        match result {
+            Ok(_) => {
+                for id in access.taken() {
+                    if let Some(info) = self.info.get(id) {
+                        trace!(
+                            target: "restor",
+                            unit = info.name,
+                            state = %self.state(id),
+                            "took values out of a unit"
+                        );
+                    }
+                }
+            }
+            Err(error) => debug!(
+                target: "restor",
+                fetch = type_name::<T>(),
+                units = %Units(self, access.used()),
+                ?error,
+                "failed to fetch"
+            ),
+        }
+    }
+
+    ///
+    /// Emits an event for taking values of `T` out of the storage using the
+    /// `take` family of functions.
+    ///
+    pub(crate) fn trace_take<T: 'static, R>(&self, result: &DynamicResult<R>) {
+        let id = TypeId::of::<T>();
+        match result {
+            Ok(_) => trace!(
+                target: "restor",
+                unit = type_name::<T>(),
+                state = %self.state(&id),
+                "took values out of a unit"
+            ),
+            Err(error) => debug!(
+                target: "restor",
+                unit = type_name::<T>(),
+                state = %self.state(&id),
+                ?error,
+                "failed to take values"
+            ),
+        }
+    }
+}
@@ -1,7 +1,10 @@
 use super::errors::*;
+use super::Signal;
+use alloc::boxed::Box;
+use core::any::{Any, TypeId};
+use core::ops::{Deref, DerefMut};
+#[cfg(feature = "parking_lot")]
 use parking_lot::{MappedMutexGuard, MappedRwLockReadGuard, MappedRwLockWriteGuard};
-use std::any::{Any, TypeId};
-use std::ops::{Deref, DerefMut};
 
 ///
 /// The type erasure trait for `restor`.
@@ -14,10 +17,20 @@ use std::ops::{Deref, DerefMut};
 ///   - [`Ref`] in the case of `DynamicStorage`
 ///   - [`MappedRwLockReadGuard`] in the case of `RwLockStorage`
 ///   - [`MappedMutexGuard`] in the case of `MutexStorage`
+///   - [`LockReadGuard`] in the case of storages over a [`RawLock`]
 /// - `MutBorrowed` which must deref_mut to a `dyn Any`
 ///   - [`RefMut`] in the case of `DynamicStorage`
 ///   - [`MappedRwLockWriteGuard`] in the case of `MutexStorage`
 ///   - [`MappedMutexGuard`] in the case of `MutexStorage`
+///   - [`LockWriteGuard`] in the case of storages over a [`RawLock`]
+///
+/// With the `lock_order` feature, the guards of `RwLockStorage` and
+/// `MutexStorage` are wrapped in an [`OrderedGuard`].
+///
+/// [`OrderedGuard`]: ../struct.OrderedGuard.html
+/// [`LockReadGuard`]: ../struct.LockReadGuard.html
+/// [`LockWriteGuard`]: ../struct.LockWriteGuard.html
+/// [`RawLock`]: ../trait.RawLock.html
 ///
 /// [`Ref`]: https://doc.rust-lang.org/std/cell/struct.Ref.html
 /// [`RefMut`]: https://doc.rust-lang.org/std/cell/struct.RefMut.html
@@ -53,10 +66,16 @@ pub trait Unit<'a> {
     ///
     fn storage_mut(&'a self) -> DynamicResult<Self::MutBorrowed>;
 
-    fn waiting_storage(&'a self) -> Self::Borrowed
+    ///
+    /// Waits for an immutable lock to the internal `StorageUnit<T>`
+    ///
+    fn waiting_storage(&'a self) -> DynamicResult<Self::Borrowed>
     where
         Self::Borrowed: Waitable;
-    fn waiting_storage_mut(&'a self) -> Self::MutBorrowed
+    ///
+    /// Waits for a mutable lock to the internal `StorageUnit<T>`
+    ///
+    fn waiting_storage_mut(&'a self) -> DynamicResult<Self::MutBorrowed>
     where
         Self::MutBorrowed: Waitable;
 
@@ -65,20 +84,40 @@ pub trait Unit<'a> {
     /// `StorageUnit<T>` (So the `TypeId` of `T`).
     ///
     fn id(&self) -> TypeId;
+
+    ///
+    /// Returns `self` as a `dyn Any`, so that the type erased unit can
+    /// be downcast back into its concrete type.
+    ///
+    fn as_any(&self) -> &dyn Any;
+
+    ///
+    /// Returns the [`Signal`] notified whenever values are inserted into
+    /// the unit, in the case that the unit can be waited on for values
+    /// using `waiting_take`.
+    ///
+    /// [`Signal`]: ../struct.Signal.html
+    ///
+    fn signal(&self) -> Option<&Signal> {
+        None
+    }
 }
 
 pub trait Waitable {}
 
+#[cfg(feature = "parking_lot")]
 impl<'b, T: ?Sized> Waitable for MappedMutexGuard<'b, T> {}
+#[cfg(feature = "parking_lot")]
 impl<'b, T: ?Sized> Waitable for MappedRwLockReadGuard<'b, T> {}
+#[cfg(feature = "parking_lot")]
 impl<'b, T: ?Sized> Waitable for MappedRwLockWriteGuard<'b, T> {}
 
-#[cfg(test)]
+#[cfg(all(test, feature = "parking_lot"))]
 mod tests {
     use super::Unit;
-    use crate::black_box::StorageUnit;
-    use crate::concurrent_black_box::RwLockUnit;
-    use std::any::TypeId;
+    use crate::black_box::{RefCellUnit, StorageUnit};
+    use crate::concurrent_black_box::{MutexUnit, RwLockUnit};
+    use core::any::TypeId;
 
     #[test]
     fn insert() {
@@ -102,6 +141,14 @@ mod tests {
         );
     }
 
+    #[test]
+    fn convert() {
+        let storage = RwLockUnit::new(StorageUnit::<usize>::new());
+        storage.insert_any(Box::new(vec![1usize, 2]));
+        let storage = MutexUnit::from(RefCellUnit::from(storage));
+        assert_eq!(storage.into_inner().many().unwrap(), &[1, 2]);
+    }
+
     #[test]
     fn id() {
         let storage = RwLockUnit::new(StorageUnit::<usize>::new());
@@ -0,0 +1,244 @@
+#[cfg(feature = "std")]
+use super::hasher::PassthroughHasherBuilder;
+use super::{BlackBox, DynamicResult, ErrorDesc, StorageUnit, Unit, UnitError};
+#[cfg(not(feature = "std"))]
+use alloc::collections::BTreeMap;
+use alloc::vec::Vec;
+use core::hash::{BuildHasher, Hash};
+#[cfg(not(feature = "std"))]
+use core::hash::{BuildHasherDefault, Hasher};
+use core::mem::take;
+#[cfg(feature = "std")]
+use std::collections::hash_map::RandomState;
+#[cfg(feature = "std")]
+use std::collections::HashMap;
+
+// The indices of the values which share a hash, keyed by that hash.
+#[cfg(feature = "std")]
+type Buckets = HashMap<u64, Vec<usize>, PassthroughHasherBuilder>;
+#[cfg(not(feature = "std"))]
+type Buckets = BTreeMap<u64, Vec<usize>>;
+
+// The hashes of the values are randomly seeded for each set, unless there
+// is no `std` to seed them with.
+#[cfg(feature = "std")]
+type HashState = RandomState;
+#[cfg(not(feature = "std"))]
+type HashState = BuildHasherDefault<FnvHasher>;
+
+///
+/// The values of a set unit, which holds at most one of each value.
+///
+/// The values are kept in a `Vec<T>` so that they can be borrowed as a
+/// slice, alongside an index of their hashes so that looking a value up
+/// doesn't require scanning all of them. Values are kept in the order
+/// they were inserted in, until one is removed, in which case the last
+/// value takes its place.
+///
+pub struct ValueSet<T> {
+    values: Vec<T>,
+    buckets: Buckets,
+    state: HashState,
+    hash: fn(&HashState, &T) -> u64,
+    eq: fn(&T, &T) -> bool,
+}
+
+///
+/// An FNV-1a hasher, which is used to hash the values of set units in the
+/// case that the `std` feature is disabled, as it needs no random seed.
+///
+#[cfg(not(feature = "std"))]
+struct FnvHasher(u64);
+
+#[cfg(not(feature = "std"))]
+impl Default for FnvHasher {
+    #[inline(always)]
+    fn default() -> Self {
+        FnvHasher(0xcbf2_9ce4_8422_2325)
+    }
+}
+
+#[cfg(not(feature = "std"))]
+impl Hasher for FnvHasher {
+    #[inline(always)]
+    fn finish(&self) -> u64 {
+        self.0
+    }
+    #[inline]
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 ^= u64::from(*byte);
+            self.0 = self.0.wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+}
+
+fn hash_value<T: Hash>(state: &HashState, value: &T) -> u64 {
+    state.hash_one(value)
+}
+
+impl<T> ValueSet<T> {
+    pub fn new() -> Self
+    where
+        T: Hash + Eq,
+    {
+        Self {
+            values: Vec::new(),
+            buckets: Buckets::default(),
+            state: HashState::default(),
+            hash: hash_value::<T>,
+            eq: <T as PartialEq>::eq,
+        }
+    }
+
+    pub fn as_slice(&self) -> &[T] {
+        &self.values
+    }
+
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    fn find(&self, hash: u64, value: &T) -> Option<usize> {
+        self.buckets
+            .get(&hash)?
+            .iter()
+            .copied()
+            .find(|&index| (self.eq)(&self.values[index], value))
+    }
+
+    pub fn contains(&self, value: &T) -> bool {
+        self.find((self.hash)(&self.state, value), value).is_some()
+    }
+
+    ///
+    /// Inserts `value`, returning it in the case that it is already present.
+    ///
+    pub fn insert(&mut self, value: T) -> Result<(), T> {
+        let hash = (self.hash)(&self.state, &value);
+        if self.find(hash, &value).is_some() {
+            return Err(value);
+        }
+        self.buckets
+            .entry(hash)
+            .or_default()
+            .push(self.values.len());
+        self.values.push(value);
+        Ok(())
+    }
+
+    ///
+    /// Removes the value equal to `value`, returning it in the case that it
+    /// is present.
+    ///
+    pub fn remove(&mut self, value: &T) -> Option<T> {
+        let hash = (self.hash)(&self.state, value);
+        let index = self.find(hash, value)?;
+        self.unindex(hash, index);
+        let last = self.values.len() - 1;
+        if index != last {
+            // The last value is moved into the place of the removed one.
+            let moved = (self.hash)(&self.state, &self.values[last]);
+            for i in self.buckets.get_mut(&moved).unwrap() {
+                if *i == last {
+                    *i = index;
+                }
+            }
+        }
+        Some(self.values.swap_remove(index))
+    }
+
+    ///
+    /// Removes the last value, which is the most recently inserted one in
+    /// the case that nothing has been removed since.
+    ///
+    pub fn pop(&mut self) -> Option<T> {
+        let value = self.values.pop()?;
+        self.unindex((self.hash)(&self.state, &value), self.values.len());
+        Some(value)
+    }
+
+    pub fn take_all(&mut self) -> Vec<T> {
+        self.buckets.clear();
+        take(&mut self.values)
+    }
+
+    fn unindex(&mut self, hash: u64, index: usize) {
+        let bucket = self.buckets.get_mut(&hash).unwrap();
+        bucket.retain(|&i| i != index);
+        if bucket.is_empty() {
+            self.buckets.remove(&hash);
+        }
+    }
+}
+
+impl<T: Hash + Eq> Default for ValueSet<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Clone> Clone for ValueSet<T> {
+    fn clone(&self) -> Self {
+        Self {
+            values: self.values.clone(),
+            buckets: self.buckets.clone(),
+            state: self.state.clone(),
+            hash: self.hash,
+            eq: self.eq,
+        }
+    }
+}
+
+impl<U: ?Sized + for<'a> Unit<'a>> BlackBox<U> {
+    ///
+    /// Checks whether the set unit for `T` contains `value`, without
+    /// scanning all of its values.
+    ///
+    /// This returns `UnitError::Unsupported` in the case that the unit for
+    /// `T` was not allocated using `allocate_set`.
+    ///
+    /// # Example
+    /// ```
+    /// # fn main() {
+    /// use restor::{DynamicStorage, ErrorDesc, UnitError};
+    /// let mut storage = DynamicStorage::new();
+    /// storage.allocate_set::<&'static str>();
+    /// storage.insert("admin").unwrap();
+    /// let (_, error) = storage.insert("admin").unwrap_err();
+    /// assert_eq!(error, ErrorDesc::Unit(UnitError::AlreadyPresent));
+    /// assert!(storage.contains(&"admin").unwrap());
+    /// assert_eq!(storage.remove_value(&"admin").unwrap(), "admin");
+    /// assert!(!storage.contains(&"admin").unwrap());
+    /// # }
+    /// ```
+    ///
+    pub fn contains<T: 'static>(&self, value: &T) -> DynamicResult<bool> {
+        let storage = self.unit_get::<T>()?.storage()?;
+        match storage.downcast_ref::<StorageUnit<T>>().unwrap() {
+            StorageUnit::Set(set) => Ok(set.contains(value)),
+            _ => Err(ErrorDesc::Unit(UnitError::Unsupported)),
+        }
+    }
+
+    ///
+    /// Removes the value equal to `value` from the set unit for `T`,
+    /// returning `UnitError::IsNope` in the case that it isn't present.
+    ///
+    /// Please refer to [`BlackBox::contains`] for the units which support
+    /// this.
+    ///
+    /// [`BlackBox::contains`]: #method.contains
+    ///
+    pub fn remove_value<T: 'static>(&self, value: &T) -> DynamicResult<T> {
+        let mut storage = self.unit_get::<T>()?.storage_mut()?;
+        match storage.downcast_mut::<StorageUnit<T>>().unwrap() {
+            StorageUnit::Set(set) => set.remove(value).ok_or(ErrorDesc::Unit(UnitError::IsNope)),
+            _ => Err(ErrorDesc::Unit(UnitError::Unsupported)),
+        }
+    }
+}
@@ -0,0 +1,403 @@
+use super::{StorageReadGuard, StorageWriteGuard};
+use crate::black_box::{DynamicResult, ErrorDesc, StorageUnit, Unit, UnitError};
+use alloc::{boxed::Box, vec::Vec};
+use core::any::{Any, TypeId};
+use core::cell::UnsafeCell;
+use core::hint::spin_loop;
+use core::mem::{size_of, transmute_copy, MaybeUninit};
+use core::ptr;
+#[cfg(target_has_atomic = "64")]
+use core::sync::atomic::AtomicU64;
+use core::sync::atomic::{fence, AtomicU16, AtomicU32, AtomicU8, AtomicUsize, Ordering};
+
+///
+/// A lock-free unit for small `Copy` values, which can only be loaded
+/// and stored, but never borrowed.
+///
+/// Values of 1, 2, 4 or 8 bytes are kept in an `AtomicU8`, `AtomicU16`,
+/// `AtomicU32` or `AtomicU64` of the same size, so loading, storing and
+/// updating them is a single atomic operation. Only the first store into
+/// the unit waits for any other first store to finish.
+///
+/// Larger values are guarded by a sequence lock instead: writers bump the
+/// sequence to an odd number while they write, and readers retry in the
+/// case that the sequence changed while they were copying the value out.
+/// This means that readers never block writers, and neither ever park
+/// the thread.
+///
+/// This is allocated in a [`RwLockStorage`](crate::RwLockStorage) using
+/// `allocate_atomic`, and interacted with using `load`, `store` and
+/// `update`. Any attempt at borrowing the contents of the unit returns
+/// `UnitError::Unsupported`.
+///
+pub struct AtomicUnit<T> {
+    slot: Slot<T>,
+    // Whether an atomic slot holds a value, which is one of the below.
+    state: AtomicU8,
+}
+
+const EMPTY: u8 = 0;
+const STORING: u8 = 1;
+const FULL: u8 = 2;
+
+// Where the value is kept, which is an atomic of the same size in the case
+// that there is one.
+enum Slot<T> {
+    U8(AtomicU8),
+    U16(AtomicU16),
+    U32(AtomicU32),
+    #[cfg(target_has_atomic = "64")]
+    U64(AtomicU64),
+    Sequenced {
+        sequence: AtomicUsize,
+        value: UnsafeCell<Option<T>>,
+    },
+}
+
+// Runs `$body` with `$atomic` bound to the atomic slot and `$int` to the
+// integer it holds, or returns `$sequenced` in the case of a sequence lock.
+macro_rules! with_atomic {
+    ($slot:expr, |$atomic:ident: $int:ident| $body:expr, $sequenced:expr) => {
+        match $slot {
+            Slot::U8($atomic) => {
+                type $int = u8;
+                $body
+            }
+            Slot::U16($atomic) => {
+                type $int = u16;
+                $body
+            }
+            Slot::U32($atomic) => {
+                type $int = u32;
+                $body
+            }
+            #[cfg(target_has_atomic = "64")]
+            Slot::U64($atomic) => {
+                type $int = u64;
+                $body
+            }
+            Slot::Sequenced { .. } => $sequenced,
+        }
+    };
+}
+
+// The value is only ever copied out, or written while holding the
+// sequence lock or through an atomic, so sharing it only requires that it
+// be `Send`.
+unsafe impl<T: Copy + Send> Sync for AtomicUnit<T> {}
+
+impl<T: Copy> AtomicUnit<T> {
+    pub fn new() -> Self {
+        let slot = match size_of::<T>() {
+            1 => Slot::U8(AtomicU8::new(0)),
+            2 => Slot::U16(AtomicU16::new(0)),
+            4 => Slot::U32(AtomicU32::new(0)),
+            #[cfg(target_has_atomic = "64")]
+            8 => Slot::U64(AtomicU64::new(0)),
+            _ => Slot::Sequenced {
+                sequence: AtomicUsize::new(0),
+                value: UnsafeCell::new(None),
+            },
+        };
+        Self {
+            slot,
+            state: AtomicU8::new(EMPTY),
+        }
+    }
+
+    ///
+    /// Returns a copy of the stored value, if there is one.
+    ///
+    pub fn load(&self) -> Option<T> {
+        with_atomic!(
+            &self.slot,
+            |atomic: Int| {
+                if self.state.load(Ordering::Acquire) != FULL {
+                    return None;
+                }
+                // Safety: The slot is the same size as `T`, and only ever
+                // holds the bits of a `T`.
+                Some(unsafe { transmute_copy::<Int, T>(&atomic.load(Ordering::Acquire)) })
+            },
+            loop {
+                let sequence = self.sequence().load(Ordering::Acquire);
+                if let Some(value) = self.read(sequence) {
+                    return value;
+                }
+                spin_loop();
+            }
+        )
+    }
+
+    ///
+    /// Stores `value`, returning the previously stored value.
+    ///
+    pub fn store(&self, value: T) -> Option<T> {
+        with_atomic!(
+            &self.slot,
+            |atomic: Int| {
+                // Safety: The slot is the same size as `T`.
+                let bits = unsafe { transmute_copy::<T, Int>(&value) };
+                if self.fill(|| atomic.store(bits, Ordering::Release)) {
+                    return None;
+                }
+                let previous = atomic.swap(bits, Ordering::AcqRel);
+                // Safety: The slot only ever holds the bits of a `T`.
+                Some(unsafe { transmute_copy::<Int, T>(&previous) })
+            },
+            {
+                let sequence = self.lock();
+                // Safety: We hold the sequence lock, so no one else is writing.
+                let previous = unsafe { ptr::replace(self.value(), Some(value)) };
+                self.sequence()
+                    .store(sequence.wrapping_add(2), Ordering::Release);
+                previous
+            }
+        )
+    }
+
+    ///
+    /// Replaces the stored value with the result of `f`, returning the
+    /// previous value, or `None` in the case that there is no value.
+    ///
+    /// Much like `AtomicUsize::fetch_update`, `f` may be called more than
+    /// once in the case that another thread stored a value in between.
+    ///
+    pub fn update<F: FnMut(T) -> T>(&self, mut f: F) -> Option<T> {
+        with_atomic!(
+            &self.slot,
+            |atomic: Int| {
+                if self.state.load(Ordering::Acquire) != FULL {
+                    return None;
+                }
+                let mut bits = atomic.load(Ordering::Acquire);
+                loop {
+                    // Safety: The slot is the same size as `T`, and only ever
+                    // holds the bits of a `T`.
+                    let current = unsafe { transmute_copy::<Int, T>(&bits) };
+                    let new = unsafe { transmute_copy::<T, Int>(&f(current)) };
+                    match atomic.compare_exchange_weak(
+                        bits,
+                        new,
+                        Ordering::AcqRel,
+                        Ordering::Acquire,
+                    ) {
+                        Ok(_) => return Some(current),
+                        Err(actual) => bits = actual,
+                    }
+                }
+            },
+            loop {
+                let sequence = self.sequence().load(Ordering::Acquire);
+                let current = match self.read(sequence) {
+                    Some(Some(current)) => current,
+                    Some(None) => return None,
+                    None => {
+                        spin_loop();
+                        continue;
+                    }
+                };
+                let new = f(current);
+                if self
+                    .sequence()
+                    .compare_exchange_weak(
+                        sequence,
+                        sequence.wrapping_add(1),
+                        Ordering::Acquire,
+                        Ordering::Relaxed,
+                    )
+                    .is_ok()
+                {
+                    fence(Ordering::Release);
+                    // Safety: We hold the sequence lock, so no one else is writing.
+                    unsafe { *self.value() = Some(new) };
+                    self.sequence()
+                        .store(sequence.wrapping_add(2), Ordering::Release);
+                    return Some(current);
+                }
+            }
+        )
+    }
+
+    ///
+    /// Runs `store` in the case that an atomic slot is empty, returning
+    /// whether it was run. A store racing with the first one waits for it
+    /// to finish, so that it returns the value which was first stored.
+    ///
+    fn fill(&self, store: impl FnOnce()) -> bool {
+        if self.state.load(Ordering::Acquire) == FULL {
+            return false;
+        }
+        if self
+            .state
+            .compare_exchange(EMPTY, STORING, Ordering::Acquire, Ordering::Acquire)
+            .is_ok()
+        {
+            store();
+            self.state.store(FULL, Ordering::Release);
+            return true;
+        }
+        while self.state.load(Ordering::Acquire) != FULL {
+            spin_loop();
+        }
+        false
+    }
+
+    fn sequence(&self) -> &AtomicUsize {
+        match &self.slot {
+            Slot::Sequenced { sequence, .. } => sequence,
+            _ => unreachable!("The value is kept in an atomic"),
+        }
+    }
+
+    fn value(&self) -> *mut Option<T> {
+        match &self.slot {
+            Slot::Sequenced { value, .. } => value.get(),
+            _ => unreachable!("The value is kept in an atomic"),
+        }
+    }
+
+    ///
+    /// Copies the value out of the sequence lock, returning `None` in the
+    /// case that it may have been written to since the sequence was
+    /// `sequence`.
+    ///
+    fn read(&self, sequence: usize) -> Option<Option<T>> {
+        if sequence & 1 == 1 {
+            return None;
+        }
+        // Safety: This may race with a writer, so it is read as possibly
+        // uninitialized and only assumed to be valid once we know that no
+        // writer has touched it since we started reading.
+        let value = unsafe { ptr::read_volatile(self.value() as *const MaybeUninit<Option<T>>) };
+        fence(Ordering::Acquire);
+        if self.sequence().load(Ordering::Relaxed) == sequence {
+            Some(unsafe { value.assume_init() })
+        } else {
+            None
+        }
+    }
+
+    ///
+    /// Spins until the sequence lock is acquired, returning the sequence
+    /// it was acquired at.
+    ///
+    fn lock(&self) -> usize {
+        let sequence_lock = self.sequence();
+        loop {
+            let sequence = sequence_lock.load(Ordering::Relaxed);
+            if sequence & 1 == 0
+                && sequence_lock
+                    .compare_exchange_weak(
+                        sequence,
+                        sequence.wrapping_add(1),
+                        Ordering::Acquire,
+                        Ordering::Relaxed,
+                    )
+                    .is_ok()
+            {
+                fence(Ordering::Release);
+                return sequence;
+            }
+            spin_loop();
+        }
+    }
+
+    fn insert(&self, new: Box<dyn Any>) -> Option<(Box<dyn Any>, ErrorDesc)>
+    where
+        T: 'static,
+    {
+        if new.is::<T>() {
+            self.store(*new.downcast::<T>().unwrap());
+            None
+        } else if new.is::<Vec<T>>() {
+            Some((new, ErrorDesc::Unit(UnitError::Unsupported)))
+        } else {
+            Some((new, ErrorDesc::NoMatchingType))
+        }
+    }
+}
+
+impl<T: Copy> Default for AtomicUnit<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<'a, T: 'static + Copy + Send + Sync> Unit<'a> for AtomicUnit<T> {
+    type Borrowed = StorageReadGuard<'a>;
+    type MutBorrowed = StorageWriteGuard<'a>;
+    fn insert_any(&self, new: Box<dyn Any>) -> Option<(Box<dyn Any>, ErrorDesc)> {
+        self.insert(new)
+    }
+    fn waiting_insert(&self, new: Box<dyn Any>) -> Option<(Box<dyn Any>, ErrorDesc)> {
+        self.insert(new)
+    }
+    fn storage(&'a self) -> DynamicResult<StorageReadGuard<'a>> {
+        Err(ErrorDesc::Unit(UnitError::Unsupported))
+    }
+    fn storage_mut(&'a self) -> DynamicResult<StorageWriteGuard<'a>> {
+        Err(ErrorDesc::Unit(UnitError::Unsupported))
+    }
+    fn waiting_storage(&'a self) -> DynamicResult<StorageReadGuard<'a>> {
+        Err(ErrorDesc::Unit(UnitError::Unsupported))
+    }
+    fn waiting_storage_mut(&'a self) -> DynamicResult<StorageWriteGuard<'a>> {
+        Err(ErrorDesc::Unit(UnitError::Unsupported))
+    }
+
+    fn id(&self) -> TypeId {
+        TypeId::of::<T>()
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+///
+/// Moves the value of `source` into `dest` when merging storages, where
+/// either of them is an `AtomicUnit<T>`.
+///
+/// A value moved into an atomic unit replaces the one in it. In the case
+/// that the other unit holds more than one value, this returns
+/// `UnitError::IsNotOne` and leaves both units untouched, and in the case
+/// that the other unit cannot hold the value, such as a full bounded unit,
+/// the value is left in the atomic unit.
+///
+pub(crate) fn append_atomic_unit<T: 'static + Copy, U: ?Sized + for<'a> Unit<'a>>(
+    source: &U,
+    dest: &U,
+) -> DynamicResult<()> {
+    if let Some(source) = source.as_any().downcast_ref::<AtomicUnit<T>>() {
+        let value = match source.load() {
+            Some(value) => value,
+            None => return Ok(()),
+        };
+        return match dest.as_any().downcast_ref::<AtomicUnit<T>>() {
+            Some(dest) => {
+                dest.store(value);
+                Ok(())
+            }
+            None => dest
+                .storage_mut()?
+                .downcast_mut::<StorageUnit<T>>()
+                .unwrap()
+                .try_insert(value)
+                .map_err(|(_, e)| e),
+        };
+    }
+    let dest = dest
+        .as_any()
+        .downcast_ref::<AtomicUnit<T>>()
+        .ok_or(ErrorDesc::Unit(UnitError::Unsupported))?;
+    let mut source = source.storage_mut()?;
+    let source = source.downcast_mut::<StorageUnit<T>>().unwrap();
+    if source.len() > 1 {
+        return Err(ErrorDesc::Unit(UnitError::IsNotOne));
+    }
+    if let Ok(value) = source.extract_one() {
+        dest.store(value);
+    }
+    Ok(())
+}
@@ -0,0 +1,130 @@
+use super::atomic_unit::{append_atomic_unit, AtomicUnit};
+use super::rcu_unit::{append_rcu_unit, RcuUnit};
+use super::RwLockStorage;
+use crate::black_box::{DynamicResult, ErrorDesc, UnitError};
+use alloc::boxed::Box;
+use alloc::sync::Arc;
+use core::any::Any;
+
+// The units of `RwLockStorage` which are not borrowed, but are instead
+// copied out of or published to.
+impl RwLockStorage {
+    ///
+    /// Adds a lock-free [`AtomicUnit`] for the given type, which can then
+    /// be used with `load`, `store` and `update` instead of being borrowed.
+    ///
+    /// This will not add another unit in the case that it already exists,
+    /// even if the existing unit is not atomic.
+    ///
+    /// # Example
+    /// ```
+    /// # fn main() {
+    /// use restor::RwLockStorage;
+    /// let mut storage = RwLockStorage::new();
+    /// storage.allocate_atomic::<usize>();
+    /// storage.store(1usize).unwrap();
+    /// assert_eq!(storage.update::<usize, _>(|x| x + 1).unwrap(), 1);
+    /// assert_eq!(storage.load::<usize>().unwrap(), 2);
+    /// assert!(storage.get::<&usize>().is_err());
+    /// # }
+    /// ```
+    ///
+    pub fn allocate_atomic<T: Copy + Send + Sync + Any>(&mut self) {
+        self.black_box.allocate_with::<T>(
+            || Box::new(AtomicUnit::<T>::new()),
+            append_atomic_unit::<T, _>,
+        );
+    }
+
+    ///
+    /// Returns a copy of the value stored in the atomic unit for `T`.
+    ///
+    /// This returns `UnitError::IsNope` in the case that no value has been
+    /// stored, and `UnitError::Unsupported` in the case that the unit for
+    /// `T` was not allocated using `allocate_atomic`.
+    ///
+    pub fn load<T: Copy + Send + Sync + Any>(&self) -> DynamicResult<T> {
+        self.atomic_unit::<T>()?
+            .load()
+            .ok_or(ErrorDesc::Unit(UnitError::IsNope))
+    }
+
+    ///
+    /// Stores a value in the atomic unit for `T`, replacing the previous
+    /// one, without taking any locks.
+    ///
+    pub fn store<T: Copy + Send + Sync + Any>(&self, data: T) -> DynamicResult<()> {
+        self.atomic_unit::<T>()?.store(data);
+        Ok(())
+    }
+
+    ///
+    /// Replaces the value in the atomic unit for `T` with the result of
+    /// `f`, and returns the previous value.
+    ///
+    /// `f` may be called more than once in the case that another thread
+    /// stores a value at the same time. This returns `UnitError::IsNope`
+    /// without calling `f` in the case that no value has been stored.
+    ///
+    pub fn update<T: Copy + Send + Sync + Any, F: FnMut(T) -> T>(&self, f: F) -> DynamicResult<T> {
+        self.atomic_unit::<T>()?
+            .update(f)
+            .ok_or(ErrorDesc::Unit(UnitError::IsNope))
+    }
+
+    fn atomic_unit<T: Copy + Send + Sync + Any>(&self) -> DynamicResult<&AtomicUnit<T>> {
+        self.black_box
+            .unit_get::<T>()?
+            .as_any()
+            .downcast_ref::<AtomicUnit<T>>()
+            .ok_or(ErrorDesc::Unit(UnitError::Unsupported))
+    }
+
+    ///
+    /// Adds a read-copy-update [`RcuUnit`] for the given type, which can
+    /// then be read using the [`Snapshot`](crate::Snapshot) fetch kind, and
+    /// written to using `replace` and `rcu`.
+    ///
+    /// This will not add another unit in the case that it already exists,
+    /// even if the existing unit is not an rcu unit.
+    ///
+    pub fn allocate_rcu<T: Send + Sync + Any>(&mut self) {
+        self.black_box
+            .allocate_with::<T>(|| Box::new(RcuUnit::<T>::new()), append_rcu_unit::<T, _>);
+    }
+
+    ///
+    /// Publishes `data` as the value of the rcu unit for `T`. Snapshots
+    /// taken before this keep seeing the previous value.
+    ///
+    /// This returns `UnitError::Unsupported` in the case that the unit
+    /// for `T` was not allocated using `allocate_rcu`.
+    ///
+    pub fn replace<T: Send + Sync + Any>(&self, data: T) -> DynamicResult<()> {
+        self.rcu_unit::<T>()?.replace(Arc::new(data));
+        Ok(())
+    }
+
+    ///
+    /// Publishes the result of running `f` on the current value of the rcu
+    /// unit for `T`, returning the snapshot that `f` was run on.
+    ///
+    /// `f` is run without holding any locks, so it may be run more than
+    /// once in the case that another value is published at the same time.
+    /// This returns `UnitError::IsNope` without running `f` in the case
+    /// that no value has been published.
+    ///
+    pub fn rcu<T: Send + Sync + Any, F: FnMut(&T) -> T>(&self, f: F) -> DynamicResult<Arc<T>> {
+        self.rcu_unit::<T>()?
+            .rcu(f)
+            .ok_or(ErrorDesc::Unit(UnitError::IsNope))
+    }
+
+    fn rcu_unit<T: Send + Sync + Any>(&self) -> DynamicResult<&RcuUnit<T>> {
+        self.black_box
+            .unit_get::<T>()?
+            .as_any()
+            .downcast_ref::<RcuUnit<T>>()
+            .ok_or(ErrorDesc::Unit(UnitError::Unsupported))
+    }
+}
@@ -0,0 +1,248 @@
+use crate::black_box::StorageUnit;
+#[cfg(feature = "parking_lot")]
+use crate::black_box::{Map, MapMut, Waitable};
+use std::any::type_name;
+use std::cell::RefCell;
+#[cfg(feature = "parking_lot")]
+use std::ops::{Deref, DerefMut};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Mutex, MutexGuard, PoisonError};
+
+static NEXT_NODE: AtomicUsize = AtomicUsize::new(0);
+
+// Each pair of locks where the second was waited on while the first was held,
+// which is the order those locks must always be acquired in.
+static ORDER: Mutex<Vec<(Held, Held)>> = Mutex::new(Vec::new());
+
+thread_local! {
+    // The locks held by the current thread, oldest first.
+    static HELD: RefCell<Vec<Holding>> = const { RefCell::new(Vec::new()) };
+}
+
+#[derive(Clone, Copy)]
+struct Held {
+    id: usize,
+    name: &'static str,
+}
+
+// A lock held by the current thread, and whether it is held exclusively.
+struct Holding {
+    lock: Held,
+    exclusive: bool,
+}
+
+fn order() -> MutexGuard<'static, Vec<(Held, Held)>> {
+    ORDER.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+///
+/// A lock in the lock order, named after the type it guards. The lock is
+/// forgotten about once this is dropped.
+///
+pub(crate) struct Node {
+    lock: Held,
+}
+
+impl Node {
+    pub(crate) fn new<T>() -> Self {
+        // Units guard a `StorageUnit<T>`, so they are named after `T` instead.
+        let unit = type_name::<StorageUnit<()>>();
+        let name = type_name::<T>();
+        let name = name
+            .strip_prefix(&unit[..unit.len() - "()>".len()])
+            .and_then(|x| x.strip_suffix('>'))
+            .unwrap_or(name);
+        Node {
+            lock: Held {
+                id: NEXT_NODE.fetch_add(1, Ordering::Relaxed),
+                name,
+            },
+        }
+    }
+
+    ///
+    /// Records that the current thread is about to wait on this lock, and
+    /// panics instead in the case that it could deadlock, which is when the
+    /// locks the thread holds have been waited on while this one was held
+    /// before, or when it already holds this lock exclusively.
+    ///
+    pub(crate) fn wait(&self, exclusive: bool) {
+        let this = self.lock;
+        let cycle = HELD.with(|held| {
+            let held = held.borrow();
+            if held
+                .iter()
+                .any(|x| x.lock.id == this.id && (exclusive || x.exclusive))
+            {
+                return Some(vec![this.name, this.name]);
+            }
+            let mut order = order();
+            for before in held.iter().map(|x| x.lock).filter(|x| x.id != this.id) {
+                if let Some(mut path) = find_path(&order, this.id, before.id) {
+                    path.insert(0, before.name);
+                    return Some(path);
+                }
+                if !order
+                    .iter()
+                    .any(|(x, y)| x.id == before.id && y.id == this.id)
+                {
+                    order.push((before, this));
+                }
+            }
+            None
+        });
+        if let Some(cycle) = cycle {
+            let cycle = cycle.iter().map(|x| format!("`{}`", x)).collect::<Vec<_>>();
+            panic!(
+                "Waiting on the lock for `{}` while holding the one for {} could deadlock, \
+                 as the locks would be acquired in the cycle {}",
+                this.name,
+                cycle[0],
+                cycle.join(" -> ")
+            );
+        }
+    }
+
+    ///
+    /// Records that the current thread now holds this lock.
+    ///
+    pub(crate) fn acquired(&self, exclusive: bool) {
+        HELD.with(|held| {
+            held.borrow_mut().push(Holding {
+                lock: self.lock,
+                exclusive,
+            })
+        });
+    }
+
+    ///
+    /// Records that the current thread no longer holds this lock.
+    ///
+    pub(crate) fn released(&self) {
+        // This may be called while the thread is exiting, in which case
+        // there is nothing left to record.
+        let _ = HELD.try_with(|held| {
+            let mut held = held.borrow_mut();
+            if let Some(i) = held.iter().rposition(|x| x.lock.id == self.lock.id) {
+                held.remove(i);
+            }
+        });
+    }
+}
+
+impl Drop for Node {
+    fn drop(&mut self) {
+        let id = self.lock.id;
+        order().retain(|(x, y)| x.id != id && y.id != id);
+    }
+}
+
+///
+/// A guard of a lock which isn't built on a [`RawLock`], such as the ones of
+/// `parking_lot`, which records that the lock was released in the case that
+/// it is dropped, the same way the guards of a [`LockUnit`] do. This derefs
+/// to whatever the guard it wraps derefs to, and can be mapped the same way.
+///
+/// The units of `RwLockStorage` and `MutexStorage` borrow their values as
+/// these with the `lock_order` feature.
+///
+/// [`RawLock`]: ./trait.RawLock.html
+/// [`LockUnit`]: ./struct.LockUnit.html
+///
+#[cfg(feature = "parking_lot")]
+pub struct OrderedGuard<'a, G> {
+    // The release is recorded before the guard releases the lock, as it is
+    // dropped first.
+    release: Release<'a>,
+    guard: G,
+}
+
+#[cfg(feature = "parking_lot")]
+struct Release<'a>(&'a Node);
+
+#[cfg(feature = "parking_lot")]
+impl Drop for Release<'_> {
+    fn drop(&mut self) {
+        self.0.released();
+    }
+}
+
+#[cfg(feature = "parking_lot")]
+impl<'a, G> OrderedGuard<'a, G> {
+    ///
+    /// Records that the current thread now holds the lock of `node`, which
+    /// `guard` was just acquired from.
+    ///
+    pub(crate) fn new(node: &'a Node, exclusive: bool, guard: G) -> Self {
+        node.acquired(exclusive);
+        Self {
+            release: Release(node),
+            guard,
+        }
+    }
+}
+
+#[cfg(feature = "parking_lot")]
+impl<G: Deref> Deref for OrderedGuard<'_, G> {
+    type Target = G::Target;
+    fn deref(&self) -> &G::Target {
+        &self.guard
+    }
+}
+
+#[cfg(feature = "parking_lot")]
+impl<G: DerefMut> DerefMut for OrderedGuard<'_, G> {
+    fn deref_mut(&mut self) -> &mut G::Target {
+        &mut self.guard
+    }
+}
+
+#[cfg(feature = "parking_lot")]
+impl<'a, I: ?Sized, O: ?Sized, G: Map<I, O>> Map<I, O> for OrderedGuard<'a, G> {
+    type Output = OrderedGuard<'a, G::Output>;
+    type Func = G::Func;
+    fn map(self, f: &Self::Func) -> OrderedGuard<'a, G::Output> {
+        OrderedGuard {
+            release: self.release,
+            guard: self.guard.map(f),
+        }
+    }
+}
+
+#[cfg(feature = "parking_lot")]
+impl<'a, I: ?Sized, O: ?Sized, G: MapMut<I, O>> MapMut<I, O> for OrderedGuard<'a, G> {
+    type Output = OrderedGuard<'a, G::Output>;
+    type Func = G::Func;
+    fn map(self, f: &Self::Func) -> OrderedGuard<'a, G::Output> {
+        OrderedGuard {
+            release: self.release,
+            guard: self.guard.map(f),
+        }
+    }
+}
+
+#[cfg(feature = "parking_lot")]
+impl<G: Waitable> Waitable for OrderedGuard<'_, G> {}
+
+// Finds the locks from `from` to `to` in the lock order, including both.
+fn find_path(order: &[(Held, Held)], from: usize, to: usize) -> Option<Vec<&'static str>> {
+    let mut visited = vec![from];
+    let mut stack = vec![(from, Vec::new())];
+    while let Some((id, path)) = stack.pop() {
+        for (x, y) in order.iter().filter(|(x, _)| x.id == id) {
+            let mut path = path.clone();
+            if path.is_empty() {
+                path.push(x.name);
+            }
+            path.push(y.name);
+            if y.id == to {
+                return Some(path);
+            }
+            if !visited.contains(&y.id) {
+                visited.push(y.id);
+                stack.push((y.id, path));
+            }
+        }
+    }
+    None
+}
@@ -0,0 +1,125 @@
+use super::raw_lock::{SpinMutexBackend, SpinRwLockBackend};
+#[cfg(feature = "std")]
+use super::raw_lock::{StdMutexBackend, StdRwLockBackend};
+use crate::define_storage;
+// The documentation of the functions of the storages links to `BlackBox`'s.
+#[allow(unused_imports)]
+use crate::BlackBox;
+
+#[cfg(feature = "std")]
+define_storage! {
+    ///
+    /// The storage with interior mutability based on locks which allow many
+    /// readers, built only on `std::sync` using the [`StdRwLockBackend`].
+    ///
+    /// > ## Please note!
+    /// > The documentation for the functions implemented for this type are
+    /// > found in [`BlackBox`]'s documentation under the same name.
+    ///
+    /// This is what `RwLockStorage` is in the case that the `parking_lot`
+    /// feature is disabled, and only allows for allocation of
+    /// `T: Send + Sync + Any` units.
+    ///
+    /// [`BlackBox`]: ./struct.BlackBox.html
+    /// [`StdRwLockBackend`]: ./struct.StdRwLockBackend.html
+    ///
+    pub struct StdRwLockStorage(StdRwLockBackend);
+}
+
+#[cfg(feature = "std")]
+define_storage! {
+    ///
+    /// The storage with interior mutability based on locks which only allow
+    /// a single holder, built only on `std::sync` using the
+    /// [`StdMutexBackend`].
+    ///
+    /// > ## Please note!
+    /// > The documentation for the functions implemented for this type are
+    /// > found in [`BlackBox`]'s documentation under the same name.
+    ///
+    /// This is what `MutexStorage` is in the case that the `parking_lot`
+    /// feature is disabled, and only allows for allocation of `T: Send + Any`
+    /// units.
+    ///
+    /// [`BlackBox`]: ./struct.BlackBox.html
+    /// [`StdMutexBackend`]: ./struct.StdMutexBackend.html
+    ///
+    pub struct StdMutexStorage(StdMutexBackend);
+}
+
+define_storage! {
+    ///
+    /// The storage with interior mutability based on spin locks which allow
+    /// many readers, built on the [`SpinRwLockBackend`].
+    ///
+    /// > ## Please note!
+    /// > The documentation for the functions implemented for this type are
+    /// > found in [`BlackBox`]'s documentation under the same name.
+    ///
+    /// This is what `RwLockStorage` is in the case that the `std` feature is
+    /// disabled, and only allows for allocation of `T: Send + Sync + Any`
+    /// units.
+    ///
+    /// [`BlackBox`]: ./struct.BlackBox.html
+    /// [`SpinRwLockBackend`]: ./struct.SpinRwLockBackend.html
+    ///
+    pub struct SpinRwLockStorage(SpinRwLockBackend);
+}
+
+define_storage! {
+    ///
+    /// The storage with interior mutability based on spin locks which only
+    /// allow a single holder, built on the [`SpinMutexBackend`].
+    ///
+    /// > ## Please note!
+    /// > The documentation for the functions implemented for this type are
+    /// > found in [`BlackBox`]'s documentation under the same name.
+    ///
+    /// This is what `MutexStorage` is in the case that the `std` feature is
+    /// disabled, and only allows for allocation of `T: Send + Any` units.
+    ///
+    /// [`BlackBox`]: ./struct.BlackBox.html
+    /// [`SpinMutexBackend`]: ./struct.SpinMutexBackend.html
+    ///
+    pub struct SpinMutexStorage(SpinMutexBackend);
+}
+
+///
+/// The storage with interior mutability based on locks which allow many
+/// readers, which is the [`StdRwLockStorage`] in the case that the
+/// `parking_lot` feature is disabled.
+///
+/// [`StdRwLockStorage`]: ./struct.StdRwLockStorage.html
+///
+#[cfg(all(feature = "std", not(feature = "parking_lot")))]
+pub type RwLockStorage = StdRwLockStorage;
+
+///
+/// The storage with interior mutability based on locks which only allow a
+/// single holder, which is the [`StdMutexStorage`] in the case that the
+/// `parking_lot` feature is disabled.
+///
+/// [`StdMutexStorage`]: ./struct.StdMutexStorage.html
+///
+#[cfg(all(feature = "std", not(feature = "parking_lot")))]
+pub type MutexStorage = StdMutexStorage;
+
+///
+/// The storage with interior mutability based on locks which allow many
+/// readers, which is the [`SpinRwLockStorage`] in the case that the `std`
+/// feature is disabled.
+///
+/// [`SpinRwLockStorage`]: ./struct.SpinRwLockStorage.html
+///
+#[cfg(not(feature = "std"))]
+pub type RwLockStorage = SpinRwLockStorage;
+
+///
+/// The storage with interior mutability based on locks which only allow a
+/// single holder, which is the [`SpinMutexStorage`] in the case that the
+/// `std` feature is disabled.
+///
+/// [`SpinMutexStorage`]: ./struct.SpinMutexStorage.html
+///
+#[cfg(not(feature = "std"))]
+pub type MutexStorage = SpinMutexStorage;
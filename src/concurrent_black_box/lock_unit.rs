@@ -0,0 +1,336 @@
+#[cfg(feature = "lock_order")]
+use super::lock_order::Node;
+#[cfg(feature = "lock_order")]
+use super::raw_lock::Exclusive;
+use super::raw_lock::{Guardable, RawLock};
+use crate::black_box::{
+    DynamicResult, ErrorDesc, Map, MapMut, Signal, StorageUnit, Unit, Waitable,
+};
+use alloc::{boxed::Box, vec::Vec};
+use core::any::{Any, TypeId};
+use core::cell::UnsafeCell;
+use core::fmt;
+use core::marker::PhantomData;
+use core::mem::forget;
+use core::ops::{Deref, DerefMut};
+use core::ptr::NonNull;
+
+///
+/// The part of a [`LockUnit`] needed to release its lock, which is what its
+/// guards hold on to. This erases the type of the lock, so that units with
+/// different backends can be stored side by side.
+///
+trait Unlock {
+    unsafe fn unlock_shared(&self);
+    unsafe fn unlock_exclusive(&self);
+}
+
+impl<R: RawLock, T> Unlock for LockUnit<R, T> {
+    unsafe fn unlock_shared(&self) {
+        #[cfg(feature = "lock_order")]
+        self.node.released();
+        self.raw.unlock_shared()
+    }
+    unsafe fn unlock_exclusive(&self) {
+        #[cfg(feature = "lock_order")]
+        self.node.released();
+        self.raw.unlock_exclusive()
+    }
+}
+
+///
+/// A unit guarded by any [`RawLock`], whose guards do not depend on the
+/// type of the lock.
+///
+/// This is what allows a [`MixedStorage`](crate::MixedStorage) to pick the
+/// lock used for each type separately.
+///
+pub struct LockUnit<R, T> {
+    raw: R,
+    data: UnsafeCell<T>,
+    inserted: Signal,
+    #[cfg(feature = "lock_order")]
+    node: Node,
+}
+
+///
+/// The type erased [`LockUnit`] shared by every storage over a [`RawLock`],
+/// which means that any two such storages can be used together, such as
+/// when using `transfer` or `try_clone_into`.
+///
+pub type DynLockUnit = dyn for<'a> Unit<
+        'a,
+        Borrowed = LockReadGuard<'a, dyn Any>,
+        MutBorrowed = LockWriteGuard<'a, dyn Any>,
+    > + Send
+    + Sync;
+
+unsafe impl<R: RawLock, T: Guardable<R::Kind>> Sync for LockUnit<R, StorageUnit<T>> {}
+
+impl<R: RawLock, T> LockUnit<R, T> {
+    pub fn new(data: T) -> Self {
+        Self {
+            raw: R::new(),
+            data: UnsafeCell::new(data),
+            inserted: Signal::new(),
+            #[cfg(feature = "lock_order")]
+            node: Node::new::<T>(),
+        }
+    }
+    pub fn into_inner(self) -> T {
+        self.data.into_inner()
+    }
+
+    ///
+    /// Waits for the lock in the shared mode.
+    ///
+    /// With the `lock_order` feature, this panics instead in the case that
+    /// waiting could deadlock.
+    ///
+    pub fn read(&self) -> LockReadGuard<'_, T> {
+        #[cfg(feature = "lock_order")]
+        self.node.wait(self.is_exclusive());
+        self.raw.lock_shared();
+        #[cfg(feature = "lock_order")]
+        self.node.acquired(self.is_exclusive());
+        // Safety: We now hold the lock in the shared mode.
+        unsafe { LockReadGuard::new(self, &self.data) }
+    }
+    ///
+    /// Tries to acquire the lock in the shared mode without waiting.
+    ///
+    pub fn try_read(&self) -> Option<LockReadGuard<'_, T>> {
+        if self.raw.try_lock_shared() {
+            #[cfg(feature = "lock_order")]
+            self.node.acquired(self.is_exclusive());
+            // Safety: We now hold the lock in the shared mode.
+            Some(unsafe { LockReadGuard::new(self, &self.data) })
+        } else {
+            None
+        }
+    }
+    ///
+    /// Waits for the lock in the exclusive mode.
+    ///
+    /// With the `lock_order` feature, this panics instead in the case that
+    /// waiting could deadlock.
+    ///
+    pub fn write(&self) -> LockWriteGuard<'_, T> {
+        #[cfg(feature = "lock_order")]
+        self.node.wait(true);
+        self.raw.lock_exclusive();
+        #[cfg(feature = "lock_order")]
+        self.node.acquired(true);
+        // Safety: We now hold the lock in the exclusive mode.
+        unsafe { LockWriteGuard::new(self, &self.data) }
+    }
+    ///
+    /// Tries to acquire the lock in the exclusive mode without waiting.
+    ///
+    pub fn try_write(&self) -> Option<LockWriteGuard<'_, T>> {
+        if self.raw.try_lock_exclusive() {
+            #[cfg(feature = "lock_order")]
+            self.node.acquired(true);
+            // Safety: We now hold the lock in the exclusive mode.
+            Some(unsafe { LockWriteGuard::new(self, &self.data) })
+        } else {
+            None
+        }
+    }
+
+    // Whether the shared mode of the lock is also exclusive.
+    #[cfg(feature = "lock_order")]
+    fn is_exclusive(&self) -> bool {
+        TypeId::of::<R::Kind>() == TypeId::of::<Exclusive>()
+    }
+}
+
+///
+/// A guard holding the lock of a [`LockUnit`] in the shared mode, which
+/// can be mapped to any part of the data it guards.
+///
+pub struct LockReadGuard<'a, T: ?Sized> {
+    raw: &'a dyn Unlock,
+    data: NonNull<T>,
+    _marker: PhantomData<&'a T>,
+}
+
+unsafe impl<T: ?Sized + Sync> Sync for LockReadGuard<'_, T> {}
+
+impl<'a, T: ?Sized> LockReadGuard<'a, T> {
+    unsafe fn new(raw: &'a dyn Unlock, data: &'a UnsafeCell<T>) -> Self
+    where
+        T: Sized,
+    {
+        Self {
+            raw,
+            data: NonNull::new_unchecked(data.get()),
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<T: ?Sized> Deref for LockReadGuard<'_, T> {
+    type Target = T;
+    fn deref(&self) -> &T {
+        // Safety: The lock is held in the shared mode for as long as we exist.
+        unsafe { self.data.as_ref() }
+    }
+}
+
+impl<T: ?Sized> Drop for LockReadGuard<'_, T> {
+    fn drop(&mut self) {
+        // Safety: We hold the lock in the shared mode.
+        unsafe { self.raw.unlock_shared() }
+    }
+}
+
+impl<'a, I: 'static + ?Sized, O: 'static + ?Sized> Map<I, O> for LockReadGuard<'a, I> {
+    type Output = LockReadGuard<'a, O>;
+    type Func = dyn for<'b> Fn(&'b I) -> &'b O;
+    fn map(self, f: &Self::Func) -> LockReadGuard<'a, O> {
+        let raw = self.raw;
+        let data = NonNull::from(f(&*self));
+        forget(self);
+        LockReadGuard {
+            raw,
+            data,
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<T: ?Sized> Waitable for LockReadGuard<'_, T> {}
+
+impl<T: ?Sized + fmt::Debug> fmt::Debug for LockReadGuard<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
+
+///
+/// A guard holding the lock of a [`LockUnit`] in the exclusive mode, which
+/// can be mapped to any part of the data it guards.
+///
+pub struct LockWriteGuard<'a, T: ?Sized> {
+    raw: &'a dyn Unlock,
+    data: NonNull<T>,
+    _marker: PhantomData<&'a mut T>,
+}
+
+unsafe impl<T: ?Sized + Sync> Sync for LockWriteGuard<'_, T> {}
+
+impl<'a, T: ?Sized> LockWriteGuard<'a, T> {
+    unsafe fn new(raw: &'a dyn Unlock, data: &'a UnsafeCell<T>) -> Self
+    where
+        T: Sized,
+    {
+        Self {
+            raw,
+            data: NonNull::new_unchecked(data.get()),
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<T: ?Sized> Deref for LockWriteGuard<'_, T> {
+    type Target = T;
+    fn deref(&self) -> &T {
+        // Safety: The lock is held in the exclusive mode for as long as we exist.
+        unsafe { self.data.as_ref() }
+    }
+}
+
+impl<T: ?Sized> DerefMut for LockWriteGuard<'_, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        // Safety: The lock is held in the exclusive mode for as long as we exist.
+        unsafe { self.data.as_mut() }
+    }
+}
+
+impl<T: ?Sized> Drop for LockWriteGuard<'_, T> {
+    fn drop(&mut self) {
+        // Safety: We hold the lock in the exclusive mode.
+        unsafe { self.raw.unlock_exclusive() }
+    }
+}
+
+impl<'a, I: 'static + ?Sized, O: 'static + ?Sized> MapMut<I, O> for LockWriteGuard<'a, I> {
+    type Output = LockWriteGuard<'a, O>;
+    type Func = dyn for<'b> Fn(&'b mut I) -> &'b mut O;
+    fn map(mut self, f: &Self::Func) -> LockWriteGuard<'a, O> {
+        let raw = self.raw;
+        let data = NonNull::from(f(&mut *self));
+        forget(self);
+        LockWriteGuard {
+            raw,
+            data,
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<T: ?Sized> Waitable for LockWriteGuard<'_, T> {}
+
+impl<T: ?Sized + fmt::Debug> fmt::Debug for LockWriteGuard<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
+
+impl<'a, R: RawLock, T: 'static + Send> Unit<'a> for LockUnit<R, StorageUnit<T>> {
+    type Borrowed = LockReadGuard<'a, dyn Any>;
+    type MutBorrowed = LockWriteGuard<'a, dyn Any>;
+
+    fn insert_any(&self, new: Box<dyn Any>) -> Option<(Box<dyn Any>, ErrorDesc)> {
+        let rejected = match self.try_write() {
+            Some(mut x) => x.insert_any(new),
+            None => return Some((new, ErrorDesc::BorrowedIncompatibly)),
+        };
+        if rejected.is_none() {
+            self.inserted.notify();
+        }
+        rejected
+    }
+
+    fn waiting_insert(&self, new: Box<dyn Any>) -> Option<(Box<dyn Any>, ErrorDesc)> {
+        if !new.is::<T>() && !new.is::<Vec<T>>() {
+            return Some((new, ErrorDesc::NoMatchingType));
+        }
+        let rejected = self.write().insert_any(new);
+        if rejected.is_none() {
+            self.inserted.notify();
+        }
+        rejected
+    }
+
+    fn storage(&'a self) -> DynamicResult<LockReadGuard<'a, dyn Any>> {
+        self.try_read()
+            .map(|x| Map::<_, dyn Any>::map(x, &|z| z))
+            .ok_or(ErrorDesc::BorrowedIncompatibly)
+    }
+    fn storage_mut(&'a self) -> DynamicResult<LockWriteGuard<'a, dyn Any>> {
+        self.try_write()
+            .map(|x| MapMut::<_, dyn Any>::map(x, &|z| z))
+            .ok_or(ErrorDesc::BorrowedIncompatibly)
+    }
+
+    fn waiting_storage(&'a self) -> DynamicResult<LockReadGuard<'a, dyn Any>> {
+        Ok(Map::<_, dyn Any>::map(self.read(), &|z| z))
+    }
+    fn waiting_storage_mut(&'a self) -> DynamicResult<LockWriteGuard<'a, dyn Any>> {
+        Ok(MapMut::<_, dyn Any>::map(self.write(), &|z| z))
+    }
+
+    fn id(&self) -> TypeId {
+        TypeId::of::<T>()
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn signal(&self) -> Option<&Signal> {
+        Some(&self.inserted)
+    }
+}
@@ -0,0 +1,83 @@
+use super::lock_unit::{DynLockUnit, LockUnit};
+use super::raw_lock::{Exclusive, Guardable, MutexBackend, RawLock};
+use crate::black_box::StorageUnit;
+use crate::{impl_unit, BlackBox};
+use alloc::boxed::Box;
+use core::any::Any;
+
+type MixedBlackBox = BlackBox<DynLockUnit>;
+
+type MixedUnit<T> = LockUnit<MutexBackend, T>;
+
+///
+/// A storage where the lock guarding each type is chosen when the type is
+/// allocated, using `allocate_for_with`.
+///
+/// > ## Please note!
+/// > The documentation for the functions implemented for this type are
+/// > found in [`BlackBox`]'s documentation under the same name.
+///
+/// Types allocated using `allocate_for` are guarded by a [`MutexBackend`],
+/// so they only need to be `T: Send`. Read-heavy types can instead be
+/// allocated with a [`RwLockBackend`](crate::RwLockBackend), which requires
+/// that they also be `Sync`:
+///
+/// ```
+/// # fn main() {
+/// use restor::{MixedStorage, MutexBackend, RwLockBackend};
+/// use std::cell::Cell;
+/// let mut storage = MixedStorage::new();
+/// storage.allocate_for_with::<String, RwLockBackend>();
+/// storage.allocate_for_with::<Cell<usize>, MutexBackend>();
+/// storage.insert(String::from("config")).unwrap();
+/// storage.insert(Cell::new(0usize)).unwrap();
+/// let (a, b) = storage.get::<(&String, &String)>().unwrap();
+/// assert_eq!(*a, *b);
+/// assert!(storage.get::<(&Cell<usize>, &Cell<usize>)>().is_err());
+/// # }
+/// ```
+///
+/// This fits into the same context as any storage type provided by
+/// `restor`.
+///
+pub struct MixedStorage {
+    black_box: MixedBlackBox,
+}
+
+impl_unit!(
+    MixedStorage,
+    (dyn Any + Send),
+    (Guardable<Exclusive> + Any),
+    MixedUnit(DynLockUnit),
+    LockWriteGuard,
+    LockReadGuard,
+    black_box,
+    add_unmut,
+    add_waiting
+);
+
+impl MixedStorage {
+    pub fn new() -> Self {
+        MixedStorage {
+            black_box: MixedBlackBox::new(),
+        }
+    }
+
+    ///
+    /// Adds a storage unit for the given type which is guarded by the lock
+    /// `B`, such as a [`MutexBackend`] or [`RwLockBackend`](crate::RwLockBackend).
+    ///
+    /// This will not add another unit in the case that it already exists,
+    /// even if the existing unit uses another lock.
+    ///
+    pub fn allocate_for_with<T: Guardable<B::Kind>, B: RawLock>(&mut self) {
+        self.black_box
+            .allocate::<T>(|| Box::new(LockUnit::<B, _>::new(StorageUnit::<T>::new())));
+    }
+}
+
+impl Default for MixedStorage {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -1,185 +1,305 @@
-use std::any::{Any, TypeId};
+use core::any::Any;
+#[cfg(feature = "parking_lot")]
+use core::any::TypeId;
 
+#[cfg(feature = "parking_lot")]
 use super::black_box::{
     DynamicResult,
     ErrorDesc::{self, *},
-    StorageUnit, Unit,
+    RefCellUnit, Signal, StorageUnit, Unit,
 };
+mod atomic_unit;
+mod lock_free;
+#[cfg(feature = "lock_order")]
+mod lock_order;
+mod lock_storage;
+mod lock_unit;
+mod mixed;
+#[cfg(feature = "parking_lot")]
 mod newtype;
+mod raw_lock;
+mod rcu_unit;
+#[cfg(feature = "std")]
+mod scheduler;
+pub use atomic_unit::AtomicUnit;
+#[cfg(all(feature = "parking_lot", feature = "lock_order"))]
+use lock_order::Node;
+#[cfg(all(feature = "parking_lot", feature = "lock_order"))]
+pub use lock_order::OrderedGuard;
+#[cfg(not(feature = "parking_lot"))]
+pub use lock_storage::{MutexStorage, RwLockStorage};
+pub use lock_storage::{SpinMutexStorage, SpinRwLockStorage};
+#[cfg(feature = "std")]
+pub use lock_storage::{StdMutexStorage, StdRwLockStorage};
+pub use lock_unit::{DynLockUnit, LockReadGuard, LockUnit, LockWriteGuard};
+pub use mixed::MixedStorage;
+#[cfg(feature = "parking_lot")]
 pub use newtype::{MutexStorage, RwLockStorage};
+#[cfg(feature = "parking_lot")]
 use parking_lot::{
     MappedMutexGuard, MappedRwLockReadGuard, MappedRwLockWriteGuard, Mutex, MutexGuard, RwLock,
     RwLockReadGuard, RwLockWriteGuard,
 };
+pub use raw_lock::{
+    Exclusive, Guardable, MutexBackend, RawLock, RwLockBackend, Shared, SpinMutexBackend,
+    SpinRwLockBackend,
+};
+#[cfg(feature = "std")]
+pub use raw_lock::{StdMutexBackend, StdRwLockBackend};
+pub use rcu_unit::{RcuUnit, Snapshot};
+#[cfg(feature = "std")]
+pub use scheduler::Scheduler;
+
+// The guards of the units built on `parking_lot`, which record being released
+// with the `lock_order` feature.
+#[cfg(all(feature = "parking_lot", feature = "lock_order"))]
+type Ordered<'a, G> = OrderedGuard<'a, G>;
+#[cfg(all(feature = "parking_lot", not(feature = "lock_order")))]
+type Ordered<'a, G> = G;
+
+// The guards of the units in a `RwLockStorage`, which depend on whether it
+// is built on `parking_lot` or on a `RawLock`.
+#[cfg(feature = "parking_lot")]
+type StorageReadGuard<'a> = Ordered<'a, MappedRwLockReadGuard<'a, dyn Any>>;
+#[cfg(feature = "parking_lot")]
+type StorageWriteGuard<'a> = Ordered<'a, MappedRwLockWriteGuard<'a, dyn Any>>;
+#[cfg(not(feature = "parking_lot"))]
+type StorageReadGuard<'a> = LockReadGuard<'a, dyn Any>;
+#[cfg(not(feature = "parking_lot"))]
+type StorageWriteGuard<'a> = LockWriteGuard<'a, dyn Any>;
 
-#[repr(transparent)]
+// The guards of the units in a `MutexStorage`.
+#[cfg(feature = "parking_lot")]
+type StorageMutexGuard<'a> = Ordered<'a, MappedMutexGuard<'a, dyn Any>>;
+
+#[cfg(feature = "parking_lot")]
 pub struct MutexUnit<T> {
     inner: Mutex<T>,
+    inserted: Signal,
+    #[cfg(feature = "lock_order")]
+    node: Node,
 }
 
+#[cfg(feature = "parking_lot")]
 impl<T> MutexUnit<T> {
     pub fn new(data: T) -> Self {
         Self {
             inner: Mutex::new(data),
+            inserted: Signal::new(),
+            #[cfg(feature = "lock_order")]
+            node: Node::new::<T>(),
         }
     }
+    pub fn into_inner(self) -> T {
+        self.inner.into_inner()
+    }
+}
+
+#[cfg(feature = "parking_lot")]
+impl<T: 'static> MutexUnit<T> {
+    fn lock(&self) -> MutexGuard<'_, T> {
+        #[cfg(feature = "lock_order")]
+        self.node.wait(true);
+        self.inner.lock()
+    }
+    // Records that the lock is held for as long as `guard` is.
+    fn ordered<G>(&self, guard: G) -> Ordered<'_, G> {
+        #[cfg(feature = "lock_order")]
+        return OrderedGuard::new(&self.node, true, guard);
+        #[cfg(not(feature = "lock_order"))]
+        guard
+    }
 }
 
+#[cfg(feature = "parking_lot")]
 impl<'a, T: 'static + Send> Unit<'a> for MutexUnit<StorageUnit<T>> {
-    type Borrowed = MappedMutexGuard<'a, dyn Any>;
-    type MutBorrowed = MappedMutexGuard<'a, dyn Any>;
+    type Borrowed = StorageMutexGuard<'a>;
+    type MutBorrowed = StorageMutexGuard<'a>;
     fn insert_any(&self, new: Box<dyn Any>) -> Option<(Box<dyn Any>, ErrorDesc)> {
-        let newtype = new.type_id();
-        if let Some(mut x) = self.inner.try_lock() {
-            if new.is::<T>() {
-                x.insert(*new.downcast::<T>().unwrap_or_else(|_| {
-                    panic!(
-                        "Tried to insert an object with type {:?} into a storage of type {:?}",
-                        newtype,
-                        TypeId::of::<T>()
-                    )
-                }));
-                None
-            } else if new.is::<Vec<T>>() {
-                x.insert_many(*new.downcast::<Vec<T>>().unwrap());
-                None
-            } else {
-                Some((new, ErrorDesc::NoMatchingType))
-            }
-        } else {
-            Some((new, ErrorDesc::BorrowedIncompatibly))
+        let rejected = match self.inner.try_lock() {
+            Some(mut x) => x.insert_any(new),
+            None => return Some((new, ErrorDesc::BorrowedIncompatibly)),
+        };
+        if rejected.is_none() {
+            self.inserted.notify();
         }
+        rejected
     }
     fn waiting_insert(&self, new: Box<dyn Any>) -> Option<(Box<dyn Any>, ErrorDesc)> {
-        let newtype = new.type_id();
-        if new.is::<T>() || new.is::<Vec<T>>() {
-            let mut x = self.inner.lock();
-            if new.is::<T>() {
-                x.insert(*new.downcast::<T>().unwrap_or_else(|_| {
-                    panic!(
-                        "Tried to insert an object with type {:?} into a storage of type {:?}",
-                        newtype,
-                        TypeId::of::<T>()
-                    )
-                }));
-                None
-            } else {
-                x.insert_many(*new.downcast::<Vec<T>>().unwrap());
-                None
-            }
-        } else {
-            Some((new, ErrorDesc::NoMatchingType))
+        if !new.is::<T>() && !new.is::<Vec<T>>() {
+            return Some((new, ErrorDesc::NoMatchingType));
         }
+        let rejected = self.lock().insert_any(new);
+        if rejected.is_none() {
+            self.inserted.notify();
+        }
+        rejected
     }
-    fn storage(&'a self) -> DynamicResult<MappedMutexGuard<'a, dyn Any>> {
+    fn storage(&'a self) -> DynamicResult<StorageMutexGuard<'a>> {
         self.inner
             .try_lock()
-            .map(|x| MutexGuard::map::<dyn Any, _>(x, |z| &mut *z))
+            .map(|x| self.ordered(MutexGuard::map::<dyn Any, _>(x, |z| &mut *z)))
             .ok_or(BorrowedIncompatibly)
     }
-    fn storage_mut(&'a self) -> DynamicResult<MappedMutexGuard<'a, dyn Any>> {
+    fn storage_mut(&'a self) -> DynamicResult<StorageMutexGuard<'a>> {
         self.storage()
     }
 
-    fn waiting_storage(&'a self) -> MappedMutexGuard<'a, dyn Any> {
-        MutexGuard::map::<dyn Any, _>(self.inner.lock(), |z| &mut *z)
+    fn waiting_storage(&'a self) -> DynamicResult<StorageMutexGuard<'a>> {
+        Ok(self.ordered(MutexGuard::map::<dyn Any, _>(self.lock(), |z| &mut *z)))
     }
-    fn waiting_storage_mut(&'a self) -> MappedMutexGuard<'a, dyn Any> {
+    fn waiting_storage_mut(&'a self) -> DynamicResult<StorageMutexGuard<'a>> {
         self.waiting_storage()
     }
 
     fn id(&self) -> TypeId {
         TypeId::of::<T>()
     }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn signal(&self) -> Option<&Signal> {
+        Some(&self.inserted)
+    }
 }
 
-#[repr(transparent)]
+#[cfg(feature = "parking_lot")]
 pub struct RwLockUnit<T> {
     inner: RwLock<T>,
+    inserted: Signal,
+    #[cfg(feature = "lock_order")]
+    node: Node,
 }
 
+#[cfg(feature = "parking_lot")]
 impl<T> RwLockUnit<T> {
     pub fn new(data: T) -> Self {
         Self {
             inner: RwLock::new(data),
+            inserted: Signal::new(),
+            #[cfg(feature = "lock_order")]
+            node: Node::new::<T>(),
         }
     }
+    pub fn into_inner(self) -> T {
+        self.inner.into_inner()
+    }
     #[cfg(test)]
     pub fn inner(&self) -> &RwLock<T> {
         &self.inner
     }
 }
 
+#[cfg(feature = "parking_lot")]
+impl<T: 'static> RwLockUnit<T> {
+    fn read(&self) -> RwLockReadGuard<'_, T> {
+        #[cfg(feature = "lock_order")]
+        self.node.wait(false);
+        self.inner.read()
+    }
+    fn write(&self) -> RwLockWriteGuard<'_, T> {
+        #[cfg(feature = "lock_order")]
+        self.node.wait(true);
+        self.inner.write()
+    }
+    // Records that the lock is held for as long as `guard` is.
+    #[cfg_attr(not(feature = "lock_order"), allow(unused_variables))]
+    fn ordered<G>(&self, exclusive: bool, guard: G) -> Ordered<'_, G> {
+        #[cfg(feature = "lock_order")]
+        return OrderedGuard::new(&self.node, exclusive, guard);
+        #[cfg(not(feature = "lock_order"))]
+        guard
+    }
+}
+
+#[cfg(feature = "parking_lot")]
 impl<'a, T: 'static + Send> Unit<'a> for RwLockUnit<StorageUnit<T>> {
-    type Borrowed = MappedRwLockReadGuard<'a, dyn Any>;
-    type MutBorrowed = MappedRwLockWriteGuard<'a, dyn Any>;
+    type Borrowed = StorageReadGuard<'a>;
+    type MutBorrowed = StorageWriteGuard<'a>;
 
     fn insert_any(&self, new: Box<dyn Any>) -> Option<(Box<dyn Any>, ErrorDesc)> {
-        let newtype = new.type_id();
-        if let Some(mut x) = self.inner.try_write() {
-            if new.is::<T>() {
-                x.insert(*new.downcast::<T>().unwrap_or_else(|_| {
-                    panic!(
-                        "Tried to insert an object with type {:?} into a storage of type {:?}",
-                        newtype,
-                        TypeId::of::<T>()
-                    )
-                }));
-                None
-            } else if new.is::<Vec<T>>() {
-                x.insert_many(*new.downcast::<Vec<T>>().unwrap());
-                None
-            } else {
-                Some((new, ErrorDesc::NoMatchingType))
-            }
-        } else {
-            Some((new, ErrorDesc::BorrowedIncompatibly))
+        let rejected = match self.inner.try_write() {
+            Some(mut x) => x.insert_any(new),
+            None => return Some((new, ErrorDesc::BorrowedIncompatibly)),
+        };
+        if rejected.is_none() {
+            self.inserted.notify();
         }
+        rejected
     }
 
     fn waiting_insert(&self, new: Box<dyn Any>) -> Option<(Box<dyn Any>, ErrorDesc)> {
-        let newtype = new.type_id();
-        if new.is::<T>() || new.is::<Vec<T>>() {
-            let mut x = self.inner.write();
-            if new.is::<T>() {
-                x.insert(*new.downcast::<T>().unwrap_or_else(|_| {
-                    panic!(
-                        "Tried to insert an object with type {:?} into a storage of type {:?}",
-                        newtype,
-                        TypeId::of::<T>()
-                    )
-                }));
-                None
-            } else {
-                x.insert_many(*new.downcast::<Vec<T>>().unwrap());
-                None
-            }
-        } else {
-            Some((new, ErrorDesc::NoMatchingType))
+        if !new.is::<T>() && !new.is::<Vec<T>>() {
+            return Some((new, ErrorDesc::NoMatchingType));
+        }
+        let rejected = self.write().insert_any(new);
+        if rejected.is_none() {
+            self.inserted.notify();
         }
+        rejected
     }
 
-    fn storage(&'a self) -> DynamicResult<MappedRwLockReadGuard<'a, dyn Any>> {
+    fn storage(&'a self) -> DynamicResult<StorageReadGuard<'a>> {
         self.inner
             .try_read()
-            .map(|x| RwLockReadGuard::map::<dyn Any, _>(x, |z| &*z))
+            .map(|x| self.ordered(false, RwLockReadGuard::map::<dyn Any, _>(x, |z| z)))
             .ok_or(BorrowedIncompatibly)
     }
-    fn storage_mut(&'a self) -> DynamicResult<MappedRwLockWriteGuard<'a, dyn Any>> {
+    fn storage_mut(&'a self) -> DynamicResult<StorageWriteGuard<'a>> {
         self.inner
             .try_write()
-            .map(|x| RwLockWriteGuard::map::<dyn Any, _>(x, |z| &mut *z))
+            .map(|x| self.ordered(true, RwLockWriteGuard::map::<dyn Any, _>(x, |z| &mut *z)))
             .ok_or(BorrowedIncompatibly)
     }
 
-    fn waiting_storage(&'a self) -> MappedRwLockReadGuard<'a, dyn Any> {
-        RwLockReadGuard::map::<dyn Any, _>(self.inner.read(), |z| &*z)
+    fn waiting_storage(&'a self) -> DynamicResult<StorageReadGuard<'a>> {
+        Ok(self.ordered(
+            false,
+            RwLockReadGuard::map::<dyn Any, _>(self.read(), |z| z),
+        ))
     }
-    fn waiting_storage_mut(&'a self) -> MappedRwLockWriteGuard<'a, dyn Any> {
-        RwLockWriteGuard::map::<dyn Any, _>(self.inner.write(), |z| &mut *z)
+    fn waiting_storage_mut(&'a self) -> DynamicResult<StorageWriteGuard<'a>> {
+        Ok(self.ordered(
+            true,
+            RwLockWriteGuard::map::<dyn Any, _>(self.write(), |z| &mut *z),
+        ))
     }
 
     fn id(&self) -> TypeId {
         TypeId::of::<T>()
     }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn signal(&self) -> Option<&Signal> {
+        Some(&self.inserted)
+    }
 }
+
+// Conversions between the units of the different backends, keeping their contents.
+#[cfg(feature = "parking_lot")]
+macro_rules! impl_unit_from {
+    ($($from:ident => $to:ident),*) => {
+        $(
+            impl<T> From<$from<T>> for $to<T> {
+                fn from(unit: $from<T>) -> Self {
+                    $to::new(unit.into_inner())
+                }
+            }
+        )*
+    };
+}
+
+#[cfg(feature = "parking_lot")]
+impl_unit_from!(
+    RefCellUnit => RwLockUnit,
+    RefCellUnit => MutexUnit,
+    RwLockUnit => RefCellUnit,
+    RwLockUnit => MutexUnit,
+    MutexUnit => RefCellUnit,
+    MutexUnit => RwLockUnit
+);
@@ -1,16 +1,12 @@
-use super::{MutexUnit, RwLockUnit};
+use super::{MutexUnit, RwLockUnit, StorageMutexGuard, StorageReadGuard, StorageWriteGuard};
 use crate::BlackBox;
 use crate::{black_box::Unit, impl_unit};
-use parking_lot::{MappedMutexGuard, MappedRwLockReadGuard, MappedRwLockWriteGuard};
-use std::any::Any;
+use core::any::Any;
 
 type RwLockBlackBox = BlackBox<
-    (dyn for<'a> Unit<
-        'a,
-        Borrowed = MappedRwLockReadGuard<'a, dyn Any>,
-        MutBorrowed = MappedRwLockWriteGuard<'a, dyn Any>,
-    > + Send
-         + Sync),
+    dyn for<'a> Unit<'a, Borrowed = StorageReadGuard<'a>, MutBorrowed = StorageWriteGuard<'a>>
+        + Send
+        + Sync,
 >;
 
 ///
@@ -33,7 +29,7 @@ type RwLockBlackBox = BlackBox<
 /// `restor`.
 ///
 pub struct RwLockStorage {
-    black_box: RwLockBlackBox,
+    pub(super) black_box: RwLockBlackBox,
 }
 
 impl_unit!(
@@ -41,15 +37,12 @@ impl_unit!(
     (dyn Any + Send + Sync),
     (Send + Sync + Any),
     RwLockUnit(
-        (dyn for<'u> Unit<
-            'u,
-            Borrowed = MappedRwLockReadGuard<'u, dyn Any>,
-            MutBorrowed = MappedRwLockWriteGuard<'u, dyn Any>,
-        > + Send
-             + Sync),
+        dyn for<'u> Unit<'u, Borrowed = StorageReadGuard<'u>, MutBorrowed = StorageWriteGuard<'u>>
+            + Send
+            + Sync,
     ),
-    MappedRwLockWriteGuard,
-    MappedRwLockReadGuard,
+    StorageWriteGuard,
+    StorageReadGuard,
     black_box,
     add_unmut,
     add_waiting
@@ -70,12 +63,9 @@ impl Default for RwLockStorage {
 }
 
 type MutexBlackBox = BlackBox<
-    (dyn for<'a> Unit<
-        'a,
-        Borrowed = MappedMutexGuard<'a, dyn Any>,
-        MutBorrowed = MappedMutexGuard<'a, dyn Any>,
-    > + Send
-         + Sync),
+    dyn for<'a> Unit<'a, Borrowed = StorageMutexGuard<'a>, MutBorrowed = StorageMutexGuard<'a>>
+        + Send
+        + Sync,
 >;
 
 ///
@@ -125,15 +115,12 @@ impl_unit!(
     (dyn Any + Send),
     (Send + Any),
     MutexUnit(
-        (dyn for<'u> Unit<
-            'u,
-            Borrowed = MappedMutexGuard<'u, dyn Any>,
-            MutBorrowed = MappedMutexGuard<'u, dyn Any>,
-        > + Send
-             + Sync),
+        dyn for<'u> Unit<'u, Borrowed = StorageMutexGuard<'u>, MutBorrowed = StorageMutexGuard<'u>>
+            + Send
+            + Sync,
     ),
-    MappedMutexGuard,
-    MappedMutexGuard,
+    StorageMutexGuard,
+    StorageMutexGuard,
     black_box,
     add_waiting
 );
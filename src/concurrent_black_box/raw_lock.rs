@@ -0,0 +1,412 @@
+use core::hint::spin_loop;
+#[cfg(feature = "parking_lot")]
+use core::mem::forget;
+use core::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+#[cfg(feature = "parking_lot")]
+use parking_lot::{Mutex, RwLock};
+#[cfg(feature = "std")]
+use std::sync::{Condvar, Mutex as StdMutex, MutexGuard as StdMutexGuard, PoisonError};
+
+///
+/// A lock which does not own the data it guards, which is the extension
+/// point for storages over custom locks.
+///
+/// Implementing this for a lock, and passing it to [`define_storage`], is
+/// all that is needed to get a storage with the same api as any other
+/// storage provided by `restor`. It can also be used for a single type in a
+/// [`MixedStorage`](crate::MixedStorage) using `allocate_for_with`.
+///
+/// A lock has two modes, shared and exclusive. Whether more than one
+/// holder of the shared mode may exist at once is described by `Kind`,
+/// which is either [`Shared`] for locks that behave like a `RwLock`, or
+/// [`Exclusive`] for locks that behave like a `Mutex` and therefore lock
+/// exclusively in both modes. This decides whether the types stored using
+/// the lock need to be `Sync`, as described by [`Guardable`].
+///
+/// Please see [`define_storage`] for an example.
+///
+/// # Safety
+/// Implementors must guarantee that the exclusive mode is never held while
+/// any other mode is held, and that the shared mode is never held twice at
+/// once in the case that `Kind` is [`Exclusive`].
+///
+/// [`define_storage`]: ./macro.define_storage.html
+///
+pub unsafe trait RawLock: Send + Sync + 'static {
+    ///
+    /// Either [`Shared`] or [`Exclusive`], depending on whether the shared
+    /// mode can be held more than once at a time.
+    ///
+    type Kind: 'static;
+    ///
+    /// Creates a new unlocked lock.
+    ///
+    fn new() -> Self
+    where
+        Self: Sized;
+    ///
+    /// Waits for the lock in the shared mode.
+    ///
+    fn lock_shared(&self);
+    ///
+    /// Tries to acquire the lock in the shared mode without waiting.
+    ///
+    fn try_lock_shared(&self) -> bool;
+    ///
+    /// Releases the lock from the shared mode.
+    ///
+    /// # Safety
+    /// This may only be called when the lock is held in the shared mode.
+    ///
+    unsafe fn unlock_shared(&self);
+    ///
+    /// Waits for the lock in the exclusive mode.
+    ///
+    fn lock_exclusive(&self);
+    ///
+    /// Tries to acquire the lock in the exclusive mode without waiting.
+    ///
+    fn try_lock_exclusive(&self) -> bool;
+    ///
+    /// Releases the lock from the exclusive mode.
+    ///
+    /// # Safety
+    /// This may only be called when the lock is held in the exclusive mode.
+    ///
+    unsafe fn unlock_exclusive(&self);
+}
+
+///
+/// The [`RawLock::Kind`] of locks which allow many shared holders at once,
+/// and therefore require the data they guard to be `Send + Sync`.
+///
+pub enum Shared {}
+
+///
+/// The [`RawLock::Kind`] of locks which only ever allow a single holder,
+/// and therefore only require the data they guard to be `Send`.
+///
+pub enum Exclusive {}
+
+///
+/// Implemented for the types which can be stored behind a lock whose
+/// [`RawLock::Kind`] is `K`. These are the `Send + Sync` types for the
+/// [`Shared`] kind, and the `Send` types for the [`Exclusive`] kind.
+///
+/// This is sealed, and is used as a bound wherever a unit over a lock
+/// is created.
+///
+pub trait Guardable<K>: Send + 'static + sealed::Sealed<K> {}
+
+impl<T: Send + Sync + 'static> Guardable<Shared> for T {}
+impl<T: Send + 'static> Guardable<Exclusive> for T {}
+
+mod sealed {
+    use super::{Exclusive, Shared};
+
+    pub trait Sealed<K> {}
+
+    impl<T: Send + Sync + 'static> Sealed<Shared> for T {}
+    impl<T: Send + 'static> Sealed<Exclusive> for T {}
+}
+
+///
+/// A [`RawLock`] backed by a `parking_lot` `RwLock`, which is best suited
+/// to read-heavy types.
+///
+/// In the case that the `parking_lot` feature is disabled, this is instead
+/// the [`StdRwLockBackend`], or the [`SpinRwLockBackend`] without `std`.
+///
+#[cfg(feature = "parking_lot")]
+pub struct RwLockBackend(RwLock<()>);
+
+#[cfg(feature = "parking_lot")]
+unsafe impl RawLock for RwLockBackend {
+    type Kind = Shared;
+    fn new() -> Self {
+        RwLockBackend(RwLock::new(()))
+    }
+    fn lock_shared(&self) {
+        forget(self.0.read());
+    }
+    fn try_lock_shared(&self) -> bool {
+        self.0.try_read().map(forget).is_some()
+    }
+    unsafe fn unlock_shared(&self) {
+        self.0.force_unlock_read();
+    }
+    fn lock_exclusive(&self) {
+        forget(self.0.write());
+    }
+    fn try_lock_exclusive(&self) -> bool {
+        self.0.try_write().map(forget).is_some()
+    }
+    unsafe fn unlock_exclusive(&self) {
+        self.0.force_unlock_write();
+    }
+}
+
+///
+/// A [`RawLock`] backed by a `parking_lot` `Mutex`, which is best suited
+/// to write-heavy types, and to types which are not `Sync`.
+///
+/// In the case that the `parking_lot` feature is disabled, this is instead
+/// the [`StdMutexBackend`], or the [`SpinMutexBackend`] without `std`.
+///
+#[cfg(feature = "parking_lot")]
+pub struct MutexBackend(Mutex<()>);
+
+#[cfg(feature = "parking_lot")]
+unsafe impl RawLock for MutexBackend {
+    type Kind = Exclusive;
+    fn new() -> Self {
+        MutexBackend(Mutex::new(()))
+    }
+    fn lock_shared(&self) {
+        self.lock_exclusive();
+    }
+    fn try_lock_shared(&self) -> bool {
+        self.try_lock_exclusive()
+    }
+    unsafe fn unlock_shared(&self) {
+        self.unlock_exclusive();
+    }
+    fn lock_exclusive(&self) {
+        forget(self.0.lock());
+    }
+    fn try_lock_exclusive(&self) -> bool {
+        self.0.try_lock().map(forget).is_some()
+    }
+    unsafe fn unlock_exclusive(&self) {
+        self.0.force_unlock();
+    }
+}
+
+#[cfg(not(feature = "std"))]
+pub use self::{SpinMutexBackend as MutexBackend, SpinRwLockBackend as RwLockBackend};
+#[cfg(all(feature = "std", not(feature = "parking_lot")))]
+pub use self::{StdMutexBackend as MutexBackend, StdRwLockBackend as RwLockBackend};
+
+///
+/// A [`RawLock`] built only on `std::sync`, which behaves like a `RwLock`.
+///
+/// This tracks the holders of the lock in a `Mutex`, and uses a `Condvar`
+/// to wait for the lock to be released.
+///
+#[cfg(feature = "std")]
+pub struct StdRwLockBackend {
+    // The number of shared holders, or `WRITER` while held exclusively.
+    state: StdMutex<usize>,
+    released: Condvar,
+}
+
+// The state of a shared lock while it is held exclusively.
+const WRITER: usize = usize::MAX;
+
+// The state is never left inconsistent while its lock is held, so a
+// poisoned lock can safely be recovered.
+#[cfg(feature = "std")]
+fn recover<T>(lock: &StdMutex<T>) -> StdMutexGuard<'_, T> {
+    lock.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+#[cfg(feature = "std")]
+unsafe impl RawLock for StdRwLockBackend {
+    type Kind = Shared;
+    fn new() -> Self {
+        StdRwLockBackend {
+            state: StdMutex::new(0),
+            released: Condvar::new(),
+        }
+    }
+    fn lock_shared(&self) {
+        let mut state = recover(&self.state);
+        while *state == WRITER {
+            state = self
+                .released
+                .wait(state)
+                .unwrap_or_else(PoisonError::into_inner);
+        }
+        *state += 1;
+    }
+    fn try_lock_shared(&self) -> bool {
+        let mut state = recover(&self.state);
+        if *state == WRITER {
+            false
+        } else {
+            *state += 1;
+            true
+        }
+    }
+    unsafe fn unlock_shared(&self) {
+        let mut state = recover(&self.state);
+        *state -= 1;
+        if *state == 0 {
+            self.released.notify_all();
+        }
+    }
+    fn lock_exclusive(&self) {
+        let mut state = recover(&self.state);
+        while *state != 0 {
+            state = self
+                .released
+                .wait(state)
+                .unwrap_or_else(PoisonError::into_inner);
+        }
+        *state = WRITER;
+    }
+    fn try_lock_exclusive(&self) -> bool {
+        let mut state = recover(&self.state);
+        if *state == 0 {
+            *state = WRITER;
+            true
+        } else {
+            false
+        }
+    }
+    unsafe fn unlock_exclusive(&self) {
+        *recover(&self.state) = 0;
+        self.released.notify_all();
+    }
+}
+
+///
+/// A [`RawLock`] built only on `std::sync`, which behaves like a `Mutex`.
+///
+#[cfg(feature = "std")]
+pub struct StdMutexBackend {
+    locked: StdMutex<bool>,
+    released: Condvar,
+}
+
+#[cfg(feature = "std")]
+unsafe impl RawLock for StdMutexBackend {
+    type Kind = Exclusive;
+    fn new() -> Self {
+        StdMutexBackend {
+            locked: StdMutex::new(false),
+            released: Condvar::new(),
+        }
+    }
+    fn lock_shared(&self) {
+        self.lock_exclusive();
+    }
+    fn try_lock_shared(&self) -> bool {
+        self.try_lock_exclusive()
+    }
+    unsafe fn unlock_shared(&self) {
+        self.unlock_exclusive();
+    }
+    fn lock_exclusive(&self) {
+        let mut locked = recover(&self.locked);
+        while *locked {
+            locked = self
+                .released
+                .wait(locked)
+                .unwrap_or_else(PoisonError::into_inner);
+        }
+        *locked = true;
+    }
+    fn try_lock_exclusive(&self) -> bool {
+        let mut locked = recover(&self.locked);
+        !core::mem::replace(&mut *locked, true)
+    }
+    unsafe fn unlock_exclusive(&self) {
+        *recover(&self.locked) = false;
+        self.released.notify_one();
+    }
+}
+
+///
+/// A [`RawLock`] which spins while waiting, and behaves like a `RwLock`.
+///
+/// This only depends on `core`, and is therefore what `RwLockBackend` is
+/// in the case that the `std` feature is disabled. Holders of the shared
+/// mode may keep the exclusive mode from being acquired indefinitely.
+///
+pub struct SpinRwLockBackend {
+    // The number of shared holders, or `WRITER` while held exclusively.
+    state: AtomicUsize,
+}
+
+unsafe impl RawLock for SpinRwLockBackend {
+    type Kind = Shared;
+    fn new() -> Self {
+        SpinRwLockBackend {
+            state: AtomicUsize::new(0),
+        }
+    }
+    fn lock_shared(&self) {
+        while !self.try_lock_shared() {
+            spin_loop();
+        }
+    }
+    fn try_lock_shared(&self) -> bool {
+        self.state
+            .fetch_update(Ordering::Acquire, Ordering::Relaxed, |state| {
+                if state == WRITER || state == WRITER - 1 {
+                    None
+                } else {
+                    Some(state + 1)
+                }
+            })
+            .is_ok()
+    }
+    unsafe fn unlock_shared(&self) {
+        self.state.fetch_sub(1, Ordering::Release);
+    }
+    fn lock_exclusive(&self) {
+        while !self.try_lock_exclusive() {
+            spin_loop();
+        }
+    }
+    fn try_lock_exclusive(&self) -> bool {
+        self.state
+            .compare_exchange(0, WRITER, Ordering::Acquire, Ordering::Relaxed)
+            .is_ok()
+    }
+    unsafe fn unlock_exclusive(&self) {
+        self.state.store(0, Ordering::Release);
+    }
+}
+
+///
+/// A [`RawLock`] which spins while waiting, and behaves like a `Mutex`.
+///
+/// This only depends on `core`, and is therefore what `MutexBackend` is
+/// in the case that the `std` feature is disabled.
+///
+pub struct SpinMutexBackend {
+    locked: AtomicBool,
+}
+
+unsafe impl RawLock for SpinMutexBackend {
+    type Kind = Exclusive;
+    fn new() -> Self {
+        SpinMutexBackend {
+            locked: AtomicBool::new(false),
+        }
+    }
+    fn lock_shared(&self) {
+        self.lock_exclusive();
+    }
+    fn try_lock_shared(&self) -> bool {
+        self.try_lock_exclusive()
+    }
+    unsafe fn unlock_shared(&self) {
+        self.unlock_exclusive();
+    }
+    fn lock_exclusive(&self) {
+        while !self.try_lock_exclusive() {
+            spin_loop();
+        }
+    }
+    fn try_lock_exclusive(&self) -> bool {
+        self.locked
+            .compare_exchange(false, true, Ordering::Acquire, Ordering::Relaxed)
+            .is_ok()
+    }
+    unsafe fn unlock_exclusive(&self) {
+        self.locked.store(false, Ordering::Release);
+    }
+}
@@ -0,0 +1,286 @@
+use super::lock_unit::LockUnit;
+use super::raw_lock::RwLockBackend;
+use super::{StorageReadGuard, StorageWriteGuard};
+use crate::black_box::{
+    Access, BlackBox, Borrowed, DynamicResult, ErrorDesc, Fetch, FetchMultiple, MutBorrowed,
+    StorageUnit, Taken, Unit, UnitError, Waitable,
+};
+use alloc::sync::Arc;
+use alloc::{boxed::Box, vec::Vec};
+use core::any::{Any, TypeId};
+use core::marker::PhantomData;
+
+///
+/// A read-copy-update unit for read-mostly values, which hands out
+/// [`Arc`] snapshots of the value instead of borrowing it.
+///
+/// The `Arc` is kept behind a read-write lock, which is only ever held
+/// for as long as it takes to clone or swap it. Readers never wait on a
+/// writer which is still building the replacement value, and a snapshot
+/// can be held for as long as needed without blocking anyone. This is not
+/// lock-free though: taking a snapshot takes the read lock, so it blocks
+/// for as long as a writer holds the lock or is queued on it.
+///
+/// This is allocated in a [`RwLockStorage`](crate::RwLockStorage) using
+/// `allocate_rcu`, read with the [`Snapshot`] fetch kind and written to
+/// using `replace` and `rcu`. Any attempt at borrowing the contents of
+/// the unit returns `UnitError::Unsupported`.
+///
+/// [`Arc`]: https://doc.rust-lang.org/std/sync/struct.Arc.html
+///
+pub struct RcuUnit<T> {
+    inner: LockUnit<RwLockBackend, Option<Arc<T>>>,
+}
+
+// The lock only ever guards the `Arc`, which is what is shared between
+// threads, so this is as `Sync` as an `Arc<T>`.
+unsafe impl<T: Send + Sync> Sync for RcuUnit<T> {}
+
+impl<T> RcuUnit<T> {
+    pub fn new() -> Self {
+        Self {
+            inner: LockUnit::new(None),
+        }
+    }
+
+    ///
+    /// Returns a snapshot of the current value, if there is one.
+    ///
+    /// This takes the read lock, so it blocks while another thread is
+    /// swapping the value in or waiting to.
+    ///
+    pub fn snapshot(&self) -> Option<Arc<T>> {
+        self.inner.read().clone()
+    }
+
+    ///
+    /// Publishes `new` as the current value, returning the previous one.
+    ///
+    pub fn replace(&self, new: Arc<T>) -> Option<Arc<T>> {
+        self.inner.write().replace(new)
+    }
+
+    ///
+    /// Publishes the result of `f` as the current value, returning the
+    /// snapshot it was created from, or `None` in the case that there is
+    /// no value.
+    ///
+    /// `f` is run without holding the lock, so in the case that another
+    /// value was published in the meantime, `f` is run again on the newer
+    /// value.
+    ///
+    pub fn rcu<F: FnMut(&T) -> T>(&self, mut f: F) -> Option<Arc<T>> {
+        loop {
+            let current = self.snapshot()?;
+            let new = Arc::new(f(&current));
+            let mut inner = self.inner.write();
+            if inner.as_ref().is_some_and(|x| Arc::ptr_eq(x, &current)) {
+                *inner = Some(new);
+                return Some(current);
+            }
+        }
+    }
+
+    fn insert(&self, new: Box<dyn Any>) -> Option<(Box<dyn Any>, ErrorDesc)>
+    where
+        T: 'static,
+    {
+        if new.is::<T>() {
+            self.replace(Arc::new(*new.downcast::<T>().unwrap()));
+            None
+        } else if new.is::<Vec<T>>() {
+            Some((new, ErrorDesc::Unit(UnitError::Unsupported)))
+        } else {
+            Some((new, ErrorDesc::NoMatchingType))
+        }
+    }
+}
+
+impl<T> Default for RcuUnit<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<'a, T: 'static + Send + Sync> Unit<'a> for RcuUnit<T> {
+    type Borrowed = StorageReadGuard<'a>;
+    type MutBorrowed = StorageWriteGuard<'a>;
+    fn insert_any(&self, new: Box<dyn Any>) -> Option<(Box<dyn Any>, ErrorDesc)> {
+        self.insert(new)
+    }
+    fn waiting_insert(&self, new: Box<dyn Any>) -> Option<(Box<dyn Any>, ErrorDesc)> {
+        self.insert(new)
+    }
+    fn storage(&'a self) -> DynamicResult<StorageReadGuard<'a>> {
+        Err(ErrorDesc::Unit(UnitError::Unsupported))
+    }
+    fn storage_mut(&'a self) -> DynamicResult<StorageWriteGuard<'a>> {
+        Err(ErrorDesc::Unit(UnitError::Unsupported))
+    }
+    fn waiting_storage(&'a self) -> DynamicResult<StorageReadGuard<'a>> {
+        Err(ErrorDesc::Unit(UnitError::Unsupported))
+    }
+    fn waiting_storage_mut(&'a self) -> DynamicResult<StorageWriteGuard<'a>> {
+        Err(ErrorDesc::Unit(UnitError::Unsupported))
+    }
+
+    fn id(&self) -> TypeId {
+        TypeId::of::<T>()
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+///
+/// Moves the value of `source` into `dest` when merging storages, where
+/// either of them is an `RcuUnit<T>`.
+///
+/// A value moved into an rcu unit replaces the one in it. In the case that
+/// the other unit holds more than one value, this returns
+/// `UnitError::IsNotOne` and leaves both units untouched.
+///
+/// A value can only be moved out of an rcu unit into another kind of unit
+/// in the case that there are no snapshots of it left, and that the other
+/// unit can hold it. Otherwise the value is left in the rcu unit.
+///
+pub(crate) fn append_rcu_unit<T: 'static, U: ?Sized + for<'a> Unit<'a>>(
+    source: &U,
+    dest: &U,
+) -> DynamicResult<()> {
+    if let Some(source) = source.as_any().downcast_ref::<RcuUnit<T>>() {
+        let mut inner = source.inner.write();
+        let value = match inner.take() {
+            Some(value) => value,
+            None => return Ok(()),
+        };
+        let dest = match dest.as_any().downcast_ref::<RcuUnit<T>>() {
+            Some(dest) => {
+                dest.replace(value);
+                return Ok(());
+            }
+            None => dest,
+        };
+        // The value is put back in the case that it cannot be moved.
+        let result = match dest.storage_mut() {
+            Ok(mut dest) => match Arc::try_unwrap(value) {
+                Ok(value) => dest
+                    .downcast_mut::<StorageUnit<T>>()
+                    .unwrap()
+                    .try_insert(value)
+                    .map_err(|(value, e)| (Arc::new(value), e)),
+                Err(value) => Err((value, ErrorDesc::BorrowedIncompatibly)),
+            },
+            Err(e) => Err((value, e)),
+        };
+        return result.map_err(|(value, e)| {
+            *inner = Some(value);
+            e
+        });
+    }
+    let dest = dest
+        .as_any()
+        .downcast_ref::<RcuUnit<T>>()
+        .ok_or(ErrorDesc::Unit(UnitError::Unsupported))?;
+    let mut source = source.storage_mut()?;
+    let source = source.downcast_mut::<StorageUnit<T>>().unwrap();
+    if source.len() > 1 {
+        return Err(ErrorDesc::Unit(UnitError::IsNotOne));
+    }
+    if let Ok(value) = source.extract_one() {
+        dest.replace(Arc::new(value));
+    }
+    Ok(())
+}
+
+///
+/// A [`Fetch`] kind which returns an [`Arc`] snapshot of the value in the
+/// [`RcuUnit`] allocated for `T`, without holding any lock on the storage.
+///
+/// # Example
+/// ```
+/// # fn main() {
+/// use restor::{RwLockStorage, Snapshot};
+/// let mut storage = RwLockStorage::new();
+/// storage.allocate_rcu::<String>();
+/// storage.allocate_for::<usize>();
+/// storage.replace(String::from("config")).unwrap();
+/// storage.insert(0usize).unwrap();
+/// let (config, count) = storage.get::<(Snapshot<String>, &usize)>().unwrap();
+/// storage.rcu::<String, _>(|old| format!("new {}", old)).unwrap();
+/// assert_eq!(&*config, "config");
+/// assert_eq!(*count, 0);
+/// assert_eq!(&*storage.get::<Snapshot<String>>().unwrap(), "new config");
+/// # }
+/// ```
+///
+/// [`Arc`]: https://doc.rust-lang.org/std/sync/struct.Arc.html
+///
+pub struct Snapshot<T>(PhantomData<T>);
+
+impl<T: 'static> Snapshot<T> {
+    fn unit<U: for<'b> Unit<'b> + ?Sized>(boxed: &BlackBox<U>) -> DynamicResult<Arc<T>> {
+        boxed
+            .unit_get::<T>()?
+            .as_any()
+            .downcast_ref::<RcuUnit<T>>()
+            .ok_or(ErrorDesc::Unit(UnitError::Unsupported))?
+            .snapshot()
+            .ok_or(ErrorDesc::Unit(UnitError::IsNope))
+    }
+}
+
+impl<'a, T: Sized + Any + 'static, U: for<'b> Unit<'b> + ?Sized> Fetch<'a, U> for Snapshot<T> {
+    type Output = Arc<T>;
+
+    type Actual = T;
+    type Guard = ();
+    #[inline]
+    fn access(access: &mut Access) {
+        access.read::<T>();
+    }
+    #[inline]
+    fn can_get(boxed: &'a BlackBox<U>, _: &mut Taken) -> DynamicResult<Self::Guard> {
+        Self::unit(boxed).map(drop)
+    }
+    #[inline]
+    fn get(boxed: &'a BlackBox<U>) -> DynamicResult<Self::Output> {
+        Self::unit(boxed)
+    }
+    #[inline]
+    fn waiting_get(boxed: &'a BlackBox<U>) -> DynamicResult<Self::Output>
+    where
+        Borrowed<'a, U>: Waitable,
+        MutBorrowed<'a, U>: Waitable,
+    {
+        Self::unit(boxed)
+    }
+}
+
+impl<'a, T: Sized + Any + 'static, U: for<'b> Unit<'b> + ?Sized> FetchMultiple<'a, U>
+    for Snapshot<T>
+{
+    type Output = Arc<T>;
+    type Actual = T;
+    #[inline]
+    fn access(access: &mut Access) {
+        access.read::<T>();
+    }
+    #[inline]
+    fn can_get_many(boxed: &'a BlackBox<U>) -> DynamicResult<()> {
+        Self::unit(boxed).map(drop)
+    }
+    #[inline]
+    fn get_many(boxed: &'a BlackBox<U>) -> DynamicResult<Self::Output> {
+        Self::unit(boxed)
+    }
+    #[inline]
+    fn waiting_get_many(boxed: &'a BlackBox<U>) -> DynamicResult<Self::Output>
+    where
+        Borrowed<'a, U>: Waitable,
+        MutBorrowed<'a, U>: Waitable,
+    {
+        Self::unit(boxed)
+    }
+}
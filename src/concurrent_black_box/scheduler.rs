@@ -0,0 +1,359 @@
+use super::RwLockStorage;
+use crate::black_box::{Access, DynamicResult, ErrorDesc, FetchMultiple, Storage, Unit, Waitable};
+use std::any::Any;
+use std::collections::VecDeque;
+use std::mem::transmute;
+use std::panic::{catch_unwind, resume_unwind, AssertUnwindSafe};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard, OnceLock, PoisonError};
+use std::thread::{available_parallelism, spawn, JoinHandle};
+
+type Borrowed<'a, S> = <<S as Storage>::Unit as Unit<'a>>::Borrowed;
+type MutBorrowed<'a, S> = <<S as Storage>::Unit as Unit<'a>>::MutBorrowed;
+
+struct System<'s> {
+    access: Access,
+    run: Box<dyn Fn() -> DynamicResult<()> + Send + Sync + 's>,
+}
+
+///
+/// Runs systems, which are functions over the values fetched from a
+/// storage, on several threads at once.
+///
+/// Each system is added alongside the [`FetchMultiple`] it is run with,
+/// such as `(&A, &mut B)`, which is what decides the types it reads and
+/// writes to. Systems whose fetches do not [conflict] are run at the same
+/// time, while conflicting ones are run in the order they were added in.
+///
+/// The threads are started the first time `run` is called, and are kept
+/// until the scheduler is dropped, so that running the systems once per
+/// frame doesn't start any threads. The thread calling `run` runs systems
+/// as well, and systems can borrow from the scope the scheduler was
+/// created in, as `run` only returns once every system has finished.
+///
+/// # Example
+/// ```
+/// # fn main() {
+/// use restor::{make_storage, RwLockStorage, Scheduler};
+/// #[derive(Debug)]
+/// struct Position(f32);
+/// #[derive(Debug)]
+/// struct Velocity(f32);
+/// #[derive(Debug)]
+/// struct Time(f32);
+/// let storage = make_storage!(RwLockStorage: Position, Velocity, Time);
+/// storage.insert(Position(0.0)).unwrap();
+/// storage.insert(Velocity(2.0)).unwrap();
+/// storage.insert(Time(0.0)).unwrap();
+///
+/// let mut scheduler = Scheduler::new(&storage);
+/// scheduler
+///     .add::<(&mut Position, &Velocity), _>(|(mut position, velocity)| {
+///         position.0 += velocity.0;
+///     })
+///     .add::<&mut Time, _>(|mut time| time.0 += 1.0)
+///     .add::<(&Position, &Time), _>(|(position, time)| {
+///         assert_eq!(position.0, 2.0 * time.0);
+///     });
+/// for _ in 0..10 {
+///     scheduler.run().unwrap();
+/// }
+/// # }
+/// ```
+///
+/// [`FetchMultiple`]: ./trait.FetchMultiple.html
+/// [conflict]: ./struct.Access.html#method.conflicts_with
+///
+pub struct Scheduler<'s, S: Storage = RwLockStorage> {
+    storage: &'s S,
+    systems: Vec<System<'s>>,
+    threads: usize,
+    pool: OnceLock<Pool>,
+}
+
+// The progress of a single `run`, shared between its threads.
+struct State {
+    ready: VecDeque<usize>,
+    waiting_on: Vec<usize>,
+    finished: usize,
+    errors: Vec<(usize, ErrorDesc)>,
+    panic: Option<Box<dyn Any + Send>>,
+}
+
+// The work of a single `run`, which every worker runs once. Its lifetime is
+// erased, as `Posted` waits for every worker to be done with it before the
+// work it points to goes out of scope.
+type Job = &'static (dyn Fn() + Sync);
+
+// The job posted to the workers, shared with them.
+struct Slot {
+    job: Option<Job>,
+    posted: usize,
+    active: usize,
+    shutdown: bool,
+}
+
+struct Shared {
+    slot: Mutex<Slot>,
+    posted: Condvar,
+    done: Condvar,
+}
+
+impl Shared {
+    fn lock(&self) -> MutexGuard<'_, Slot> {
+        self.slot.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+///
+/// The threads which run systems alongside the thread calling `run`, which
+/// wait for a job to be posted in between runs.
+///
+struct Pool {
+    shared: Arc<Shared>,
+    workers: Vec<JoinHandle<()>>,
+    // Held for the duration of a run, so that only one job is posted at once.
+    running: Mutex<()>,
+}
+
+impl Pool {
+    fn new(threads: usize) -> Self {
+        let shared = Arc::new(Shared {
+            slot: Mutex::new(Slot {
+                job: None,
+                posted: 0,
+                active: 0,
+                shutdown: false,
+            }),
+            posted: Condvar::new(),
+            done: Condvar::new(),
+        });
+        let workers = (0..threads)
+            .map(|_| {
+                let shared = shared.clone();
+                spawn(move || Self::work(&shared))
+            })
+            .collect();
+        Self {
+            shared,
+            workers,
+            running: Mutex::new(()),
+        }
+    }
+
+    fn work(shared: &Shared) {
+        let mut seen = 0;
+        let mut slot = shared.lock();
+        loop {
+            if slot.shutdown {
+                return;
+            }
+            match slot.job {
+                Some(job) if slot.posted != seen => {
+                    seen = slot.posted;
+                    drop(slot);
+                    job();
+                    slot = shared.lock();
+                    slot.active -= 1;
+                    if slot.active == 0 {
+                        shared.done.notify_all();
+                    }
+                }
+                _ => {
+                    slot = shared
+                        .posted
+                        .wait(slot)
+                        .unwrap_or_else(PoisonError::into_inner);
+                }
+            }
+        }
+    }
+
+    ///
+    /// Runs `job` on every worker and on the current thread, returning once
+    /// all of them are done with it.
+    ///
+    fn run(&self, job: &(dyn Fn() + Sync)) {
+        let _running = self.running.lock().unwrap_or_else(PoisonError::into_inner);
+        // Safety: `posted` is dropped before `job` goes out of scope, even
+        // when unwinding, and it waits for every worker to be done with the
+        // job, after which no worker refers to it anymore.
+        let erased: Job = unsafe { transmute::<&(dyn Fn() + Sync), Job>(job) };
+        let posted = Posted(&self.shared);
+        {
+            let mut slot = self.shared.lock();
+            slot.job = Some(erased);
+            slot.posted += 1;
+            slot.active = self.workers.len();
+        }
+        self.shared.posted.notify_all();
+        job();
+        drop(posted);
+    }
+}
+
+// Waits for the workers to be done with the posted job once dropped.
+struct Posted<'a>(&'a Shared);
+
+impl Drop for Posted<'_> {
+    fn drop(&mut self) {
+        let mut slot = self.0.lock();
+        while slot.active > 0 {
+            slot = self
+                .0
+                .done
+                .wait(slot)
+                .unwrap_or_else(PoisonError::into_inner);
+        }
+        slot.job = None;
+    }
+}
+
+impl Drop for Pool {
+    fn drop(&mut self) {
+        self.shared.lock().shutdown = true;
+        self.shared.posted.notify_all();
+        for worker in self.workers.drain(..) {
+            let _ = worker.join();
+        }
+    }
+}
+
+impl<'s, S: Storage + Sync> Scheduler<'s, S> {
+    ///
+    /// Creates a scheduler for the systems over `storage`, which runs them
+    /// on as many threads as are available.
+    ///
+    pub fn new(storage: &'s S) -> Self {
+        Self {
+            storage,
+            systems: Vec::new(),
+            threads: available_parallelism().map_or(1, |x| x.get()),
+            pool: OnceLock::new(),
+        }
+    }
+
+    ///
+    /// Sets the largest number of systems which are run at the same time,
+    /// including the one run on the thread calling `run`.
+    ///
+    pub fn with_threads(mut self, threads: usize) -> Self {
+        self.threads = threads.max(1);
+        self.pool = OnceLock::new();
+        self
+    }
+
+    ///
+    /// Adds a system which is run with the values fetched using `P`,
+    /// waiting in the case that they are borrowed outside of the scheduler.
+    ///
+    /// A system whose fetch conflicts with itself, such as `(&mut A, &A)`,
+    /// is never run, and instead fails with `ErrorDesc::BorrowedIncompatibly`.
+    ///
+    pub fn add<P, F>(&mut self, f: F) -> &mut Self
+    where
+        P: FetchMultiple<'s, S::Unit>,
+        Borrowed<'s, S>: Waitable,
+        MutBorrowed<'s, S>: Waitable,
+        F: Fn(P::Output) + Send + Sync + 's,
+    {
+        let mut access = Access::new();
+        P::access(&mut access);
+        let storage = self.storage;
+        let valid = !access.conflicts_with_itself();
+        self.systems.push(System {
+            access,
+            run: Box::new(move || {
+                if !valid {
+                    return Err(ErrorDesc::BorrowedIncompatibly);
+                }
+                f(P::waiting_get_many(storage.black_box())?);
+                Ok(())
+            }),
+        });
+        self
+    }
+
+    ///
+    /// Runs every system once, and returns the errors the systems which
+    /// could not be run failed with, alongside the order they were added
+    /// in.
+    ///
+    /// A system which fails does not stop the ones which conflict with it
+    /// from being run. In the case that a system panics, this panics once
+    /// the other systems have finished.
+    ///
+    pub fn run(&self) -> Result<(), Vec<(usize, ErrorDesc)>> {
+        let count = self.systems.len();
+        // Each system waits on every conflicting system added before it.
+        let mut dependents = vec![Vec::new(); count];
+        let mut waiting_on = vec![0; count];
+        for (i, later) in self.systems.iter().enumerate() {
+            for (j, earlier) in self.systems[..i].iter().enumerate() {
+                if earlier.access.conflicts_with(&later.access) {
+                    dependents[j].push(i);
+                    waiting_on[i] += 1;
+                }
+            }
+        }
+        let state = Mutex::new(State {
+            ready: (0..count).filter(|&i| waiting_on[i] == 0).collect(),
+            waiting_on,
+            finished: 0,
+            errors: Vec::new(),
+            panic: None,
+        });
+        let progressed = Condvar::new();
+        let lock = || state.lock().unwrap_or_else(PoisonError::into_inner);
+
+        let work = || {
+            let mut state: MutexGuard<State> = lock();
+            loop {
+                if let Some(i) = state.ready.pop_front() {
+                    drop(state);
+                    let result = catch_unwind(AssertUnwindSafe(|| (self.systems[i].run)()));
+                    state = lock();
+                    match result {
+                        Ok(Err(e)) => state.errors.push((i, e)),
+                        Err(payload) => {
+                            state.panic.get_or_insert(payload);
+                        }
+                        Ok(Ok(())) => {}
+                    }
+                    state.finished += 1;
+                    for &j in dependents[i].iter() {
+                        state.waiting_on[j] -= 1;
+                        if state.waiting_on[j] == 0 {
+                            state.ready.push_back(j);
+                        }
+                    }
+                    progressed.notify_all();
+                } else if state.finished == count {
+                    return;
+                } else {
+                    state = progressed
+                        .wait(state)
+                        .unwrap_or_else(PoisonError::into_inner);
+                }
+            }
+        };
+        if self.threads > 1 && count > 1 {
+            self.pool
+                .get_or_init(|| Pool::new(self.threads - 1))
+                .run(&work);
+        } else {
+            work();
+        }
+
+        let state = state.into_inner().unwrap_or_else(PoisonError::into_inner);
+        if let Some(payload) = state.panic {
+            resume_unwind(payload);
+        }
+        let mut errors = state.errors;
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            errors.sort_by_key(|&(i, _)| i);
+            Err(errors)
+        }
+    }
+}
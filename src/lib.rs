@@ -1,3 +1,4 @@
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
 #![deny(bare_trait_objects)]
 #![doc(html_logo_url = "https://optimisticpeach.github.io/Logo.svg")]
 
@@ -28,6 +29,46 @@
 //! # }
 //! ```
 //!
+//! ## Features
+//! - `parking_lot` (enabled by default): Builds `RwLockStorage` and `MutexStorage` on the
+//!   locks from [`parking_lot`][pl]. In the case that it is disabled, they are instead the
+//!   [`StdRwLockStorage`][srw] and [`StdMutexStorage`][sm], which are built only on `std::sync` and can
+//!   be named whether or not this is enabled.
+//! - `std` (enabled by default): Builds on the standard library. In the case that it is
+//!   disabled, `restor` only depends on `core` and `alloc`, and storages are keyed using a
+//!   `BTreeMap` instead of a `HashMap`. `RwLockStorage` and `MutexStorage` are then the
+//!   [`SpinRwLockStorage`] and [`SpinMutexStorage`], which are built on spin locks and can
+//!   always be named. Enabling `parking_lot` also enables this.
+//! - `rayon`: Adds `par_run_for` and `par_run_for_mut`, which process the values of a unit in
+//!   parallel using [`rayon`][ry]. This also enables `std`.
+//! - `diagnostics`: Records where and on which thread each borrow held on a unit was made, which
+//!   [`BlackBox::borrow_sites`][bb] returns to find out which borrows a fetch failing with
+//!   `ErrorDesc::BorrowedIncompatibly` conflicted with. This also enables `std`.
+//! - `lock_order`: Records the order in which each thread waits on the locks of the units of
+//!   `RwLockStorage`, `MutexStorage` and the storages built on a [`RawLock`], and panics instead
+//!   of waiting in the case that the locks would be acquired in a cycle, which could deadlock,
+//!   naming the types involved. The guards of `parking_lot` are wrapped in an
+//!   [`OrderedGuard`][og] in order to record being released. This also enables `std`.
+//! - `metrics`: Counts the successful and failed fetches, the waits and the time spent waiting
+//!   for each unit, along with the most values it held, which are collected by `metrics` into
+//!   a [`Metrics`][mt] report that can be rendered in the Prometheus text format. This also
+//!   enables `std`.
+//! - `tracing`: Emits [`tracing`][tr] spans around the waits of the `waiting_*` functions, and
+//!   events for allocating units, inserting values, taking them out and failing to fetch them,
+//!   under the `restor` target. Each is annotated with the type of the unit and its state, which
+//!   is its kind and number of values. This also enables `std`.
+//!
+//! [pl]: https://docs.rs/parking_lot
+//! [srw]: ./struct.StdRwLockStorage.html
+//! [sm]: ./struct.StdMutexStorage.html
+//! [ry]: https://docs.rs/rayon
+//! [bb]: ./struct.BlackBox.html#method.borrow_sites
+//! [mt]: ./struct.Metrics.html
+//! [og]: ./struct.OrderedGuard.html
+//! [tr]: https://docs.rs/tracing
+//!
+extern crate alloc;
+
 mod black_box;
 mod concurrent_black_box;
 mod macros;
@@ -89,10 +130,39 @@ macro_rules! make_storage {
     };
     (Arc $storage:ty $(: $($contents:ty),*)? ) => {
         {
-            ::std::sync::Arc::new(make_storage!($storage $(: $($contents),*)?))
+            $crate::__private::Arc::new(make_storage!($storage $(: $($contents),*)?))
         }
     }
 }
 
-pub use black_box::{BlackBox, DynamicStorage, ErrorDesc, Fetch, FetchMultiple, Unit, UnitError};
-pub use concurrent_black_box::{MutexStorage, RwLockStorage};
+#[cfg(feature = "diagnostics")]
+pub use black_box::BorrowSite;
+pub use black_box::{
+    Access, BlackBox, BoundPolicy, Dependencies, Drain, DynamicResult, DynamicStorage, Entry,
+    ErrorDesc, Fetch, FetchMultiple, FromStorage, Order, Pop, RefCellUnit, Signal, Storage,
+    StorageUnit, Taken, Unit, UnitError, ValueSet, Waitable,
+};
+#[cfg(feature = "metrics")]
+pub use black_box::{Metrics, UnitMetrics};
+#[cfg(all(feature = "parking_lot", feature = "lock_order"))]
+pub use concurrent_black_box::OrderedGuard;
+pub use concurrent_black_box::{
+    AtomicUnit, DynLockUnit, Exclusive, Guardable, LockReadGuard, LockUnit, LockWriteGuard,
+    MixedStorage, MutexBackend, MutexStorage, RawLock, RcuUnit, RwLockBackend, RwLockStorage,
+    Shared, Snapshot, SpinMutexBackend, SpinMutexStorage, SpinRwLockBackend, SpinRwLockStorage,
+};
+#[cfg(feature = "parking_lot")]
+pub use concurrent_black_box::{MutexUnit, RwLockUnit};
+#[cfg(feature = "std")]
+pub use concurrent_black_box::{
+    Scheduler, StdMutexBackend, StdMutexStorage, StdRwLockBackend, StdRwLockStorage,
+};
+
+// Paths used by the exported macros, which must resolve in crates without
+// `std`.
+#[doc(hidden)]
+pub mod __private {
+    pub use alloc::{boxed::Box, sync::Arc, vec::Vec};
+    #[cfg(feature = "rayon")]
+    pub use rayon;
+}
@@ -8,9 +8,255 @@ macro_rules! impl_unit {
             #[inline(always)]
             pub fn allocate_for<T: $($constraint)*>(&mut self) {
                 self.$internal
-                    .data
-                    .entry(::std::any::TypeId::of::<T>())
-                    .or_insert_with(|| Box::new($storage_wrapper::new($crate::black_box::StorageUnit::<T>::new())));
+                    .allocate::<T>(|| $crate::__private::Box::new($storage_wrapper::new($crate::StorageUnit::<T>::new())));
+            }
+            #[doc = "Adds a queue unit for the given type, whose values are inserted at the back\n\
+            and taken from the front in constant time using the `Pop` and `Drain` fetch kinds.\n\n\
+            The values in a queue unit cannot be borrowed. This will not add another unit in the\n\
+            case that it already exists."]
+            #[inline(always)]
+            pub fn allocate_queue<T: $($constraint)*>(&mut self) {
+                self.$internal
+                    .allocate::<T>(|| $crate::__private::Box::new($storage_wrapper::new($crate::StorageUnit::<T>::new_queue())));
+            }
+            #[doc = "Adds a double-buffered event unit for the given type. Inserted values are only\n\
+            seen through the `&[T]` fetch kind after the next call to `swap_event_buffers`, and are\n\
+            dropped after the one following it.\n\n\
+            This will not add another unit in the case that it already exists."]
+            #[inline(always)]
+            pub fn allocate_events<T: $($constraint)*>(&mut self) {
+                self.$internal
+                    .allocate::<T>(|| $crate::__private::Box::new($storage_wrapper::new($crate::StorageUnit::<T>::new_events())));
+                self.$internal.register_events::<T>();
+            }
+            #[doc = "Adds a set unit for the given type, which holds at most one of each value.\n\
+            Inserting a value which is already present returns it alongside\n\
+            `UnitError::AlreadyPresent`, and `insert_many` inserts the values which are not\n\
+            present yet, returning the rest in the same way.\n\n\
+            The values in a set unit can only be borrowed immutably, and can be looked up\n\
+            using `contains` and `remove_value`. This will not add another unit in the case\n\
+            that it already exists."]
+            #[inline(always)]
+            pub fn allocate_set<T: ::core::hash::Hash + ::core::cmp::Eq + $($constraint)*>(&mut self) {
+                self.$internal
+                    .allocate::<T>(|| $crate::__private::Box::new($storage_wrapper::new($crate::StorageUnit::<T>::new_set())));
+            }
+            #[doc = "Adds a unit for the given type which keeps its values in ascending order,\n\
+            so that `&[T]` is sorted and `Box<T>` and `Pop<T>` take the greatest value.\n\n\
+            Values which compare equal are taken in the order they were inserted in, so they\n\
+            are kept newest first in `&[T]`. The values in a sorted unit can only be borrowed immutably. This will not add\n\
+            another unit in the case that it already exists."]
+            #[inline(always)]
+            pub fn allocate_sorted<T: ::core::cmp::Ord + $($constraint)*>(&mut self) {
+                self.$internal.allocate_unit::<T>(
+                    || $crate::__private::Box::new($storage_wrapper::new($crate::StorageUnit::<T>::new())),
+                    $crate::StorageUnit::new_sorted($crate::__private::Arc::new(<T as ::core::cmp::Ord>::cmp)),
+                );
+            }
+            #[doc = "Adds a unit for the given type which keeps its values in ascending order of\n\
+            the key returned by `key`. Please refer to `allocate_sorted` for the details."]
+            #[inline(always)]
+            pub fn allocate_sorted_by_key<T: $($constraint)*, K: ::core::cmp::Ord>(
+                &mut self,
+                key: impl ::core::ops::Fn(&T) -> K + ::core::marker::Send + ::core::marker::Sync + 'static,
+            ) {
+                self.$internal.allocate_unit::<T>(
+                    || $crate::__private::Box::new($storage_wrapper::new($crate::StorageUnit::<T>::new())),
+                    $crate::StorageUnit::new_sorted($crate::__private::Arc::new(move |a: &T, b: &T| key(a).cmp(&key(b)))),
+                );
+            }
+            #[doc = "Adds a unit for the given type which holds at most `capacity` values, and\n\
+            handles any more according to `policy`, either by rejecting them with\n\
+            `UnitError::Full`, or by evicting the oldest or least recently touched value.\n\n\
+            The values in a bounded unit can only be borrowed immutably, from oldest to newest.\n\
+            This will not add another unit in the case that it already exists."]
+            #[inline(always)]
+            pub fn allocate_bounded<T: $($constraint)*>(&mut self, capacity: usize, policy: $crate::BoundPolicy) {
+                self.$internal.allocate_unit::<T>(
+                    || $crate::__private::Box::new($storage_wrapper::new($crate::StorageUnit::<T>::new())),
+                    $crate::StorageUnit::new_bounded(capacity, policy),
+                );
+            }
+            #[doc = "Adds a storage unit for the given type, and registers it to be shown\n\
+            when the storage is formatted with `{:?}`.\n\n\
+            This will not add another unit in the case that it already exists."]
+            #[inline(always)]
+            pub fn allocate_for_debug<T: ::core::fmt::Debug + $($constraint)*>(&mut self) {
+                self.allocate_for::<T>();
+                self.$internal.register_debug::<T>();
+            }
+            #[doc = "Adds a storage unit for the given type, and registers it to be cloned\n\
+            when the storage is cloned using `try_clone` or `try_clone_into`.\n\n\
+            This will not add another unit in the case that it already exists."]
+            #[inline(always)]
+            pub fn allocate_for_clone<T: Clone + $($constraint)*>(&mut self) {
+                self.allocate_for::<T>();
+                self.$internal.register_clone::<T>();
+            }
+            #[doc = "Adds a storage unit for the given type, whose value is constructed using\n\
+            `factory` the first time it is borrowed as `&T` or `&mut T` while the unit is empty,\n\
+            instead of failing with `UnitError::IsNotOne`.\n\n\
+            The value is constructed while holding the lock on the unit, so it is only\n\
+            constructed once when several threads ask for it at the same time. A `get` which\n\
+            cannot take that lock right away fails with `ErrorDesc::BorrowedIncompatibly`, and\n\
+            checking a fetch using `can_get` never constructs the value, but succeeds for an\n\
+            empty unit which `get` would construct the value of.\n\n\
+            This will not add another unit in the case that it already exists."]
+            #[inline(always)]
+            pub fn allocate_with_factory<T: $($constraint)*>(
+                &mut self,
+                factory: impl ::core::ops::Fn() -> T + ::core::marker::Send + ::core::marker::Sync + 'static,
+            ) {
+                self.allocate_for::<T>();
+                self.$internal.register_factory::<T>(factory);
+            }
+            #[doc = "Adds a storage unit for the given type, whose value is constructed using\n\
+            `Default::default` the first time it is borrowed. Please refer to\n\
+            `allocate_with_factory` for the details."]
+            #[inline(always)]
+            pub fn allocate_default<T: ::core::default::Default + $($constraint)*>(&mut self) {
+                self.allocate_with_factory::<T>(T::default);
+            }
+            #[doc = "Adds a storage unit for the given type, and registers it to be constructed\n\
+            using its `FromStorage` implementation when its unit is empty and a type being\n\
+            constructed using `construct` depends on it.\n\n\
+            This will not add another unit in the case that it already exists."]
+            #[inline(always)]
+            pub fn allocate_from_storage<T: for<'a> $crate::FromStorage<'a, Self> + $($constraint)*>(&mut self) {
+                self.allocate_for::<T>();
+                self.$internal.register_constructor::<Self, T>();
+            }
+            #[doc = "Please refer to the documentation for this function at [`BlackBox::construct`]."]
+            #[inline(always)]
+            pub fn construct<T: for<'a> $crate::FromStorage<'a, Self> + $($constraint)*>(&self) -> $crate::DynamicResult<()> {
+                self.$internal
+                    .construct::<Self, T>()
+            }
+            #[doc = "Please refer to the documentation for this function at [`BlackBox::entry`]."]
+            #[inline(always)]
+            #[track_caller]
+            pub fn entry<'a, T: $($constraint)*>(&'a self) -> $crate::DynamicResult<$crate::Entry<'a, $unit, T>> {
+                self.$internal
+                    .entry::<T>()
+            }
+            #[doc = "Creates a new storage with the same units allocated, cloning the contents\n\
+            of every unit allocated using `allocate_for_clone`.\n\n\
+            In the case that a unit could not be cloned, because its type was not allocated\n\
+            using `allocate_for_clone` or because it is currently borrowed, the new storage\n\
+            is returned alongside the names of those types and the reason for each one."]
+            pub fn try_clone(&self) -> Result<Self, (Self, $crate::__private::Vec<(&'static str, $crate::ErrorDesc)>)> {
+                let (black_box, failed) = self.$internal.try_clone();
+                let cloned = Self { $internal: black_box };
+                if failed.is_empty() {
+                    Ok(cloned)
+                } else {
+                    Err((cloned, failed))
+                }
+            }
+            #[doc = "Please refer to the documentation for this function at [`BlackBox::try_clone_into`]."]
+            #[inline(always)]
+            pub fn try_clone_into<S: $crate::Storage>(&self, other: &S) -> Result<(), $crate::__private::Vec<(&'static str, $crate::ErrorDesc)>> {
+                self.$internal
+                    .try_clone_into(other.black_box())
+            }
+            #[doc = "Moves every unit of `other` into this storage. In the case that both storages\n\
+            have a unit allocated for a type, the values of `other` are appended to the ones\n\
+            in this storage.\n\n\
+            In the case that the values of any unit could not all be moved, such as when the\n\
+            unit in this storage is a full bounded unit, those units are returned in a storage\n\
+            holding the values which were not moved, alongside the names of their types and\n\
+            the reason why, sorted by name."]
+            #[inline(always)]
+            pub fn merge_from(&mut self, other: Self) -> ::core::result::Result<(), (Self, $crate::__private::Vec<(&'static str, $crate::ErrorDesc)>)> {
+                self.$internal
+                    .merge_from(other.$internal)
+                    .map_err(|(black_box, failed)| (Self { $internal: black_box }, failed))
+            }
+            #[doc = "Please refer to the documentation for this function at [`BlackBox::transfer`]."]
+            #[inline(always)]
+            pub fn transfer<T: $($constraint)*, S: $crate::Storage>(&self, to: &S) -> $crate::DynamicResult<()> {
+                self.$internal
+                    .transfer::<T, S::Unit>(to.black_box())
+            }
+            #[doc = "Please refer to the documentation for this function at [`BlackBox::swap_event_buffers`]."]
+            #[inline(always)]
+            pub fn swap_event_buffers(&self) -> $crate::DynamicResult<()> {
+                self.$internal
+                    .swap_event_buffers()
+            }
+            #[doc = "Please refer to the documentation for this function at [`BlackBox::contains`]."]
+            #[inline(always)]
+            pub fn contains<T: $($constraint)*>(&self, value: &T) -> $crate::DynamicResult<bool> {
+                self.$internal
+                    .contains::<T>(value)
+            }
+            #[doc = "Please refer to the documentation for this function at [`BlackBox::remove_value`]."]
+            #[inline(always)]
+            pub fn remove_value<T: $($constraint)*>(&self, value: &T) -> $crate::DynamicResult<T> {
+                self.$internal
+                    .remove_value::<T>(value)
+            }
+            $crate::__if_rayon! {
+                #[doc = "Please refer to the documentation for this function at [`BlackBox::par_run_for`]."]
+                #[inline(always)]
+                pub fn par_run_for<
+                    T: ::core::marker::Sync + $($constraint)*,
+                    D,
+                    F: ::core::ops::FnOnce($crate::__private::rayon::slice::Iter<'_, T>) -> D
+                >(
+                    &self,
+                    f: F
+                ) -> $crate::DynamicResult<D> {
+                    self.$internal
+                        .par_run_for(f)
+                }
+                #[doc = "Please refer to the documentation for this function at [`BlackBox::par_run_for_mut`]."]
+                #[inline(always)]
+                pub fn par_run_for_mut<
+                    T: ::core::marker::Send + $($constraint)*,
+                    D,
+                    F: ::core::ops::FnOnce($crate::__private::rayon::slice::IterMut<'_, T>) -> D
+                >(
+                    &self,
+                    f: F
+                ) -> $crate::DynamicResult<D> {
+                    self.$internal
+                        .par_run_for_mut(f)
+                }
+            }
+            $crate::__if_metrics! {
+                #[doc = "Please refer to the documentation for this function at [`BlackBox::metrics`]."]
+                #[inline(always)]
+                pub fn metrics(&self) -> $crate::Metrics {
+                    self.$internal
+                        .metrics()
+                }
+            }
+            $crate::__if_diagnostics! {
+                #[doc = "Please refer to the documentation for this function at [`BlackBox::borrow_sites`]."]
+                #[inline(always)]
+                pub fn borrow_sites<T: $($constraint)*>(&self) -> $crate::__private::Vec<$crate::BorrowSite> {
+                    self.$internal
+                        .borrow_sites::<T>()
+                }
+            }
+            #[doc = "Please refer to the documentation for this function at [`BlackBox::touch`]."]
+            #[inline(always)]
+            pub fn touch<T: $($constraint)*, F: FnMut(&T) -> bool>(&self, f: F) -> $crate::DynamicResult<()> {
+                self.$internal
+                    .touch::<T, F>(f)
+            }
+            #[doc = "Please refer to the documentation for this function at [`BlackBox::try_take`]."]
+            #[inline(always)]
+            pub fn try_take<T: $($constraint)*>(&self) -> $crate::DynamicResult<T> {
+                self.$internal
+                    .try_take::<T>()
+            }
+            #[doc = "Please refer to the documentation for this function at [`BlackBox::try_take_many`]."]
+            #[inline(always)]
+            pub fn try_take_many<T: $($constraint)*>(&self) -> $crate::DynamicResult<$crate::__private::Vec<T>> {
+                self.$internal
+                    .try_take_many::<T>()
             }
             #[doc = "Please refer to the documentation for this function at [`BlackBox::has_unit`]."]
             #[inline(always)]
@@ -26,7 +272,7 @@ macro_rules! impl_unit {
             }
             #[doc = "Please refer to the documentation for this function at [`BlackBox::insert_many`]."]
             #[inline(always)]
-            pub fn insert_many<T: $($constraint)*>(&self, data: Vec<T>) -> Result<(), (Vec<T>, $crate::black_box::ErrorDesc)> {
+            pub fn insert_many<T: $($constraint)*>(&self, data: $crate::__private::Vec<T>) -> Result<(), ($crate::__private::Vec<T>, $crate::ErrorDesc)> {
                 self.$internal
                     .insert_many(data)
             }
@@ -35,26 +281,66 @@ macro_rules! impl_unit {
             #[inline(always)]
             pub fn run_for_mut<
                 T: $($constraint)*,
-                D: 'static + Any,
-                F: FnMut(&mut Vec<T>) -> D
+                D: 'static + ::core::any::Any,
+                F: FnMut(&mut $crate::__private::Vec<T>) -> D
             >(
                 &self,
                 f: F
-            ) -> $crate::black_box::DynamicResult<D> {
+            ) -> $crate::DynamicResult<D> {
                 self.$internal
                     .run_for_mut(f)
             }
 
             #[doc = "Please refer to the documentation for this function at [`BlackBox::get`]."]
             #[inline(always)]
+            #[track_caller]
             pub fn get<
                 'a,
                 T: $crate::FetchMultiple<'a, $unit>,
-            > (&'a self) -> $crate::black_box::DynamicResult<T::Output>
+            > (&'a self) -> $crate::DynamicResult<T::Output>
             where <T as $crate::FetchMultiple<'a, $unit>>::Actual: $($constraint)*{
                 self.$internal
                     .get::<T>()
             }
+            #[doc = "Please refer to the documentation for this function at [`BlackBox::can_get`]."]
+            #[inline(always)]
+            #[track_caller]
+            pub fn can_get<
+                'a,
+                T: $crate::FetchMultiple<'a, $unit>,
+            > (&'a self) -> $crate::DynamicResult<()>
+            where <T as $crate::FetchMultiple<'a, $unit>>::Actual: $($constraint)*{
+                self.$internal
+                    .can_get::<T>()
+            }
+            #[doc = "Please refer to the documentation for this function at [`BlackBox::access`]."]
+            #[inline(always)]
+            pub fn access<'a, T: $crate::FetchMultiple<'a, $unit>>() -> $crate::Access {
+                $crate::BlackBox::<$unit>::access::<T>()
+            }
+            #[doc = "Please refer to the documentation for this function at [`BlackBox::conflicts`]."]
+            #[inline(always)]
+            pub fn conflicts<
+                'a,
+                T: $crate::FetchMultiple<'a, $unit>,
+                V: $crate::FetchMultiple<'a, $unit>,
+            >() -> bool {
+                $crate::BlackBox::<$unit>::conflicts::<T, V>()
+            }
+        }
+
+        impl $crate::Storage for $name {
+            type Unit = $unit;
+            #[inline(always)]
+            fn black_box(&self) -> &$crate::BlackBox<$unit> {
+                &self.$internal
+            }
+        }
+
+        impl ::core::fmt::Debug for $name {
+            fn fmt(&self, f: &mut ::core::fmt::Formatter) -> ::core::fmt::Result {
+                self.$internal.fmt_units(stringify!($name), f)
+            }
         }
     };
     ($name:ident, $traitobject:ty, ($($constraint:tt)*), $storage_wrapper:ident($unit:ty$(,)?), $mutlock:ident, $unmutlock:ident, $internal:ident, add_unmut $(, $($rest:tt)*)?) => {
@@ -63,12 +349,12 @@ macro_rules! impl_unit {
             #[inline(always)]
             pub fn run_for<
                 T: $($constraint)*,
-                D: 'static + Any,
+                D: 'static + ::core::any::Any,
                 F: FnMut(&[T]) -> D,
             >(
                 &self,
                 f: F,
-            ) -> $crate::black_box::DynamicResult<D> {
+            ) -> $crate::DynamicResult<D> {
                 self.$internal
                     .run_for(f)
             }
@@ -79,19 +365,249 @@ macro_rules! impl_unit {
         impl $name {
             #[doc = "Please refer to the documentation for this function at [`BlackBox::waiting_get`]."]
             #[inline(always)]
-            pub fn waiting_get<'a, T: $crate::black_box::FetchMultiple<'a, $unit>>(&'a self) -> $crate::black_box::DynamicResult<T::Output>
+            #[track_caller]
+            pub fn waiting_get<'a, T: $crate::FetchMultiple<'a, $unit>>(&'a self) -> $crate::DynamicResult<T::Output>
             where
-                $crate::black_box::Borrowed<'a, $unit>: $crate::black_box::Waitable,
-                $crate::black_box::MutBorrowed<'a, $unit>: $crate::black_box::Waitable,
+                <$unit as $crate::Unit<'a>>::Borrowed: $crate::Waitable,
+                <$unit as $crate::Unit<'a>>::MutBorrowed: $crate::Waitable,
                 <T as $crate::FetchMultiple<'a, $unit>>::Actual: $($constraint)*
             {
                 self.$internal
                     .waiting_get::<T>()
             }
+            #[doc = "Please refer to the documentation for this function at [`BlackBox::waiting_entry`]."]
+            #[inline(always)]
+            #[track_caller]
+            pub fn waiting_entry<'a, T: $($constraint)*>(&'a self) -> $crate::DynamicResult<$crate::Entry<'a, $unit, T>> {
+                self.$internal
+                    .waiting_entry::<T>()
+            }
+            #[doc = "Please refer to the documentation for this function at [`BlackBox::waiting_insert`]."]
+            #[inline(always)]
+            pub fn waiting_insert<T: $($constraint)*>(&self, data: T) -> Result<(), (T, $crate::ErrorDesc)> {
+                self.$internal
+                    .waiting_insert(data)
+            }
+            #[doc = "Please refer to the documentation for this function at [`BlackBox::waiting_insert_many`]."]
+            #[inline(always)]
+            pub fn waiting_insert_many<T: $($constraint)*>(&self, data: $crate::__private::Vec<T>) -> Result<(), ($crate::__private::Vec<T>, $crate::ErrorDesc)> {
+                self.$internal
+                    .waiting_insert_many(data)
+            }
+            #[doc = "Please refer to the documentation for this function at [`BlackBox::waiting_take`]."]
+            #[inline(always)]
+            pub fn waiting_take<T: $($constraint)*>(&self) -> $crate::DynamicResult<T> {
+                self.$internal
+                    .waiting_take::<T>()
+            }
+            #[doc = "Please refer to the documentation for this function at [`BlackBox::waiting_take_many`]."]
+            #[inline(always)]
+            pub fn waiting_take_many<T: $($constraint)*>(&self) -> $crate::DynamicResult<$crate::__private::Vec<T>> {
+                self.$internal
+                    .waiting_take_many::<T>()
+            }
+            $crate::__if_std! {
+                #[doc = "Please refer to the documentation for this function at [`BlackBox::waiting_take_timeout`]."]
+                #[inline(always)]
+                pub fn waiting_take_timeout<T: $($constraint)*>(&self, timeout: ::core::time::Duration) -> $crate::DynamicResult<T> {
+                    self.$internal
+                        .waiting_take_timeout::<T>(timeout)
+                }
+                #[doc = "Please refer to the documentation for this function at [`BlackBox::waiting_take_many_timeout`]."]
+                #[inline(always)]
+                pub fn waiting_take_many_timeout<T: $($constraint)*>(&self, timeout: ::core::time::Duration) -> $crate::DynamicResult<$crate::__private::Vec<T>> {
+                    self.$internal
+                        .waiting_take_many_timeout::<T>(timeout)
+                }
+            }
         }
     };
 }
 
+// Expands to the items passed to it only in the case that `restor` is
+// built with the `std` feature, for use in the exported macros, where a
+// `cfg` would instead refer to the features of the calling crate.
+#[cfg(feature = "std")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __if_std {
+    ($($item:tt)*) => {
+        $($item)*
+    };
+}
+
+#[cfg(not(feature = "std"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __if_std {
+    ($($item:tt)*) => {};
+}
+
+// The same as `__if_std`, for the `rayon` feature.
+#[cfg(feature = "rayon")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __if_rayon {
+    ($($item:tt)*) => {
+        $($item)*
+    };
+}
+
+#[cfg(not(feature = "rayon"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __if_rayon {
+    ($($item:tt)*) => {};
+}
+
+// The same as `__if_std`, for the `metrics` feature.
+#[cfg(feature = "metrics")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __if_metrics {
+    ($($item:tt)*) => {
+        $($item)*
+    };
+}
+
+#[cfg(not(feature = "metrics"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __if_metrics {
+    ($($item:tt)*) => {};
+}
+
+// The same as `__if_std`, for the `diagnostics` feature.
+#[cfg(feature = "diagnostics")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __if_diagnostics {
+    ($($item:tt)*) => {
+        $($item)*
+    };
+}
+
+#[cfg(not(feature = "diagnostics"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __if_diagnostics {
+    ($($item:tt)*) => {};
+}
+
+///
+/// Defines a storage over a custom lock, which only needs to implement
+/// [`RawLock`](./trait.RawLock.html).
+///
+/// The storage gets the same api as any other storage provided by
+/// `restor`, including `get`, `waiting_get`, `insert` and `run_for`, and can
+/// therefore be used in the same contexts. The types which can be allocated
+/// in it depend on the [`RawLock::Kind`](./trait.RawLock.html#associatedtype.Kind)
+/// of the lock, as described by [`Guardable`](./trait.Guardable.html).
+///
+/// # Usage
+/// ```ignore
+/// define_storage! {
+///     /// Documentation for the storage
+///     pub struct StorageName(LockType);
+/// }
+/// ```
+///
+/// # Example
+/// ```
+/// # fn main() {
+/// use restor::{define_storage, RawLock, Shared};
+/// use std::sync::atomic::{AtomicUsize, Ordering};
+///
+/// const WRITER: usize = usize::max_value();
+///
+/// pub struct SpinLock(AtomicUsize);
+///
+/// unsafe impl RawLock for SpinLock {
+///     type Kind = Shared;
+///     fn new() -> Self {
+///         SpinLock(AtomicUsize::new(0))
+///     }
+///     fn lock_shared(&self) {
+///         while !self.try_lock_shared() {}
+///     }
+///     fn try_lock_shared(&self) -> bool {
+///         let readers = self.0.load(Ordering::Relaxed);
+///         readers != WRITER
+///             && self
+///                 .0
+///                 .compare_exchange(readers, readers + 1, Ordering::Acquire, Ordering::Relaxed)
+///                 .is_ok()
+///     }
+///     unsafe fn unlock_shared(&self) {
+///         self.0.fetch_sub(1, Ordering::Release);
+///     }
+///     fn lock_exclusive(&self) {
+///         while !self.try_lock_exclusive() {}
+///     }
+///     fn try_lock_exclusive(&self) -> bool {
+///         self.0
+///             .compare_exchange(0, WRITER, Ordering::Acquire, Ordering::Relaxed)
+///             .is_ok()
+///     }
+///     unsafe fn unlock_exclusive(&self) {
+///         self.0.store(0, Ordering::Release);
+///     }
+/// }
+///
+/// define_storage! {
+///     /// A storage guarded by spinlocks.
+///     pub struct SpinStorage(SpinLock);
+/// }
+///
+/// let mut storage = SpinStorage::new();
+/// storage.allocate_for::<usize>();
+/// storage.insert_many(vec![1usize, 2, 3]).unwrap();
+/// let sum = storage.run_for::<usize, _, _>(|x| x.iter().sum::<usize>()).unwrap();
+/// assert_eq!(sum, 6);
+/// # }
+/// ```
+///
+#[macro_export]
+macro_rules! define_storage {
+    ($(#[$attr:meta])* $vis:vis struct $name:ident($lock:ty);) => {
+        $(#[$attr])*
+        $vis struct $name {
+            pub(crate) black_box: $crate::BlackBox<$crate::DynLockUnit>,
+        }
+
+        const _: () = {
+            use ::core::any::Any;
+            type Wrapper<T> = $crate::LockUnit<$lock, T>;
+
+            $crate::impl_unit!(
+                $name,
+                (dyn Any + Send),
+                ($crate::Guardable<<$lock as $crate::RawLock>::Kind> + Any),
+                Wrapper($crate::DynLockUnit),
+                LockWriteGuard,
+                LockReadGuard,
+                black_box,
+                add_unmut,
+                add_waiting
+            );
+
+            impl $name {
+                pub fn new() -> Self {
+                    $name {
+                        black_box: $crate::BlackBox::new(),
+                    }
+                }
+            }
+
+            impl ::core::default::Default for $name {
+                fn default() -> Self {
+                    Self::new()
+                }
+            }
+        };
+    };
+}
+
 ///
 /// A shorthand for unwrapping a `Result` into an `Ok(x)`.
 ///
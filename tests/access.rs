@@ -0,0 +1,168 @@
+#![allow(unused)]
+
+use restor::{
+    err, make_storage, ok, Access, Drain, DynamicStorage, ErrorDesc, MutexStorage, Pop,
+    RwLockStorage, UnitError,
+};
+use std::any::TypeId;
+
+#[test]
+fn access() {
+    let access = RwLockStorage::access::<(&usize, &mut String, Box<u8>, &[u16], Pop<u32>)>();
+    assert_eq!(
+        access.reads(),
+        &[TypeId::of::<usize>(), TypeId::of::<u16>()]
+    );
+    assert_eq!(
+        access.writes(),
+        &[
+            TypeId::of::<String>(),
+            TypeId::of::<u8>(),
+            TypeId::of::<u32>()
+        ]
+    );
+    assert!(!access.conflicts_with_itself());
+    assert!(!DynamicStorage::access::<(Vec<usize>, &usize)>().conflicts_with_itself());
+    assert!(DynamicStorage::access::<(&usize, Vec<usize>)>().conflicts_with_itself());
+    assert!(DynamicStorage::access::<(&mut usize, &mut usize)>().conflicts_with_itself());
+    assert!(!DynamicStorage::access::<(&usize, &usize)>().conflicts_with_itself());
+}
+
+#[test]
+fn take_then_borrow() {
+    let storage = make_storage!(DynamicStorage: usize);
+    storage.insert_many(vec![0usize, 1, 2]).unwrap();
+    assert!(!DynamicStorage::access::<(Box<usize>, &[usize])>().conflicts_with_itself());
+    let (taken, rest) = ok!(storage.get::<(Box<usize>, &[usize])>());
+    assert_eq!(taken, 0);
+    assert_eq!(&*rest, &[1, 2]);
+    drop(rest);
+    assert!(DynamicStorage::access::<(&[usize], Box<usize>)>().conflicts_with_itself());
+    err!(
+        storage.get::<(&[usize], Box<usize>)>(),
+        ErrorDesc::BorrowedIncompatibly
+    );
+}
+
+#[test]
+fn conflicts() {
+    assert!(!RwLockStorage::conflicts::<&usize, (&usize, &String)>());
+    assert!(!RwLockStorage::conflicts::<&mut usize, &mut String>());
+    assert!(RwLockStorage::conflicts::<&usize, &mut usize>());
+    assert!(RwLockStorage::conflicts::<Drain<usize>, (&String, &[usize])>());
+    assert!(MutexStorage::conflicts::<Vec<usize>, Box<usize>>());
+}
+
+#[test]
+fn can_get() {
+    let mut x = DynamicStorage::new();
+    x.allocate_for::<usize>();
+    x.allocate_for::<String>();
+    x.allocate_queue::<u8>();
+    x.insert(0usize).unwrap();
+    x.insert_many(vec![String::new(), String::new()]).unwrap();
+    ok!(x.can_get::<&usize>());
+    ok!(x.can_get::<(&mut usize, &[String])>());
+    ok!(x.can_get::<(Vec<String>, Box<usize>)>());
+    ok!(x.can_get::<Drain<u8>>());
+    err!(x.can_get::<&String>(), ErrorDesc::Unit(UnitError::IsNotOne));
+    err!(
+        x.can_get::<&[usize]>(),
+        ErrorDesc::Unit(UnitError::IsNotMany)
+    );
+    err!(x.can_get::<Pop<u8>>(), ErrorDesc::Unit(UnitError::IsNope));
+    err!(x.can_get::<&isize>(), ErrorDesc::NoAllocatedUnit);
+    err!(
+        x.can_get::<(&usize, &mut usize)>(),
+        ErrorDesc::BorrowedIncompatibly
+    );
+    {
+        let _borrowed = ok!(x.get::<&mut usize>());
+        err!(x.can_get::<&usize>(), ErrorDesc::BorrowedIncompatibly);
+        ok!(x.can_get::<&[String]>());
+    }
+    // Checking left everything in place.
+    x.insert(1u8).unwrap();
+    ok!(x.can_get::<Pop<u8>>());
+    ok!(x.get::<Pop<u8>>(), 1);
+    ok!(x.get::<Box<usize>>(), 0);
+    assert_eq!(ok!(x.get::<Vec<String>>()).len(), 2);
+}
+
+#[test]
+fn can_get_backends() {
+    let rwlock = make_storage!(RwLockStorage: usize);
+    let mutex = make_storage!(MutexStorage: usize, String);
+    rwlock.insert(0usize).unwrap();
+    mutex.insert(0usize).unwrap();
+    mutex.insert(String::new()).unwrap();
+    ok!(rwlock.can_get::<(&usize, &usize)>());
+    err!(
+        mutex.can_get::<(&mut usize, Box<usize>)>(),
+        ErrorDesc::BorrowedIncompatibly
+    );
+    ok!(mutex.can_get::<(&mut usize, &mut String)>());
+    let _borrowed = ok!(rwlock.get::<&usize>());
+    ok!(rwlock.can_get::<&usize>());
+    err!(
+        rwlock.can_get::<Box<usize>>(),
+        ErrorDesc::BorrowedIncompatibly
+    );
+}
+
+#[test]
+fn can_get_after_takes() {
+    // Each check is made on a fresh storage, and matched against `get`.
+    macro_rules! check {
+        ($storage:expr, $fetch:ty, $expected:expr) => {
+            let x = $storage;
+            assert_eq!(x.can_get::<$fetch>(), $expected);
+            assert_eq!(x.get::<$fetch>().map(drop), $expected);
+        };
+    }
+    let one = || {
+        let x = make_storage!(DynamicStorage: usize);
+        x.insert(0usize).unwrap();
+        x
+    };
+    let many = || {
+        let x = make_storage!(DynamicStorage: usize);
+        x.insert_many(vec![0usize, 1, 2]).unwrap();
+        x
+    };
+    let queue = || {
+        let mut x = DynamicStorage::new();
+        x.allocate_queue::<usize>();
+        x.insert(0usize).unwrap();
+        x
+    };
+    let factory = || {
+        let mut x = DynamicStorage::new();
+        x.allocate_with_factory::<usize>(|| 1);
+        x.insert(0usize).unwrap();
+        x
+    };
+    let not_one = Err(ErrorDesc::Unit(UnitError::IsNotOne));
+    check!(one(), (Box<usize>, Box<usize>), not_one);
+    check!(one(), (Box<usize>, &usize), not_one);
+    check!(one(), (Pop<usize>, &mut usize), not_one);
+    check!(many(), (Box<usize>, Box<usize>), Ok(()));
+    check!(many(), (Box<usize>, &[usize]), Ok(()));
+    check!(many(), (Pop<usize>, Pop<usize>, &usize), Ok(()));
+    check!(
+        many(),
+        (Vec<usize>, &[usize]),
+        Err(ErrorDesc::Unit(UnitError::IsNotMany))
+    );
+    check!(
+        many(),
+        (Drain<usize>, Pop<usize>),
+        Err(ErrorDesc::Unit(UnitError::IsNope))
+    );
+    check!(
+        queue(),
+        (Pop<usize>, Pop<usize>),
+        Err(ErrorDesc::Unit(UnitError::IsNope))
+    );
+    check!(factory(), (Box<usize>, &usize), Ok(()));
+}
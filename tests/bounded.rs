@@ -0,0 +1,184 @@
+#![allow(unused)]
+
+use restor::{
+    err, ok, BoundPolicy, Drain, DynamicStorage, ErrorDesc, MutexStorage, Pop, RwLockStorage,
+    UnitError,
+};
+use std::sync::Arc;
+use std::thread::spawn;
+
+#[test]
+fn reject() {
+    let mut x = DynamicStorage::new();
+    x.allocate_bounded::<usize>(3, BoundPolicy::Reject);
+    x.insert_many(vec![0usize, 1]).unwrap();
+    err!(
+        x.insert_many(vec![2usize, 3]),
+        (vec![2, 3], ErrorDesc::Unit(UnitError::Full))
+    );
+    x.insert(2usize).unwrap();
+    err!(x.insert(3usize), (3, ErrorDesc::Unit(UnitError::Full)));
+    assert_eq!(&*ok!(x.get::<&[usize]>()), &[0, 1, 2]);
+    ok!(x.get::<Pop<usize>>(), 0);
+    x.insert(3usize).unwrap();
+    assert_eq!(&*ok!(x.get::<&[usize]>()), &[1, 2, 3]);
+}
+
+#[test]
+fn evict_oldest() {
+    let mut x = RwLockStorage::new();
+    x.allocate_bounded::<usize>(3, BoundPolicy::EvictOldest);
+    x.insert_many((0..5usize).collect()).unwrap();
+    assert_eq!(&*ok!(x.get::<&[usize]>()), &[2, 3, 4]);
+    x.insert(5usize).unwrap();
+    assert_eq!(&*ok!(x.get::<&[usize]>()), &[3, 4, 5]);
+    err!(
+        x.touch::<usize, _>(|x| *x == 3),
+        ErrorDesc::Unit(UnitError::Unsupported)
+    );
+    ok!(x.get::<Box<usize>>(), 3);
+}
+
+#[test]
+fn least_recently_touched() {
+    let mut x = DynamicStorage::new();
+    x.allocate_bounded::<&'static str>(3, BoundPolicy::EvictLeastRecentlyTouched);
+    x.insert_many(vec!["a", "b", "c"]).unwrap();
+    ok!(x.touch::<&str, _>(|x| *x == "a"));
+    ok!(x.touch::<&str, _>(|x| *x == "b"));
+    err!(
+        x.touch::<&str, _>(|x| *x == "z"),
+        ErrorDesc::Unit(UnitError::IsNope)
+    );
+    x.insert("d").unwrap();
+    assert_eq!(&*ok!(x.get::<&[&str]>()), &["a", "b", "d"]);
+    x.insert("e").unwrap();
+    assert_eq!(&*ok!(x.get::<&[&str]>()), &["b", "d", "e"]);
+}
+
+#[test]
+fn wrapped() {
+    let mut x = DynamicStorage::new();
+    x.allocate_bounded::<usize>(3, BoundPolicy::EvictOldest);
+    for i in 0..10usize {
+        x.insert(i).unwrap();
+        if i % 3 == 2 {
+            ok!(x.get::<Pop<usize>>());
+        }
+        let values = ok!(x.get::<&[usize]>());
+        assert!(values.windows(2).all(|w| w[0] + 1 == w[1]));
+        assert_eq!(values.last(), Some(&i));
+    }
+}
+
+#[test]
+fn wrapped_many_times() {
+    let mut x = DynamicStorage::new();
+    x.allocate_bounded::<usize>(4, BoundPolicy::EvictLeastRecentlyTouched);
+    let mut expected = std::collections::VecDeque::new();
+    for i in 0..200usize {
+        x.insert(i).unwrap();
+        expected.push_back(i);
+        if expected.len() > 4 {
+            expected.pop_front();
+        }
+        if i % 7 == 0 {
+            let oldest = expected[0];
+            ok!(x.touch::<usize, _>(|x| *x == oldest));
+            expected.rotate_left(1);
+        }
+        if i % 11 == 0 {
+            ok!(x.get::<Pop<usize>>(), expected.pop_front().unwrap());
+        }
+        assert_eq!(
+            &*ok!(x.get::<&[usize]>()),
+            expected.iter().copied().collect::<Vec<_>>().as_slice()
+        );
+    }
+}
+
+#[test]
+fn not_mutable() {
+    let mut x = DynamicStorage::new();
+    x.allocate_bounded::<usize>(2, BoundPolicy::EvictOldest);
+    x.insert(0usize).unwrap();
+    err!(x.get::<&usize>(), ErrorDesc::Unit(UnitError::Unsupported));
+    err!(
+        x.get::<&mut [usize]>(),
+        ErrorDesc::Unit(UnitError::Unsupported)
+    );
+}
+
+#[test]
+fn transfer_keeps_rest() {
+    let mut x = DynamicStorage::new();
+    x.allocate_for::<usize>();
+    x.insert_many(vec![0usize, 1, 2, 3]).unwrap();
+    let mut y = DynamicStorage::new();
+    y.allocate_bounded::<usize>(3, BoundPolicy::Reject);
+    y.insert(10usize).unwrap();
+    err!(x.transfer::<usize, _>(&y), ErrorDesc::Unit(UnitError::Full));
+    assert_eq!(&*ok!(y.get::<&[usize]>()), &[10, 0, 1]);
+    assert_eq!(&*ok!(x.get::<&[usize]>()), &[2, 3]);
+}
+
+#[test]
+fn merge_keeps_rest() {
+    let mut x = DynamicStorage::new();
+    x.allocate_bounded::<usize>(1, BoundPolicy::Reject);
+    x.allocate_for::<isize>();
+    x.insert(1usize).unwrap();
+    let mut y = DynamicStorage::new();
+    y.allocate_for::<usize>();
+    y.allocate_for::<isize>();
+    y.insert_many(vec![2usize, 3]).unwrap();
+    y.insert(-1isize).unwrap();
+    let (rest, failed) = x.merge_from(y).unwrap_err();
+    assert_eq!(failed, vec![("usize", ErrorDesc::Unit(UnitError::Full))]);
+    assert_eq!(&*ok!(x.get::<&[usize]>()), &[1]);
+    ok!(x.get::<&isize>(), -1, *);
+    assert_eq!(&*ok!(rest.get::<&[usize]>()), &[2, 3]);
+    assert!(!rest.has_unit::<isize>());
+}
+
+#[test]
+fn clone() {
+    let mut x = RwLockStorage::new();
+    x.allocate_bounded::<usize>(2, BoundPolicy::EvictOldest);
+    x.allocate_for_clone::<usize>();
+    x.insert_many(vec![0usize, 1]).unwrap();
+    let y = x.try_clone().unwrap();
+    y.insert(2usize).unwrap();
+    assert_eq!(&*ok!(y.get::<&[usize]>()), &[1, 2]);
+}
+
+#[test]
+fn clone_keeps_kind() {
+    let mut x = DynamicStorage::new();
+    x.allocate_bounded::<usize>(1, BoundPolicy::Reject);
+    x.insert(0usize).unwrap();
+    let (y, _) = x.try_clone().unwrap_err();
+    y.insert(1usize).unwrap();
+    err!(y.insert(2usize), (2, ErrorDesc::Unit(UnitError::Full)));
+}
+
+#[test]
+fn telemetry() {
+    let mut x = MutexStorage::new();
+    x.allocate_bounded::<usize>(10, BoundPolicy::EvictOldest);
+    let x = Arc::new(x);
+    let threads = (0..4usize)
+        .map(|i| {
+            let xc = x.clone();
+            spawn(move || {
+                for j in 0..100 {
+                    xc.waiting_insert(i * 100 + j).unwrap();
+                }
+            })
+        })
+        .collect::<Vec<_>>();
+    for t in threads {
+        t.join().unwrap();
+    }
+    assert_eq!(ok!(x.get::<Drain<usize>>()).len(), 10);
+}
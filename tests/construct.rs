@@ -0,0 +1,242 @@
+#![allow(unused)]
+
+use restor::{
+    err, ok, Dependencies, DynamicStorage, ErrorDesc, FetchMultiple, FromStorage, MutexStorage,
+    RwLockStorage, Storage, UnitError,
+};
+use std::sync::{Arc, Barrier, OnceLock};
+use std::thread::spawn;
+
+#[derive(Debug, Default)]
+struct Config {
+    url: &'static str,
+}
+
+#[derive(Debug)]
+struct Database {
+    url: String,
+}
+
+#[derive(Debug)]
+struct Users {
+    table: String,
+}
+
+#[derive(Debug)]
+struct Logger;
+
+impl<'a> FromStorage<'a, RwLockStorage> for Database {
+    type Deps = &'a Config;
+    fn from_storage(config: Dependencies<'a, Self, RwLockStorage>) -> Self {
+        Database {
+            url: config.url.to_string(),
+        }
+    }
+}
+
+impl<'a> FromStorage<'a, RwLockStorage> for Users {
+    type Deps = (&'a Database, &'a mut Logger);
+    fn from_storage((database, _): Dependencies<'a, Self, RwLockStorage>) -> Self {
+        Users {
+            table: format!("{}/users", database.url),
+        }
+    }
+}
+
+fn services() -> RwLockStorage {
+    let mut x = RwLockStorage::new();
+    x.allocate_for::<Config>();
+    x.allocate_for::<Logger>();
+    x.allocate_from_storage::<Database>();
+    x.allocate_from_storage::<Users>();
+    x.insert(Config { url: "db://a" }).unwrap();
+    x.insert(Logger).unwrap();
+    x
+}
+
+#[test]
+fn dependencies() {
+    let x = services();
+    ok!(x.construct::<Users>());
+    assert_eq!(ok!(x.get::<&Users>()).table, "db://a/users");
+    assert_eq!(ok!(x.get::<&Database>()).url, "db://a");
+
+    // Dependencies which already have a value are used as they are.
+    let x = services();
+    x.insert(Database {
+        url: "db://b".to_string(),
+    })
+    .unwrap();
+    ok!(x.construct::<Users>());
+    assert_eq!(ok!(x.get::<&Users>()).table, "db://b/users");
+}
+
+#[test]
+fn occupied() {
+    let x = services();
+    ok!(x.construct::<Database>());
+    err!(
+        x.construct::<Database>(),
+        ErrorDesc::Unit(UnitError::AlreadyPresent)
+    );
+    assert_eq!(ok!(x.get::<&Database>()).url, "db://a");
+}
+
+// Waits for `raced` to insert another value while it is being built.
+#[derive(Debug, PartialEq)]
+struct Raced(usize);
+
+static RACE: OnceLock<Barrier> = OnceLock::new();
+
+impl<'a> FromStorage<'a, RwLockStorage> for Raced {
+    type Deps = &'a Config;
+    fn from_storage(_: Dependencies<'a, Self, RwLockStorage>) -> Self {
+        let race = RACE.get().unwrap();
+        race.wait();
+        race.wait();
+        Raced(0)
+    }
+}
+
+#[test]
+fn raced() {
+    RACE.set(Barrier::new(2)).unwrap();
+    let mut x = services();
+    x.allocate_from_storage::<Raced>();
+    let x = Arc::new(x);
+    let xc = x.clone();
+    let handle = spawn(move || xc.construct::<Raced>());
+    let race = RACE.get().unwrap();
+    race.wait();
+    x.insert(Raced(1)).unwrap();
+    race.wait();
+    err!(
+        handle.join().unwrap(),
+        ErrorDesc::Unit(UnitError::AlreadyPresent)
+    );
+    ok!(x.get::<&Raced>(), Raced(1), *);
+}
+
+#[test]
+fn missing() {
+    let mut x = RwLockStorage::new();
+    x.allocate_from_storage::<Database>();
+    x.allocate_from_storage::<Users>();
+    err!(
+        x.construct::<Users>(),
+        ErrorDesc::MissingDependency("construct::Database", "construct::Config")
+    );
+    x.allocate_for::<Config>();
+    x.insert(Config::default()).unwrap();
+    err!(
+        x.construct::<Users>(),
+        ErrorDesc::MissingDependency("construct::Users", "construct::Logger")
+    );
+    // Dependencies constructed before failing are kept.
+    ok!(x.get::<&Database>());
+    x.allocate_for::<Logger>();
+    err!(
+        x.construct::<Users>(),
+        ErrorDesc::MissingDependency("construct::Users", "construct::Logger")
+    );
+    // Dependencies with a factory are constructed when they are fetched.
+    let mut x = services();
+    ok!(x.get::<Box<Config>>());
+    x.allocate_default::<Config>();
+    ok!(x.construct::<Users>());
+    assert_eq!(ok!(x.get::<&Users>()).table, "/users");
+}
+
+#[test]
+fn borrowed() {
+    let x = services();
+    let logger = ok!(x.get::<&Logger>());
+    err!(x.construct::<Users>(), ErrorDesc::BorrowedIncompatibly);
+    drop(logger);
+    ok!(x.construct::<Users>());
+}
+
+#[derive(Debug)]
+struct A;
+#[derive(Debug)]
+struct B;
+#[derive(Debug)]
+struct C;
+
+impl<'a> FromStorage<'a, DynamicStorage> for A {
+    type Deps = &'a B;
+    fn from_storage(_: Dependencies<'a, Self, DynamicStorage>) -> Self {
+        A
+    }
+}
+
+impl<'a> FromStorage<'a, DynamicStorage> for B {
+    type Deps = (&'a C, Vec<usize>);
+    fn from_storage(_: Dependencies<'a, Self, DynamicStorage>) -> Self {
+        B
+    }
+}
+
+impl<'a> FromStorage<'a, DynamicStorage> for C {
+    type Deps = &'a A;
+    fn from_storage(_: Dependencies<'a, Self, DynamicStorage>) -> Self {
+        C
+    }
+}
+
+#[test]
+fn cycle() {
+    let mut x = DynamicStorage::new();
+    x.allocate_for::<usize>();
+    x.allocate_from_storage::<A>();
+    x.allocate_from_storage::<B>();
+    x.allocate_from_storage::<C>();
+    err!(
+        x.construct::<B>(),
+        ErrorDesc::DependencyCycle(vec![
+            "construct::B",
+            "construct::C",
+            "construct::A",
+            "construct::B"
+        ])
+    );
+    // The cycle is broken by any of its types having a value.
+    x.insert(C).unwrap();
+    x.insert_many(vec![0usize, 1]).unwrap();
+    ok!(x.construct::<A>());
+    ok!(x.get::<(&A, &B, &C)>());
+}
+
+// A type which can be constructed in any storage.
+#[derive(Debug)]
+struct Counter(usize);
+
+impl<'a, S: Storage> FromStorage<'a, S> for Counter
+where
+    &'a mut usize: FetchMultiple<'a, S::Unit>,
+{
+    type Deps = &'a mut usize;
+    fn from_storage(_: Dependencies<'a, Self, S>) -> Self {
+        Counter(0)
+    }
+}
+
+#[test]
+fn generic() {
+    let mut x = MutexStorage::new();
+    x.allocate_for::<usize>();
+    x.allocate_from_storage::<Counter>();
+    x.insert(0usize).unwrap();
+    ok!(x.construct::<Counter>());
+    ok!(x.get::<&mut Counter>());
+    let mut x = DynamicStorage::new();
+    x.allocate_for::<usize>();
+    x.allocate_for::<Counter>();
+    x.insert(0usize).unwrap();
+    ok!(x.construct::<Counter>());
+    err!(
+        x.construct::<Counter>(),
+        ErrorDesc::Unit(UnitError::AlreadyPresent)
+    );
+    ok!(x.get::<&Counter>());
+}
@@ -0,0 +1,142 @@
+#![allow(unused)]
+
+use restor::{
+    define_storage, err, ok, ErrorDesc, Exclusive, MixedStorage, MutexBackend, RawLock,
+    RwLockBackend, RwLockStorage, Shared, SpinMutexStorage,
+};
+#[cfg(feature = "std")]
+use restor::{StdMutexStorage, StdRwLockStorage};
+use std::cell::Cell;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::thread::spawn;
+
+static SHARED_LOCKS: AtomicUsize = AtomicUsize::new(0);
+
+pub struct CountingLock(RwLockBackend);
+
+unsafe impl RawLock for CountingLock {
+    type Kind = Shared;
+    fn new() -> Self {
+        CountingLock(RwLockBackend::new())
+    }
+    fn lock_shared(&self) {
+        SHARED_LOCKS.fetch_add(1, Ordering::Relaxed);
+        self.0.lock_shared()
+    }
+    fn try_lock_shared(&self) -> bool {
+        SHARED_LOCKS.fetch_add(1, Ordering::Relaxed);
+        self.0.try_lock_shared()
+    }
+    unsafe fn unlock_shared(&self) {
+        self.0.unlock_shared()
+    }
+    fn lock_exclusive(&self) {
+        self.0.lock_exclusive()
+    }
+    fn try_lock_exclusive(&self) -> bool {
+        self.0.try_lock_exclusive()
+    }
+    unsafe fn unlock_exclusive(&self) {
+        self.0.unlock_exclusive()
+    }
+}
+
+define_storage! {
+    /// A storage which counts how often it is read from.
+    pub struct CountingStorage(CountingLock);
+}
+
+define_storage! {
+    struct ExclusiveStorage(MutexBackend);
+}
+
+#[test]
+fn get() {
+    let mut x = CountingStorage::new();
+    x.allocate_for::<usize>();
+    x.insert(0usize).unwrap();
+    let before = SHARED_LOCKS.load(Ordering::Relaxed);
+    {
+        let (a, b) = ok!(x.get::<(&usize, &usize)>());
+        err!(x.get::<&mut usize>(), ErrorDesc::BorrowedIncompatibly);
+    }
+    ok!(x.waiting_get::<&mut usize>());
+    x.insert(1usize).unwrap();
+    ok!(x.run_for::<usize, _, _>(|v| v.len()), 2);
+    assert!(SHARED_LOCKS.load(Ordering::Relaxed) >= before + 3);
+}
+
+#[test]
+fn exclusive() {
+    let mut x = ExclusiveStorage::new();
+    x.allocate_for::<Cell<usize>>();
+    x.insert(Cell::new(0usize)).unwrap();
+    let a = ok!(x.get::<&Cell<usize>>());
+    err!(x.get::<&Cell<usize>>(), ErrorDesc::BorrowedIncompatibly);
+}
+
+#[test]
+fn concurrent() {
+    let mut x = CountingStorage::new();
+    x.allocate_for::<usize>();
+    x.insert(0usize).unwrap();
+    let x = Arc::new(x);
+    let threads = (0..4)
+        .map(|_| {
+            let xc = x.clone();
+            spawn(move || {
+                for _ in 0..100 {
+                    *xc.waiting_get::<&mut usize>().unwrap() += 1;
+                }
+            })
+        })
+        .collect::<Vec<_>>();
+    for t in threads {
+        t.join().unwrap();
+    }
+    ok!(x.get::<&usize>(), 400, *);
+}
+
+#[test]
+fn transfer() {
+    let mut x = CountingStorage::new();
+    x.allocate_for::<usize>();
+    x.insert_many(vec![1usize, 2]).unwrap();
+    let mut y = MixedStorage::new();
+    y.allocate_for_with::<usize, CountingLock>();
+    ok!(x.transfer::<usize, _>(&y));
+    ok!(y.get::<&[usize]>(), [1, 2], *);
+}
+
+// The storages built on the backends are there whichever locks
+// `RwLockStorage` and `MutexStorage` are built on.
+#[test]
+#[cfg(feature = "std")]
+fn std_storages() {
+    let mut x = StdRwLockStorage::new();
+    x.allocate_for::<usize>();
+    x.insert(0usize).unwrap();
+    let a = ok!(x.get::<&usize>());
+    ok!(x.get::<&usize>());
+    err!(x.get::<&mut usize>(), ErrorDesc::BorrowedIncompatibly);
+    drop(a);
+    let mut y = StdMutexStorage::new();
+    y.allocate_for::<Cell<usize>>();
+    y.insert(Cell::new(1usize)).unwrap();
+    let b = ok!(y.get::<&Cell<usize>>());
+    err!(y.get::<&Cell<usize>>(), ErrorDesc::BorrowedIncompatibly);
+    let mut w = RwLockStorage::new();
+    w.allocate_for::<usize>();
+    ok!(x.transfer::<usize, _>(&w));
+    ok!(w.get::<&usize>(), 0, *);
+}
+
+#[test]
+fn spin_storages() {
+    let mut z = SpinMutexStorage::new();
+    z.allocate_for::<usize>();
+    z.insert(2usize).unwrap();
+    let a = ok!(z.get::<&mut usize>(), 2, *);
+    err!(z.get::<&usize>(), ErrorDesc::BorrowedIncompatibly);
+}
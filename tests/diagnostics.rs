@@ -0,0 +1,115 @@
+#![cfg(feature = "diagnostics")]
+#![allow(unused)]
+
+use restor::{
+    err, make_storage, ok, BorrowSite, DynamicStorage, ErrorDesc, MutexStorage, RwLockStorage,
+};
+use std::thread::Builder;
+
+fn sites(sites: Vec<BorrowSite>) -> Vec<(u32, bool)> {
+    sites
+        .iter()
+        .map(|x| (x.location().line(), x.is_mutable()))
+        .collect()
+}
+
+#[test]
+fn mutable_holder() {
+    let x = make_storage!(DynamicStorage: usize, String);
+    x.insert(0usize).unwrap();
+    x.insert(String::new()).unwrap();
+    let line = line!() + 1;
+    let held = ok!(x.get::<(&mut usize, &String)>());
+    err!(x.get::<&usize>(), ErrorDesc::BorrowedIncompatibly);
+    assert_eq!(sites(x.borrow_sites::<usize>()), vec![(line, true)]);
+    assert_eq!(sites(x.borrow_sites::<String>()), vec![(line, false)]);
+    assert!(x.borrow_sites::<isize>().is_empty());
+    drop(held);
+    ok!(x.get::<&usize>());
+}
+
+#[test]
+fn shared_holders() {
+    let x = make_storage!(RwLockStorage: usize);
+    x.insert(0usize).unwrap();
+    let first = line!() + 1;
+    let a = ok!(x.get::<&usize>());
+    let second = line!() + 1;
+    let b = ok!(x.get::<&usize>());
+    err!(x.get::<&mut usize>(), ErrorDesc::BorrowedIncompatibly);
+    assert_eq!(
+        sites(x.borrow_sites::<usize>()),
+        vec![(first, false), (second, false)]
+    );
+    // Borrows taken using `can_get` are never held onto.
+    ok!(x.can_get::<&usize>());
+    err!(x.can_get::<Box<usize>>(), ErrorDesc::BorrowedIncompatibly);
+    assert_eq!(
+        sites(x.borrow_sites::<usize>()),
+        vec![(first, false), (second, false)]
+    );
+    drop((a, b));
+    // Borrowing the unit mutably succeeded, so the old borrows are forgotten.
+    let third = line!() + 1;
+    let c = ok!(x.get::<&mut usize>());
+    assert_eq!(sites(x.borrow_sites::<usize>()), vec![(third, true)]);
+}
+
+#[test]
+fn released_holders() {
+    let x = make_storage!(RwLockStorage: usize);
+    x.insert(0usize).unwrap();
+    drop(ok!(x.get::<&mut usize>()));
+    // Releasing a borrow is only noticed by a fetch which it conflicts with.
+    assert_eq!(x.borrow_sites::<usize>().len(), 1);
+    let line = line!() + 1;
+    let held = ok!(x.get::<&usize>());
+    assert_eq!(sites(x.borrow_sites::<usize>()), vec![(line, false)]);
+}
+
+#[test]
+fn taken_values_are_not_held() {
+    let x = make_storage!(MutexStorage: usize);
+    x.insert_many(vec![0usize, 1, 2]).unwrap();
+    ok!(x.get::<Box<usize>>(), 0);
+    assert!(x.borrow_sites::<usize>().is_empty());
+    let line = line!() + 1;
+    let held = ok!(x.get::<&mut [usize]>());
+    err!(x.get::<Vec<usize>>(), ErrorDesc::BorrowedIncompatibly);
+    assert_eq!(sites(x.borrow_sites::<usize>()), vec![(line, true)]);
+}
+
+#[test]
+fn self_conflicting() {
+    let x = make_storage!(DynamicStorage: usize);
+    x.insert(0usize).unwrap();
+    err!(
+        x.get::<(&mut usize, &usize)>(),
+        ErrorDesc::BorrowedIncompatibly
+    );
+    assert!(x.borrow_sites::<usize>().is_empty());
+}
+
+#[test]
+fn thread() {
+    let x = make_storage!(RwLockStorage: usize);
+    x.insert(0usize).unwrap();
+    std::thread::scope(|scope| {
+        let held = Builder::new()
+            .name(String::from("holder"))
+            .spawn_scoped(scope, || x.get::<&mut usize>().map(|_| ()))
+            .unwrap();
+        held.join().unwrap().unwrap();
+        let _held = x.waiting_get::<&mut usize>().unwrap();
+        scope
+            .spawn(|| err!(x.get::<&usize>(), ErrorDesc::BorrowedIncompatibly))
+            .join()
+            .unwrap();
+        let sites = x.borrow_sites::<usize>();
+        assert_eq!(sites.len(), 1);
+        assert_eq!(sites[0].thread().name(), std::thread::current().name());
+        assert!(sites[0]
+            .to_string()
+            .starts_with("mutable borrow at tests/diagnostics.rs:"));
+    });
+}
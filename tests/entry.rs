@@ -0,0 +1,87 @@
+#![allow(unused)]
+
+use restor::{err, ok, DynamicStorage, ErrorDesc, MutexStorage, RwLockStorage, UnitError};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::thread::{sleep, spawn};
+use std::time::Duration;
+
+#[test]
+fn or_insert() {
+    let mut x = DynamicStorage::new();
+    x.allocate_for::<usize>();
+    x.allocate_for::<String>();
+    assert!(!ok!(x.entry::<usize>()).is_occupied());
+    *ok!(x.entry::<usize>()).or_insert(1) += 1;
+    assert_eq!(*ok!(x.entry::<usize>()).or_insert(10), 2);
+    ok!(x.entry::<String>()).or_default().push_str("abc");
+    assert_eq!(&*ok!(x.get::<&String>()), "abc");
+    err!(x.entry::<isize>(), ErrorDesc::NoAllocatedUnit);
+}
+
+#[test]
+fn and_modify() {
+    let mut x = MutexStorage::new();
+    x.allocate_for::<usize>();
+    for _ in 0..3 {
+        ok!(x.entry::<usize>())
+            .and_modify(|x| *x *= 2)
+            .or_insert_with(|| 1);
+    }
+    assert_eq!(*ok!(x.get::<&mut usize>()), 4);
+    assert!(ok!(x.entry::<usize>()).is_occupied());
+}
+
+#[test]
+fn not_one() {
+    let mut x = RwLockStorage::new();
+    x.allocate_for::<usize>();
+    x.insert_many(vec![1usize, 2]).unwrap();
+    err!(x.entry::<usize>(), ErrorDesc::Unit(UnitError::IsNotOne));
+    assert_eq!(&*ok!(x.get::<&[usize]>()), &[1, 2]);
+}
+
+#[test]
+fn borrowed() {
+    let mut x = DynamicStorage::new();
+    x.allocate_for::<usize>();
+    x.insert(0usize).unwrap();
+    let value = ok!(x.get::<&usize>());
+    err!(x.entry::<usize>(), ErrorDesc::BorrowedIncompatibly);
+    drop(value);
+    // The unit stays locked for as long as the guard is held.
+    let value = ok!(x.entry::<usize>()).or_insert(1);
+    err!(x.get::<&usize>(), ErrorDesc::BorrowedIncompatibly);
+    drop(value);
+    let mut x = RwLockStorage::new();
+    x.allocate_for::<usize>();
+    let entry = ok!(x.entry::<usize>());
+    err!(x.entry::<usize>(), ErrorDesc::BorrowedIncompatibly);
+    err!(x.get::<&usize>(), ErrorDesc::BorrowedIncompatibly);
+}
+
+#[test]
+fn race() {
+    let count = Arc::new(AtomicUsize::new(0));
+    let mut x = RwLockStorage::new();
+    x.allocate_for::<usize>();
+    let x = Arc::new(x);
+    let threads = (0..8)
+        .map(|_| {
+            let x = x.clone();
+            let count = count.clone();
+            spawn(move || {
+                *ok!(x.waiting_entry::<usize>()).or_insert_with(|| {
+                    count.fetch_add(1, Ordering::SeqCst);
+                    sleep(Duration::from_millis(20));
+                    0
+                }) += 1;
+            })
+        })
+        .collect::<Vec<_>>();
+    for thread in threads {
+        thread.join().unwrap();
+    }
+    assert_eq!(count.load(Ordering::SeqCst), 1);
+    assert_eq!(*ok!(x.get::<&usize>()), 8);
+}
@@ -0,0 +1,133 @@
+#![allow(unused)]
+
+use restor::{
+    err, ok, Drain, DynamicStorage, ErrorDesc, MutexStorage, Pop, RwLockStorage, UnitError,
+};
+use std::sync::Arc;
+use std::thread::spawn;
+
+#[test]
+fn double_buffered() {
+    let mut x = DynamicStorage::new();
+    x.allocate_events::<usize>();
+    x.insert(0usize).unwrap();
+    x.insert_many(vec![1usize, 2]).unwrap();
+    assert!(ok!(x.get::<&[usize]>()).is_empty());
+    ok!(x.swap_event_buffers());
+    assert_eq!(&*ok!(x.get::<&[usize]>()), &[0, 1, 2]);
+    x.insert(3usize).unwrap();
+    assert_eq!(&*ok!(x.get::<&[usize]>()), &[0, 1, 2]);
+    ok!(x.swap_event_buffers());
+    assert_eq!(&*ok!(x.get::<&[usize]>()), &[3]);
+    ok!(x.swap_event_buffers());
+    assert!(ok!(x.get::<&[usize]>()).is_empty());
+}
+
+#[test]
+fn every_type() {
+    let mut x = RwLockStorage::new();
+    x.allocate_events::<usize>();
+    x.allocate_events::<String>();
+    x.allocate_for::<u8>();
+    x.insert(0usize).unwrap();
+    x.insert(String::from("a")).unwrap();
+    x.insert(0u8).unwrap();
+    ok!(x.swap_event_buffers());
+    let (numbers, strings) = ok!(x.get::<(&[usize], &[String])>());
+    assert_eq!(&*numbers, &[0]);
+    assert_eq!(&*strings, &["a"]);
+    drop((numbers, strings));
+    ok!(x.get::<&u8>(), 0, *);
+}
+
+#[test]
+fn not_borrowed() {
+    let mut x = DynamicStorage::new();
+    x.allocate_events::<usize>();
+    x.insert(0usize).unwrap();
+    err!(x.get::<&usize>(), ErrorDesc::Unit(UnitError::Unsupported));
+    ok!(x.swap_event_buffers());
+    err!(x.get::<&usize>(), ErrorDesc::Unit(UnitError::Unsupported));
+}
+
+#[test]
+fn taken() {
+    let mut x = DynamicStorage::new();
+    x.allocate_events::<usize>();
+    x.insert_many(vec![0usize, 1, 2]).unwrap();
+    err!(x.get::<Pop<usize>>(), ErrorDesc::Unit(UnitError::IsNope));
+    ok!(x.swap_event_buffers());
+    ok!(x.get::<Pop<usize>>(), 0);
+    ok!(x.get::<Box<usize>>(), 1);
+    ok!(x.get::<Drain<usize>>(), vec![2]);
+    ok!(x.get::<Drain<usize>>(), Vec::<usize>::new());
+}
+
+#[test]
+fn swap_while_borrowed() {
+    let mut x = RwLockStorage::new();
+    x.allocate_events::<usize>();
+    x.allocate_events::<String>();
+    x.insert(0usize).unwrap();
+    x.insert(String::from("a")).unwrap();
+    let borrowed = ok!(x.get::<&[String]>());
+    err!(x.swap_event_buffers(), ErrorDesc::BorrowedIncompatibly);
+    drop(borrowed);
+    ok!(x.swap_event_buffers());
+    assert_eq!(&*ok!(x.get::<&[usize]>()), &[0]);
+    assert_eq!(&*ok!(x.get::<&[String]>()), &["a"]);
+}
+
+#[test]
+fn swap_wakes() {
+    let mut x = MutexStorage::new();
+    x.allocate_events::<usize>();
+    x.insert(0usize).unwrap();
+    let x = Arc::new(x);
+    let xc = x.clone();
+    let handle = spawn(move || xc.waiting_take::<usize>().unwrap());
+    ok!(x.swap_event_buffers());
+    assert_eq!(handle.join().unwrap(), 0);
+}
+
+#[test]
+fn merged() {
+    let mut x = DynamicStorage::new();
+    x.allocate_events::<usize>();
+    x.insert(0usize).unwrap();
+    x.swap_event_buffers().unwrap();
+    x.insert(1usize).unwrap();
+    let mut y = DynamicStorage::new();
+    y.allocate_events::<usize>();
+    y.insert(2usize).unwrap();
+    y.swap_event_buffers().unwrap();
+    y.insert(3usize).unwrap();
+    x.merge_from(y).unwrap();
+    assert_eq!(&*ok!(x.get::<&[usize]>()), &[0, 2]);
+    ok!(x.swap_event_buffers());
+    assert_eq!(&*ok!(x.get::<&[usize]>()), &[1, 3]);
+}
+
+#[test]
+fn clone() {
+    let mut x = RwLockStorage::new();
+    x.allocate_events::<usize>();
+    x.allocate_for_clone::<usize>();
+    x.insert(0usize).unwrap();
+    let y = x.try_clone().unwrap();
+    ok!(y.swap_event_buffers());
+    assert_eq!(&*ok!(y.get::<&[usize]>()), &[0]);
+}
+
+#[test]
+fn taken_then_borrowed() {
+    let mut x = DynamicStorage::new();
+    x.allocate_events::<usize>();
+    x.insert_many(vec![0usize, 1, 2]).unwrap();
+    ok!(x.swap_event_buffers());
+    ok!(x.get::<Pop<usize>>(), 0);
+    ok!(x.get::<&mut [usize]>())
+        .iter_mut()
+        .for_each(|x| *x *= 10);
+    assert_eq!(&*ok!(x.get::<&[usize]>()), &[10, 20]);
+}
@@ -0,0 +1,90 @@
+#![allow(unused)]
+
+use restor::{err, ok, DynamicStorage, ErrorDesc, MutexStorage, RwLockStorage, UnitError};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::thread::{sleep, spawn};
+use std::time::Duration;
+
+#[derive(Debug, Default, PartialEq)]
+struct Model {
+    weights: Vec<u8>,
+}
+
+fn counted(count: &Arc<AtomicUsize>) -> impl Fn() -> Model + Send + Sync + 'static {
+    let count = count.clone();
+    move || {
+        count.fetch_add(1, Ordering::SeqCst);
+        Model {
+            weights: vec![1, 2, 3],
+        }
+    }
+}
+
+#[test]
+fn lazy() {
+    let count = Arc::new(AtomicUsize::new(0));
+    let mut x = DynamicStorage::new();
+    x.allocate_with_factory::<Model>(counted(&count));
+    assert_eq!(count.load(Ordering::SeqCst), 0);
+    // Checking a fetch does not construct the value, but does count on it.
+    ok!(x.can_get::<&Model>());
+    ok!(x.can_get::<&mut Model>());
+    assert_eq!(count.load(Ordering::SeqCst), 0);
+    assert_eq!(ok!(x.get::<&Model>()).weights, vec![1, 2, 3]);
+    ok!(x.get::<&Model>());
+    assert_eq!(count.load(Ordering::SeqCst), 1);
+    // Taking the value out empties the unit again.
+    ok!(x.get::<Box<Model>>());
+    ok!(x.get::<&Model>());
+    assert_eq!(count.load(Ordering::SeqCst), 2);
+}
+
+#[test]
+fn mutable() {
+    let mut x = MutexStorage::new();
+    x.allocate_default::<Model>();
+    ok!(x.get::<&mut Model>()).weights.push(4);
+    assert_eq!(ok!(x.get::<&mut Model>()).weights, vec![4]);
+    let mut x = RwLockStorage::new();
+    x.allocate_default::<Model>();
+    x.allocate_default::<usize>();
+    ok!(x.can_get::<(&mut Model, &usize)>());
+    let (mut model, size) = ok!(x.waiting_get::<(&mut Model, &usize)>());
+    model.weights.push(*size as u8);
+    assert_eq!(model.weights, vec![0]);
+}
+
+#[test]
+fn other_kinds() {
+    let mut x = DynamicStorage::new();
+    x.allocate_default::<usize>();
+    err!(x.get::<&[usize]>(), ErrorDesc::Unit(UnitError::IsNotMany));
+    err!(x.get::<Box<usize>>(), ErrorDesc::Unit(UnitError::IsNotOne));
+    // Units holding several values are not empty.
+    x.insert_many(vec![1usize, 2]).unwrap();
+    err!(x.get::<&usize>(), ErrorDesc::Unit(UnitError::IsNotOne));
+    assert_eq!(&*ok!(x.get::<&[usize]>()), &[1, 2]);
+}
+
+#[test]
+fn race() {
+    let count = Arc::new(AtomicUsize::new(0));
+    let mut x = RwLockStorage::new();
+    let slow = counted(&count);
+    x.allocate_with_factory::<Model>(move || {
+        sleep(Duration::from_millis(20));
+        slow()
+    });
+    let x = Arc::new(x);
+    let threads = (0..8)
+        .map(|_| {
+            let x = x.clone();
+            spawn(move || ok!(x.waiting_get::<&Model>()).weights.len())
+        })
+        .collect::<Vec<_>>();
+    for thread in threads {
+        assert_eq!(thread.join().unwrap(), 3);
+    }
+    assert_eq!(count.load(Ordering::SeqCst), 1);
+}
@@ -0,0 +1,185 @@
+#![cfg(feature = "lock_order")]
+#![allow(unused)]
+
+use restor::{make_storage, ok, MixedStorage, MutexStorage, RwLockBackend, RwLockStorage};
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::thread::spawn;
+
+#[derive(Debug)]
+struct A(usize);
+#[derive(Debug)]
+struct B(usize);
+#[derive(Debug)]
+struct C(usize);
+
+fn storage() -> RwLockStorage {
+    let storage = make_storage!(RwLockStorage: A, B, C);
+    storage.insert(A(0)).unwrap();
+    storage.insert(B(0)).unwrap();
+    storage.insert(C(0)).unwrap();
+    storage
+}
+
+fn message(result: std::thread::Result<()>) -> String {
+    *result.unwrap_err().downcast::<String>().unwrap()
+}
+
+#[test]
+fn consistent_order() {
+    let storage = storage();
+    for _ in 0..3 {
+        let (a, b) = ok!(storage.waiting_get::<(&mut A, &B)>());
+        let c = ok!(storage.waiting_get::<&mut C>());
+    }
+    ok!(storage.waiting_get::<(&A, &mut C)>());
+    // Many shared holders of the same lock do not wait on each other.
+    ok!(storage.waiting_get::<(&A, &A)>());
+}
+
+#[test]
+fn held_guards() {
+    let storage = storage();
+    {
+        let a = ok!(storage.waiting_get::<&mut A>());
+        let b = ok!(storage.waiting_get::<&mut B>());
+    }
+    let b = ok!(storage.waiting_get::<&B>());
+    let error = message(catch_unwind(AssertUnwindSafe(|| {
+        storage.waiting_get::<&A>().map(|_| ()).unwrap();
+    })));
+    assert!(error.contains("deadlock"), "{}", error);
+    assert!(error.ends_with("cycle `lock_order::B` -> `lock_order::A` -> `lock_order::B`"));
+}
+
+#[test]
+fn tuples() {
+    let storage = storage();
+    ok!(storage.waiting_get::<(&A, &B)>());
+    ok!(storage.waiting_get::<(&B, &mut C)>());
+    let error = message(catch_unwind(AssertUnwindSafe(|| {
+        storage.waiting_get::<(&C, &A)>().map(|_| ()).unwrap();
+    })));
+    assert!(error.ends_with(
+        "cycle `lock_order::C` -> `lock_order::A` -> `lock_order::B` -> `lock_order::C`"
+    ));
+    // The locks which were held when panicking were released.
+    ok!(storage.get::<(&mut A, &mut B, &mut C)>());
+}
+
+#[test]
+fn mutex() {
+    let storage = make_storage!(MutexStorage: A, B);
+    storage.insert_many(vec![A(0), A(1)]).unwrap();
+    storage.insert(B(0)).unwrap();
+    {
+        let a = ok!(storage.waiting_get::<&mut [A]>());
+        ok!(storage.waiting_get::<&mut B>()).0 += 1;
+    }
+    let b = ok!(storage.get::<&mut B>());
+    let error = message(catch_unwind(AssertUnwindSafe(|| {
+        storage.waiting_take::<A>().map(|_| ()).unwrap();
+    })));
+    assert!(error.contains("while holding the one for `lock_order::B`"));
+}
+
+#[test]
+fn reentrant() {
+    let storage = storage();
+    let a = ok!(storage.waiting_get::<&A>());
+    let error = message(catch_unwind(AssertUnwindSafe(|| {
+        storage.waiting_get::<&mut A>().map(|_| ()).unwrap();
+    })));
+    assert!(error.ends_with("cycle `lock_order::A` -> `lock_order::A`"));
+
+    // Locks which only have a single holder cannot be held twice.
+    let mut storage = MixedStorage::new();
+    storage.allocate_for::<B>();
+    storage.allocate_for_with::<C, RwLockBackend>();
+    storage.insert(B(0)).unwrap();
+    storage.insert(C(0)).unwrap();
+    let c = ok!(storage.waiting_get::<&C>());
+    ok!(storage.waiting_get::<&C>());
+    let b = ok!(storage.waiting_get::<&B>());
+    let error = message(catch_unwind(AssertUnwindSafe(|| {
+        storage.waiting_get::<&B>().map(|_| ()).unwrap();
+    })));
+    assert!(error.contains("while holding the one for `lock_order::B`"));
+}
+
+#[test]
+fn threads() {
+    let storage = std::sync::Arc::new(storage());
+    let other = storage.clone();
+    spawn(move || {
+        let c = ok!(other.waiting_get::<&mut C>());
+        ok!(other.waiting_get::<&mut A>());
+    })
+    .join()
+    .unwrap();
+    let a = ok!(storage.waiting_get::<&A>());
+    let error = message(catch_unwind(AssertUnwindSafe(|| {
+        storage.waiting_get::<&C>().map(|_| ()).unwrap();
+    })));
+    assert!(error.ends_with("cycle `lock_order::A` -> `lock_order::C` -> `lock_order::A`"));
+}
+
+#[test]
+fn separate_storages() {
+    // Each storage has its own locks, so their orders are unrelated.
+    let first = storage();
+    let second = storage();
+    ok!(first.waiting_get::<(&A, &B)>());
+    ok!(second.waiting_get::<(&B, &A)>());
+    drop(first);
+    let third = storage();
+    ok!(third.waiting_get::<(&B, &A)>());
+}
+
+#[test]
+fn released_while_shared_elsewhere() {
+    let storage = std::sync::Arc::new(storage());
+    let (held, release) = std::sync::mpsc::channel();
+    let (done, finished) = std::sync::mpsc::channel::<()>();
+    let other = storage.clone();
+    let reader = spawn(move || {
+        let a = ok!(other.waiting_get::<&A>());
+        held.send(()).unwrap();
+        finished.recv().unwrap();
+    });
+    release.recv().unwrap();
+    // The lock for `A` is still held by the other thread, but not by this one.
+    ok!(storage.waiting_get::<&A>());
+    let b = ok!(storage.waiting_get::<&mut B>());
+    drop(b);
+    let b = ok!(storage.waiting_get::<&B>());
+    done.send(()).unwrap();
+    reader.join().unwrap();
+    ok!(storage.waiting_get::<&mut A>());
+}
+
+#[test]
+fn shared_with_other_threads() {
+    let storage = std::sync::Arc::new(storage());
+    {
+        let b = ok!(storage.waiting_get::<&mut B>());
+        ok!(storage.waiting_get::<&mut A>());
+    }
+    let (held, release) = std::sync::mpsc::channel();
+    let (done, finished) = std::sync::mpsc::channel::<()>();
+    let other = storage.clone();
+    let reader = spawn(move || {
+        let a = ok!(other.waiting_get::<&A>());
+        held.send(()).unwrap();
+        finished.recv().unwrap();
+    });
+    release.recv().unwrap();
+    // The lock for `A` is held by this thread too, even though the other
+    // thread holds it as well.
+    let a = ok!(storage.waiting_get::<&A>());
+    let error = message(catch_unwind(AssertUnwindSafe(|| {
+        storage.waiting_get::<&B>().map(|_| ()).unwrap();
+    })));
+    assert!(error.ends_with("cycle `lock_order::A` -> `lock_order::B` -> `lock_order::A`"));
+    done.send(()).unwrap();
+    reader.join().unwrap();
+}
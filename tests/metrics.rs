@@ -0,0 +1,118 @@
+#![cfg(feature = "metrics")]
+#![allow(unused)]
+
+use restor::{make_storage, ok, DynamicStorage, RwLockStorage};
+use std::sync::mpsc::channel;
+use std::thread::{scope, sleep};
+use std::time::Duration;
+
+fn counts(storage: &DynamicStorage, name: &str) -> (u64, u64) {
+    let metrics = storage.metrics();
+    let unit = metrics.unit(name).unwrap();
+    (unit.borrows(), unit.failed_borrows())
+}
+
+#[test]
+fn borrows() {
+    let x = make_storage!(DynamicStorage: usize, String);
+    x.insert(0usize).unwrap();
+    x.insert(String::new()).unwrap();
+    ok!(x.get::<&usize>());
+    ok!(x.get::<(&mut usize, &String)>());
+    ok!(x.get::<Box<String>>());
+    assert_eq!(counts(&x, "usize"), (2, 0));
+    assert_eq!(counts(&x, "alloc::string::String"), (2, 0));
+    // Checking whether a fetch would succeed is not a borrow.
+    ok!(x.can_get::<&usize>());
+    assert_eq!(counts(&x, "usize"), (2, 0));
+}
+
+#[test]
+fn failed_borrows() {
+    let x = make_storage!(DynamicStorage: usize, String);
+    x.insert(0usize).unwrap();
+    x.insert(String::new()).unwrap();
+    let held = ok!(x.get::<&mut usize>());
+    assert!(x.get::<(&String, &usize)>().is_err());
+    assert!(x.get::<&usize>().is_err());
+    // Only the unit which was borrowed is to blame.
+    assert_eq!(counts(&x, "usize"), (1, 2));
+    assert_eq!(counts(&x, "alloc::string::String"), (0, 0));
+    drop(held);
+    // Failing for any other reason is not counted.
+    assert!(x.get::<&[usize]>().is_err());
+    assert_eq!(counts(&x, "usize"), (1, 2));
+    // A fetch which conflicts with itself fails on all of its units.
+    assert!(x.get::<(&mut String, &String)>().is_err());
+    assert_eq!(counts(&x, "alloc::string::String"), (0, 1));
+}
+
+#[test]
+fn waits() {
+    let x = make_storage!(RwLockStorage: usize);
+    x.insert(0usize).unwrap();
+    ok!(x.waiting_get::<&usize>());
+    let (sender, receiver) = channel();
+    scope(|s| {
+        s.spawn(|| {
+            let held = ok!(x.get::<&mut usize>());
+            sender.send(()).unwrap();
+            sleep(Duration::from_millis(50));
+            drop(held);
+        });
+        receiver.recv().unwrap();
+        ok!(x.waiting_get::<&usize>());
+    });
+    let metrics = x.metrics();
+    let unit = &metrics.units()[0];
+    assert_eq!(unit.borrows(), 3);
+    assert_eq!(unit.waits(), 1);
+    assert!(unit.wait_time() >= Duration::from_millis(20));
+}
+
+#[test]
+fn peak_len() {
+    let mut x = DynamicStorage::new();
+    x.allocate_for::<usize>();
+    x.allocate_events::<u8>();
+    x.insert_many(vec![0usize, 1, 2, 3]).unwrap();
+    ok!(x.get::<Vec<usize>>());
+    x.insert(4usize).unwrap();
+    x.insert_many(vec![0u8, 1]).unwrap();
+    x.swap_event_buffers().unwrap();
+    x.insert(2u8).unwrap();
+    let metrics = x.metrics();
+    assert_eq!(metrics.unit("usize").unwrap().peak_len(), 4);
+    assert_eq!(metrics.unit("u8").unwrap().peak_len(), 3);
+}
+
+#[test]
+fn prometheus() {
+    let x = make_storage!(DynamicStorage: u8, u16);
+    x.insert(0u8).unwrap();
+    ok!(x.get::<&u8>());
+    assert_eq!(
+        x.metrics().to_prometheus("a \"quoted\" name"),
+        r#"# HELP restor_borrows_total Successful fetches using a unit.
+# TYPE restor_borrows_total counter
+restor_borrows_total{storage="a \"quoted\" name",unit="u16"} 0
+restor_borrows_total{storage="a \"quoted\" name",unit="u8"} 1
+# HELP restor_failed_borrows_total Fetches which failed because a unit was borrowed incompatibly.
+# TYPE restor_failed_borrows_total counter
+restor_failed_borrows_total{storage="a \"quoted\" name",unit="u16"} 0
+restor_failed_borrows_total{storage="a \"quoted\" name",unit="u8"} 0
+# HELP restor_waits_total Waiting fetches which had to wait for a unit.
+# TYPE restor_waits_total counter
+restor_waits_total{storage="a \"quoted\" name",unit="u16"} 0
+restor_waits_total{storage="a \"quoted\" name",unit="u8"} 0
+# HELP restor_wait_seconds_total Time spent in waiting fetches which had to wait for a unit.
+# TYPE restor_wait_seconds_total counter
+restor_wait_seconds_total{storage="a \"quoted\" name",unit="u16"} 0
+restor_wait_seconds_total{storage="a \"quoted\" name",unit="u8"} 0
+# HELP restor_peak_elements The largest number of values seen in a unit.
+# TYPE restor_peak_elements gauge
+restor_peak_elements{storage="a \"quoted\" name",unit="u16"} 0
+restor_peak_elements{storage="a \"quoted\" name",unit="u8"} 1
+"#
+    );
+}
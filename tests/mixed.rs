@@ -0,0 +1,144 @@
+#![allow(unused)]
+
+use restor::{
+    err, ok, ErrorDesc, MixedStorage, MutexBackend, RwLockBackend, SpinMutexBackend,
+    SpinRwLockBackend,
+};
+#[cfg(feature = "std")]
+use restor::{StdMutexBackend, StdRwLockBackend};
+use std::cell::Cell;
+use std::sync::Arc;
+use std::thread::spawn;
+use std::time::Duration;
+
+#[test]
+fn instantiate() {
+    let _ = MixedStorage::new();
+}
+
+#[test]
+fn register_repeated() {
+    let mut x = MixedStorage::new();
+    x.allocate_for_with::<usize, RwLockBackend>();
+    x.allocate_for_with::<usize, MutexBackend>();
+    x.insert(0usize).unwrap();
+    let _a = ok!(x.get::<&usize>());
+    let _b = ok!(x.get::<&usize>());
+}
+
+#[test]
+fn borrow_per_backend() {
+    let mut x = MixedStorage::new();
+    x.allocate_for_with::<usize, RwLockBackend>();
+    x.allocate_for_with::<isize, MutexBackend>();
+    x.allocate_for::<u8>();
+    x.insert(0usize).unwrap();
+    x.insert(0isize).unwrap();
+    x.insert(0u8).unwrap();
+    let (a, b, c) = ok!(x.get::<(&usize, &usize, &isize)>());
+    err!(x.get::<&isize>(), ErrorDesc::BorrowedIncompatibly);
+    err!(x.get::<&mut usize>(), ErrorDesc::BorrowedIncompatibly);
+    let _d = ok!(x.get::<&mut u8>());
+    err!(x.get::<&u8>(), ErrorDesc::BorrowedIncompatibly);
+}
+
+#[test]
+fn non_sync() {
+    let mut x = MixedStorage::new();
+    x.allocate_for_with::<Cell<usize>, MutexBackend>();
+    x.insert_many(vec![Cell::new(1usize), Cell::new(2)])
+        .unwrap();
+    ok!(x.run_for::<Cell<usize>, _, _>(|v| v[0].set(3)));
+    let v = ok!(x.get::<Vec<Cell<usize>>>());
+    assert_eq!(v[0].get(), 3);
+}
+
+#[test]
+fn concurrent() {
+    let mut x = MixedStorage::new();
+    x.allocate_for_with::<usize, RwLockBackend>();
+    x.allocate_for_with::<isize, MutexBackend>();
+    x.insert(0usize).unwrap();
+    x.insert(0isize).unwrap();
+    let x = Arc::new(x);
+    let threads = (0..4)
+        .map(|_| {
+            let xc = x.clone();
+            spawn(move || {
+                for _ in 0..100 {
+                    let (a, mut b) = xc.waiting_get::<(&usize, &mut isize)>().unwrap();
+                    *b += *a as isize + 1;
+                }
+            })
+        })
+        .collect::<Vec<_>>();
+    for t in threads {
+        t.join().unwrap();
+    }
+    ok!(x.get::<&isize>(), 400, *);
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn std_backends() {
+    let mut x = MixedStorage::new();
+    x.allocate_for_with::<usize, StdRwLockBackend>();
+    x.allocate_for_with::<isize, StdMutexBackend>();
+    x.insert(0usize).unwrap();
+    x.insert(0isize).unwrap();
+    {
+        let (a, b, c) = ok!(x.get::<(&usize, &usize, &isize)>());
+        err!(x.get::<&isize>(), ErrorDesc::BorrowedIncompatibly);
+        err!(x.get::<&mut usize>(), ErrorDesc::BorrowedIncompatibly);
+    }
+    let x = Arc::new(x);
+    let threads = (0..4)
+        .map(|_| {
+            let xc = x.clone();
+            spawn(move || {
+                for _ in 0..100 {
+                    let (mut a, mut b) = xc.waiting_get::<(&mut usize, &mut isize)>().unwrap();
+                    *a += 1;
+                    *b -= 1;
+                }
+            })
+        })
+        .collect::<Vec<_>>();
+    for t in threads {
+        t.join().unwrap();
+    }
+    let (a, b) = ok!(x.get::<(&usize, &isize)>());
+    assert_eq!((*a, *b), (400, -400));
+}
+
+#[test]
+fn spin_backends() {
+    let mut x = MixedStorage::new();
+    x.allocate_for_with::<usize, SpinRwLockBackend>();
+    x.allocate_for_with::<isize, SpinMutexBackend>();
+    x.insert(0usize).unwrap();
+    x.insert(0isize).unwrap();
+    {
+        let (a, b, c) = ok!(x.get::<(&usize, &usize, &isize)>());
+        err!(x.get::<&isize>(), ErrorDesc::BorrowedIncompatibly);
+        err!(x.get::<&mut usize>(), ErrorDesc::BorrowedIncompatibly);
+    }
+    let x = Arc::new(x);
+    let threads = (0..4)
+        .map(|_| {
+            let xc = x.clone();
+            spawn(move || {
+                for _ in 0..100 {
+                    let (mut a, mut b) = xc.waiting_get::<(&mut usize, &mut isize)>().unwrap();
+                    *a += 1;
+                    *b -= 1;
+                }
+            })
+        })
+        .collect::<Vec<_>>();
+    for t in threads {
+        t.join().unwrap();
+    }
+    let (a, b) = ok!(x.get::<(&usize, &isize)>());
+    assert_eq!((*a, *b), (400, -400));
+}
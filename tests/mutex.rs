@@ -113,3 +113,34 @@ fn concurrent_ind_mut() {
     t1.join().unwrap();
     t2.join().unwrap();
 }
+
+#[test]
+fn debug() {
+    // Without `parking_lot`, this is the storage built on `std::sync`.
+    let name = if cfg!(feature = "parking_lot") {
+        "MutexStorage"
+    } else {
+        "StdMutexStorage"
+    };
+    let mut x = MutexStorage::new();
+    x.allocate_for_debug::<usize>();
+    assert_eq!(format!("{:?}", x), format!("{} {{ usize: Nope }}", name));
+    x.insert(3usize).unwrap();
+    assert_eq!(format!("{:?}", x), format!("{} {{ usize: One(3) }}", name));
+    let _guard = x.get::<&mut usize>().unwrap();
+    assert_eq!(
+        format!("{:?}", x),
+        format!("{} {{ usize: <borrowed> }}", name)
+    );
+}
+
+#[test]
+fn try_clone() {
+    let mut x = MutexStorage::new();
+    x.allocate_for_clone::<usize>();
+    x.insert(1usize).unwrap();
+    let y = ok!(x.try_clone());
+    *y.get::<&mut usize>().unwrap() = 2;
+    ok!(x.get::<&mut usize>(), 1, *);
+    ok!(y.get::<&mut usize>(), 2, *);
+}
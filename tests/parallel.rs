@@ -0,0 +1,93 @@
+#![cfg(feature = "rayon")]
+#![allow(unused)]
+
+use rayon::prelude::*;
+use restor::{
+    err, ok, BoundPolicy, DynamicStorage, ErrorDesc, MixedStorage, MutexStorage, RwLockStorage,
+    SpinRwLockBackend, UnitError,
+};
+use std::sync::Arc;
+use std::thread::spawn;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Particle {
+    position: f32,
+    velocity: f32,
+}
+
+#[test]
+fn particles() {
+    let mut x = RwLockStorage::new();
+    x.allocate_for::<Particle>();
+    x.insert_many(
+        (0..10_000)
+            .map(|i| Particle {
+                position: 0.0,
+                velocity: i as f32,
+            })
+            .collect(),
+    )
+    .unwrap();
+    ok!(x.par_run_for_mut::<Particle, _, _>(|particles| {
+        particles.for_each(|p| p.position += p.velocity)
+    }));
+    let total = ok!(x.par_run_for::<Particle, _, _>(|particles| {
+        particles.map(|p| p.position as f64).sum::<f64>()
+    }));
+    assert_eq!(total, (0..10_000).sum::<i32>() as f64);
+}
+
+#[test]
+fn every_backend() {
+    let mut x = DynamicStorage::new();
+    x.allocate_for::<usize>();
+    x.insert_many(vec![0usize, 1, 2]).unwrap();
+    ok!(x.par_run_for_mut::<usize, _, _>(|x| x.for_each(|x| *x += 1)));
+    ok!(x.par_run_for::<usize, _, _>(|x| x.sum::<usize>()), 6);
+
+    let mut x = MutexStorage::new();
+    x.allocate_for::<usize>();
+    x.insert_many(vec![0usize, 1, 2]).unwrap();
+    ok!(x.par_run_for_mut::<usize, _, _>(|x| x.for_each(|x| *x += 1)));
+    ok!(x.par_run_for::<usize, _, _>(|x| x.sum::<usize>()), 6);
+
+    let mut x = MixedStorage::new();
+    x.allocate_for_with::<usize, SpinRwLockBackend>();
+    x.insert_many(vec![0usize, 1, 2]).unwrap();
+    ok!(x.par_run_for_mut::<usize, _, _>(|x| x.for_each(|x| *x += 1)));
+    ok!(x.par_run_for::<usize, _, _>(|x| x.sum::<usize>()), 6);
+}
+
+#[test]
+fn holds_lock() {
+    let mut x = RwLockStorage::new();
+    x.allocate_for::<usize>();
+    x.insert_many(vec![0usize, 1]).unwrap();
+    ok!(x.par_run_for_mut::<usize, _, _>(|_| {
+        err!(x.get::<&[usize]>(), ErrorDesc::BorrowedIncompatibly);
+        err!(x.insert(2usize), (2, ErrorDesc::BorrowedIncompatibly));
+    }));
+    let borrowed = ok!(x.get::<&[usize]>());
+    err!(
+        x.par_run_for_mut::<usize, _, _>(|_| ()),
+        ErrorDesc::BorrowedIncompatibly
+    );
+    ok!(x.par_run_for::<usize, _, _>(|x| x.count()), 2);
+}
+
+#[test]
+fn not_many() {
+    let mut x = RwLockStorage::new();
+    x.allocate_for::<usize>();
+    err!(
+        x.par_run_for::<usize, _, _>(|x| x.count()),
+        ErrorDesc::Unit(UnitError::IsNotMany)
+    );
+    x.allocate_bounded::<u8>(2, BoundPolicy::EvictOldest);
+    x.insert_many(vec![0u8, 1, 2]).unwrap();
+    ok!(x.par_run_for::<u8, _, _>(|x| x.count()), 2);
+    err!(
+        x.par_run_for_mut::<u8, _, _>(|x| x.count()),
+        ErrorDesc::Unit(UnitError::Unsupported)
+    );
+}
@@ -0,0 +1,112 @@
+#![allow(unused)]
+
+use restor::{
+    err, ok, Drain, DynamicStorage, ErrorDesc, MutexStorage, Pop, RwLockStorage, UnitError,
+};
+use std::sync::Arc;
+use std::thread::spawn;
+
+#[test]
+fn fifo() {
+    let mut x = DynamicStorage::new();
+    x.allocate_queue::<usize>();
+    x.insert(0usize).unwrap();
+    x.insert_many(vec![1usize, 2]).unwrap();
+    x.insert(3usize).unwrap();
+    ok!(x.get::<Pop<usize>>(), 0);
+    ok!(x.get::<Box<usize>>(), 1);
+    ok!(x.get::<Drain<usize>>(), vec![2, 3]);
+    err!(x.get::<Pop<usize>>(), ErrorDesc::Unit(UnitError::IsNope));
+    ok!(x.get::<Drain<usize>>(), Vec::<usize>::new());
+    x.insert(4usize).unwrap();
+    ok!(x.get::<Pop<usize>>(), 4);
+}
+
+#[test]
+fn not_borrowed() {
+    let mut x = DynamicStorage::new();
+    x.allocate_queue::<usize>();
+    x.insert(0usize).unwrap();
+    err!(x.get::<&usize>(), ErrorDesc::Unit(UnitError::Unsupported));
+    x.insert(1usize).unwrap();
+    err!(x.get::<&[usize]>(), ErrorDesc::Unit(UnitError::Unsupported));
+    err!(
+        x.run_for::<usize, _, _>(|x| x.len()),
+        ErrorDesc::Unit(UnitError::Unsupported)
+    );
+}
+
+#[test]
+fn pop_regular() {
+    let mut x = DynamicStorage::new();
+    x.allocate_for::<usize>();
+    x.insert_many(vec![0usize, 1, 2]).unwrap();
+    ok!(x.get::<Pop<usize>>(), 0);
+    ok!(x.get::<Pop<usize>>(), 1);
+    ok!(x.get::<&usize>(), 2, *);
+    ok!(x.get::<Drain<usize>>(), vec![2]);
+    err!(x.get::<Pop<usize>>(), ErrorDesc::Unit(UnitError::IsNope));
+}
+
+#[test]
+fn stays_queue() {
+    let mut x = DynamicStorage::new();
+    x.allocate_queue::<usize>();
+    let mut y = DynamicStorage::new();
+    y.allocate_for::<usize>();
+    y.insert_many(vec![0usize, 1]).unwrap();
+    ok!(y.transfer::<usize, _>(&x));
+    x.insert(2usize).unwrap();
+    err!(x.get::<&[usize]>(), ErrorDesc::Unit(UnitError::Unsupported));
+    ok!(x.get::<Drain<usize>>(), vec![0, 1, 2]);
+}
+
+#[test]
+fn mailbox() {
+    let mut x = MutexStorage::new();
+    x.allocate_queue::<usize>();
+    let x = Arc::new(x);
+    let producers = (0..4usize)
+        .map(|i| {
+            let xc = x.clone();
+            spawn(move || {
+                for j in 0..100 {
+                    let mut value = i * 100 + j;
+                    loop {
+                        match xc.insert(value) {
+                            Ok(()) => break,
+                            Err((v, ErrorDesc::BorrowedIncompatibly)) => value = v,
+                            Err((_, e)) => panic!("Unexpected error {:?}", e),
+                        }
+                    }
+                }
+            })
+        })
+        .collect::<Vec<_>>();
+    for t in producers {
+        t.join().unwrap();
+    }
+    let mut received = Vec::new();
+    while let Ok(x) = x.waiting_get::<Pop<usize>>() {
+        received.push(x);
+    }
+    for i in 0..4 {
+        let ordered = received
+            .iter()
+            .filter(|x| **x / 100 == i)
+            .cloned()
+            .collect::<Vec<_>>();
+        assert_eq!(ordered, (i * 100..i * 100 + 100).collect::<Vec<_>>());
+    }
+}
+
+#[test]
+fn clone() {
+    let mut x = RwLockStorage::new();
+    x.allocate_queue::<usize>();
+    x.allocate_for_clone::<usize>();
+    x.insert_many(vec![0usize, 1]).unwrap();
+    let y = x.try_clone().unwrap();
+    ok!(y.get::<Pop<usize>>(), 0);
+    ok!(x.get::<(Pop<usize>, Pop<usize>)>(), (0, 1));
+}
@@ -1,5 +1,5 @@
 #![allow(unused_must_use)]
-use restor::{err, ok, ErrorDesc, RwLockStorage};
+use restor::{err, ok, ErrorDesc, RwLockStorage, Snapshot, UnitError};
 
 #[test]
 fn instantiate() {
@@ -117,8 +117,182 @@ fn ind_mut() {
         ok!(y, 10, [0]);
     }
 }
+#[test]
+fn debug() {
+    // Without `parking_lot`, this is the storage built on `std::sync`.
+    let name = if cfg!(feature = "parking_lot") {
+        "RwLockStorage"
+    } else {
+        "StdRwLockStorage"
+    };
+    let mut x = RwLockStorage::new();
+    x.allocate_for_debug::<usize>();
+    x.insert(3usize).unwrap();
+    let guard = x.get::<&usize>().unwrap();
+    assert_eq!(format!("{:?}", x), format!("{} {{ usize: One(3) }}", name));
+    drop(guard);
+    let _guard = x.get::<&mut usize>().unwrap();
+    assert_eq!(
+        format!("{:?}", x),
+        format!("{} {{ usize: <borrowed> }}", name)
+    );
+}
+
+#[test]
+fn try_clone_into() {
+    use restor::DynamicStorage;
+    let mut x = DynamicStorage::new();
+    x.allocate_for_clone::<usize>();
+    x.allocate_for_clone::<String>();
+    x.insert_many(vec![1usize, 2]).unwrap();
+    let mut y = RwLockStorage::new();
+    y.allocate_for::<usize>();
+    y.insert(5usize).unwrap();
+    assert_eq!(
+        x.try_clone_into(&y),
+        Err(vec![("alloc::string::String", ErrorDesc::NoAllocatedUnit)])
+    );
+    ok!(y.get::<&[usize]>(), [1, 2], *);
+    y.allocate_for::<String>();
+    x.try_clone_into(&y).unwrap();
+}
+
+#[test]
+fn atomic() {
+    let mut x = RwLockStorage::new();
+    x.allocate_atomic::<usize>();
+    x.allocate_for::<isize>();
+    err!(x.load::<usize>(), ErrorDesc::Unit(UnitError::IsNope));
+    x.insert(1usize).unwrap();
+    ok!(x.store(2usize));
+    ok!(x.update::<usize, _>(|v| v * 10), 2);
+    ok!(x.load::<usize>(), 20);
+    err!(x.get::<&usize>(), ErrorDesc::Unit(UnitError::Unsupported));
+    err!(x.load::<isize>(), ErrorDesc::Unit(UnitError::Unsupported));
+}
+
+#[test]
+fn atomic_sizes() {
+    let mut x = RwLockStorage::new();
+    x.allocate_atomic::<bool>();
+    x.allocate_atomic::<i16>();
+    x.allocate_atomic::<char>();
+    x.allocate_atomic::<Option<u32>>();
+    x.allocate_atomic::<[u8; 3]>();
+    err!(
+        x.update::<char, _>(|c| c),
+        ErrorDesc::Unit(UnitError::IsNope)
+    );
+    ok!(x.store(true));
+    ok!(x.store(false));
+    ok!(x.store(-2i16));
+    ok!(x.update::<i16, _>(|v| v * 3), -2);
+    ok!(x.store('a'));
+    ok!(x.update::<char, _>(|c| (c as u8 + 1) as char), 'a');
+    ok!(x.store(Some(7u32)));
+    ok!(x.update::<Option<u32>, _>(|v| v.map(|v| v + 1)), Some(7));
+    ok!(x.store([1u8, 2, 3]));
+    ok!(x.update::<[u8; 3], _>(|[a, b, c]| [c, b, a]), [1, 2, 3]);
+    ok!(x.load::<bool>(), false);
+    ok!(x.load::<i16>(), -6);
+    ok!(x.load::<char>(), 'b');
+    ok!(x.load::<Option<u32>>(), Some(8));
+    ok!(x.load::<[u8; 3]>(), [3, 2, 1]);
+}
+
+#[test]
+fn atomic_merge() {
+    let mut x = RwLockStorage::new();
+    x.allocate_atomic::<usize>();
+    x.store(1usize).unwrap();
+    let mut y = RwLockStorage::new();
+    y.allocate_for::<usize>();
+    y.insert_many(vec![2usize, 3]).unwrap();
+    let (rest, failed) = x.merge_from(y).unwrap_err();
+    assert_eq!(
+        failed,
+        vec![("usize", ErrorDesc::Unit(UnitError::IsNotOne))]
+    );
+    ok!(x.load::<usize>(), 1);
+    ok!(rest.get::<&[usize]>(), [2, 3], *);
+
+    let mut y = RwLockStorage::new();
+    y.allocate_for::<usize>();
+    y.insert(3usize).unwrap();
+    x.merge_from(y).unwrap();
+    ok!(x.load::<usize>(), 3);
+
+    let mut y = RwLockStorage::new();
+    y.allocate_for::<usize>();
+    y.insert(4usize).unwrap();
+    y.merge_from(x).unwrap();
+    ok!(y.get::<&[usize]>(), [4, 3], *);
+}
+
+#[test]
+fn rcu() {
+    let mut x = RwLockStorage::new();
+    x.allocate_rcu::<String>();
+    x.allocate_for::<usize>();
+    err!(
+        x.get::<Snapshot<String>>(),
+        ErrorDesc::Unit(UnitError::IsNope)
+    );
+    x.insert(String::from("a")).unwrap();
+    x.insert(1usize).unwrap();
+    let (old, one) = ok!(x.get::<(Snapshot<String>, &usize)>());
+    ok!(x.replace(String::from("b")));
+    let previous = ok!(x.rcu::<String, _>(|s| format!("{}c", s)));
+    assert_eq!(&*old, "a");
+    assert_eq!(&*previous, "b");
+    assert_eq!(*one, 1);
+    ok!(x.get::<Snapshot<String>>(), "bc", *);
+    err!(x.get::<&String>(), ErrorDesc::Unit(UnitError::Unsupported));
+    err!(
+        x.get::<Snapshot<usize>>(),
+        ErrorDesc::Unit(UnitError::Unsupported)
+    );
+    err!(x.replace(2usize), ErrorDesc::Unit(UnitError::Unsupported));
+}
+
+#[test]
+fn rcu_merge() {
+    let mut x = RwLockStorage::new();
+    x.allocate_rcu::<String>();
+    x.insert(String::from("a")).unwrap();
+    let snapshot = ok!(x.get::<Snapshot<String>>());
+    let mut y = RwLockStorage::new();
+    y.allocate_for::<String>();
+    let (x, failed) = y.merge_from(x).unwrap_err();
+    assert_eq!(
+        failed,
+        vec![("alloc::string::String", ErrorDesc::BorrowedIncompatibly)]
+    );
+    ok!(x.get::<Snapshot<String>>(), "a", *);
+    drop(snapshot);
+    y.merge_from(x).unwrap();
+    ok!(y.get::<&String>(), "a", *);
+
+    let mut x = RwLockStorage::new();
+    x.allocate_rcu::<String>();
+    y.insert(String::from("b")).unwrap();
+    let (y, failed) = x.merge_from(y).unwrap_err();
+    assert_eq!(
+        failed,
+        vec![(
+            "alloc::string::String",
+            ErrorDesc::Unit(UnitError::IsNotOne)
+        )]
+    );
+    ok!(y.get::<&[String]>(), ["a", "b"], *);
+    err!(
+        x.get::<Snapshot<String>>(),
+        ErrorDesc::Unit(UnitError::IsNope)
+    );
+}
+
 mod concurrent {
-    use restor::{ok, RwLockStorage};
+    use restor::{ok, RwLockStorage, Snapshot};
     use std::sync::Arc;
     use std::thread::spawn;
     use std::time::Duration;
@@ -157,6 +331,60 @@ mod concurrent {
         t2.join().unwrap();
     }
 
+    #[test]
+    fn atomic() {
+        let mut x = RwLockStorage::new();
+        x.allocate_atomic::<(usize, usize)>();
+        x.store((0usize, 0usize)).unwrap();
+        let x = Arc::new(x);
+        let threads = (0..4)
+            .map(|_| {
+                let xc = x.clone();
+                spawn(move || {
+                    for _ in 0..1000 {
+                        xc.update::<(usize, usize), _>(|(a, b)| (a + 1, b + 1))
+                            .unwrap();
+                        let (a, b) = xc.load::<(usize, usize)>().unwrap();
+                        assert_eq!(a, b);
+                    }
+                })
+            })
+            .collect::<Vec<_>>();
+        for t in threads {
+            t.join().unwrap();
+        }
+        ok!(x.load::<(usize, usize)>(), (4000, 4000));
+    }
+
+    #[test]
+    fn rcu() {
+        let mut x = RwLockStorage::new();
+        x.allocate_rcu::<Vec<usize>>();
+        x.replace(Vec::<usize>::new()).unwrap();
+        let x = Arc::new(x);
+        let threads = (0..4)
+            .map(|_| {
+                let xc = x.clone();
+                spawn(move || {
+                    for i in 0..100 {
+                        let snapshot = xc.get::<Snapshot<Vec<usize>>>().unwrap();
+                        xc.rcu::<Vec<usize>, _>(|v| {
+                            let mut v = v.clone();
+                            v.push(i);
+                            v
+                        })
+                        .unwrap();
+                        assert!(snapshot.len() < 400);
+                    }
+                })
+            })
+            .collect::<Vec<_>>();
+        for t in threads {
+            t.join().unwrap();
+        }
+        assert_eq!(x.get::<Snapshot<Vec<usize>>>().unwrap().len(), 400);
+    }
+
     #[test]
     fn ind_mut() {
         let mut x = RwLockStorage::new();
@@ -0,0 +1,146 @@
+#![cfg(feature = "std")]
+#![allow(unused)]
+
+use restor::{make_storage, ok, ErrorDesc, RwLockStorage, Scheduler, UnitError};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Barrier, Mutex};
+
+#[derive(Debug)]
+struct A(usize);
+#[derive(Debug)]
+struct B(usize);
+
+#[test]
+fn parallel_reads() {
+    let storage = make_storage!(RwLockStorage: A, B);
+    storage.insert(A(0)).unwrap();
+    storage.insert(B(0)).unwrap();
+    // Both systems only read, so they can only get past the barrier together.
+    let barrier = Barrier::new(2);
+    let mut scheduler = Scheduler::new(&storage).with_threads(2);
+    scheduler
+        .add::<&A, _>(|_| {
+            barrier.wait();
+        })
+        .add::<(&A, &B), _>(|_| {
+            barrier.wait();
+        });
+    scheduler.run().unwrap();
+}
+
+#[test]
+fn conflicting_in_order() {
+    let storage = make_storage!(RwLockStorage: A, B);
+    storage.insert(A(0)).unwrap();
+    storage.insert(B(0)).unwrap();
+    let order = Mutex::new(Vec::new());
+    let mut scheduler = Scheduler::new(&storage);
+    for i in 0..8 {
+        let order = &order;
+        scheduler.add::<&mut A, _>(move |mut a| {
+            a.0 += 1;
+            order.lock().unwrap().push(i);
+        });
+    }
+    scheduler.add::<(&A, &mut B), _>(|(a, mut b)| b.0 = a.0);
+    scheduler.run().unwrap();
+    assert_eq!(*order.lock().unwrap(), (0..8).collect::<Vec<_>>());
+    assert_eq!(ok!(storage.get::<&B>()).0, 8);
+}
+
+#[test]
+fn single_thread() {
+    let storage = make_storage!(RwLockStorage: A, B);
+    storage.insert(A(1)).unwrap();
+    storage.insert(B(2)).unwrap();
+    let runs = AtomicUsize::new(0);
+    let mut scheduler = Scheduler::new(&storage).with_threads(1);
+    scheduler
+        .add::<&A, _>(|_| {
+            runs.fetch_add(1, Ordering::SeqCst);
+        })
+        .add::<&B, _>(|_| {
+            runs.fetch_add(1, Ordering::SeqCst);
+        })
+        .add::<(&mut A, &B), _>(|(mut a, b)| a.0 += b.0);
+    for _ in 0..3 {
+        scheduler.run().unwrap();
+    }
+    assert_eq!(runs.load(Ordering::SeqCst), 6);
+    assert_eq!(ok!(storage.get::<&A>()).0, 7);
+}
+
+#[test]
+fn errors() {
+    let storage = make_storage!(RwLockStorage: A, B);
+    storage.insert(A(0)).unwrap();
+    let ran = AtomicUsize::new(0);
+    let mut scheduler = Scheduler::new(&storage);
+    scheduler
+        .add::<(&mut A, &A), _>(|_| unreachable!())
+        .add::<&B, _>(|_| unreachable!())
+        .add::<&mut A, _>(|_| {
+            ran.fetch_add(1, Ordering::SeqCst);
+        });
+    assert_eq!(
+        scheduler.run(),
+        Err(vec![
+            (0, ErrorDesc::BorrowedIncompatibly),
+            (1, ErrorDesc::Unit(UnitError::IsNotOne)),
+        ])
+    );
+    assert_eq!(ran.load(Ordering::SeqCst), 1);
+}
+
+#[test]
+#[should_panic(expected = "system panicked")]
+fn panics() {
+    let storage = make_storage!(RwLockStorage: A);
+    storage.insert(A(0)).unwrap();
+    let mut scheduler = Scheduler::new(&storage);
+    scheduler
+        .add::<&A, _>(|_| panic!("system panicked"))
+        .add::<&mut A, _>(|mut a| a.0 += 1);
+    let _ = scheduler.run();
+}
+
+#[test]
+fn take_then_read() {
+    let storage = make_storage!(RwLockStorage: A);
+    storage.insert_many(vec![A(0), A(1), A(2)]).unwrap();
+    let taken = Mutex::new(Vec::new());
+    let mut scheduler = Scheduler::new(&storage);
+    scheduler.add::<(Box<A>, &[A]), _>(|(a, rest)| {
+        taken.lock().unwrap().push(rest.len());
+    });
+    scheduler.run().unwrap();
+    scheduler.run().unwrap();
+    assert_eq!(*taken.lock().unwrap(), vec![2, 1]);
+}
+
+#[test]
+fn repeated_runs() {
+    let storage = make_storage!(RwLockStorage: A, B);
+    storage.insert(A(0)).unwrap();
+    storage.insert(B(0)).unwrap();
+    let barrier = Barrier::new(3);
+    let mut scheduler = Scheduler::new(&storage).with_threads(3);
+    scheduler
+        .add::<&A, _>(|_| {
+            barrier.wait();
+        })
+        .add::<&B, _>(|_| {
+            barrier.wait();
+        })
+        .add::<(&A, &B), _>(|_| {
+            barrier.wait();
+        })
+        .add::<(&mut A, &mut B), _>(|(mut a, mut b)| {
+            a.0 += 1;
+            b.0 += 1;
+        });
+    for _ in 0..100 {
+        scheduler.run().unwrap();
+    }
+    assert_eq!(ok!(storage.get::<&A>()).0, 100);
+}
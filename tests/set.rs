@@ -0,0 +1,143 @@
+#![allow(unused)]
+
+use restor::{
+    err, ok, Drain, DynamicStorage, ErrorDesc, MutexStorage, Pop, RwLockStorage, UnitError,
+};
+use std::sync::Arc;
+use std::thread::spawn;
+
+#[test]
+fn deduplicated() {
+    let mut x = DynamicStorage::new();
+    x.allocate_set::<String>();
+    x.insert(String::from("a")).unwrap();
+    x.insert(String::from("b")).unwrap();
+    let (value, error) = x.insert(String::from("a")).unwrap_err();
+    assert_eq!(value, "a");
+    assert_eq!(error, ErrorDesc::Unit(UnitError::AlreadyPresent));
+    err!(
+        x.insert_many(vec![
+            String::from("b"),
+            String::from("c"),
+            String::from("c"),
+        ]),
+        (
+            vec![String::from("b"), String::from("c")],
+            ErrorDesc::Unit(UnitError::AlreadyPresent)
+        )
+    );
+    assert_eq!(&*ok!(x.get::<&[String]>()), &["a", "b", "c"]);
+}
+
+#[test]
+fn lookup() {
+    let mut x = RwLockStorage::new();
+    x.allocate_set::<usize>();
+    x.insert_many((0..100usize).collect()).unwrap();
+    ok!(x.contains(&50usize), true);
+    ok!(x.remove_value(&50usize), 50);
+    ok!(x.contains(&50usize), false);
+    err!(x.remove_value(&50usize), ErrorDesc::Unit(UnitError::IsNope));
+    ok!(x.remove_value(&0usize), 0);
+    for i in 1..100usize {
+        ok!(x.contains(&i), i != 50);
+    }
+    assert_eq!(ok!(x.get::<&[usize]>()).len(), 98);
+    x.insert(50usize).unwrap();
+    ok!(x.contains(&50usize), true);
+}
+
+#[test]
+fn not_a_set() {
+    let mut x = DynamicStorage::new();
+    x.allocate_for::<usize>();
+    x.insert(0usize).unwrap();
+    err!(x.contains(&0usize), ErrorDesc::Unit(UnitError::Unsupported));
+    err!(
+        x.remove_value(&0usize),
+        ErrorDesc::Unit(UnitError::Unsupported)
+    );
+    err!(x.contains(&0u8), ErrorDesc::NoAllocatedUnit);
+}
+
+#[test]
+fn not_mutable() {
+    let mut x = DynamicStorage::new();
+    x.allocate_set::<usize>();
+    x.insert_many(vec![0usize, 1]).unwrap();
+    err!(
+        x.get::<&mut [usize]>(),
+        ErrorDesc::Unit(UnitError::Unsupported)
+    );
+    err!(
+        x.run_for_mut::<usize, _, _>(|x| x.push(0)),
+        ErrorDesc::Unit(UnitError::Unsupported)
+    );
+}
+
+#[test]
+fn taken() {
+    let mut x = DynamicStorage::new();
+    x.allocate_set::<usize>();
+    x.insert_many(vec![0usize, 1, 2]).unwrap();
+    ok!(x.get::<Pop<usize>>(), 2);
+    ok!(x.contains(&2usize), false);
+    ok!(x.get::<Drain<usize>>(), vec![0, 1]);
+    ok!(x.contains(&0usize), false);
+    x.insert(0usize).unwrap();
+    ok!(x.get::<Box<usize>>(), 0);
+}
+
+#[test]
+fn merged() {
+    let mut x = DynamicStorage::new();
+    x.allocate_set::<usize>();
+    x.insert_many(vec![0usize, 1]).unwrap();
+    let mut y = DynamicStorage::new();
+    y.allocate_for::<usize>();
+    y.insert_many(vec![1usize, 2]).unwrap();
+    let (y, failed) = x.merge_from(y).unwrap_err();
+    assert_eq!(
+        failed,
+        vec![("usize", ErrorDesc::Unit(UnitError::AlreadyPresent))]
+    );
+    assert_eq!(&*ok!(x.get::<&[usize]>()), &[0, 1, 2]);
+    ok!(y.get::<&usize>(), 1, *);
+}
+
+#[test]
+fn clone() {
+    let mut x = RwLockStorage::new();
+    x.allocate_set::<usize>();
+    x.allocate_for_clone::<usize>();
+    x.insert_many(vec![0usize, 1]).unwrap();
+    let y = x.try_clone().unwrap();
+    err!(
+        y.insert(1usize),
+        (1, ErrorDesc::Unit(UnitError::AlreadyPresent))
+    );
+    ok!(y.contains(&0usize), true);
+}
+
+#[test]
+fn registry() {
+    let mut x = MutexStorage::new();
+    x.allocate_set::<usize>();
+    let x = Arc::new(x);
+    let threads = (0..4)
+        .map(|_| {
+            let xc = x.clone();
+            spawn(move || {
+                (0..50usize)
+                    .filter(|i| xc.waiting_insert(*i).is_ok())
+                    .count()
+            })
+        })
+        .collect::<Vec<_>>();
+    let inserted = threads
+        .into_iter()
+        .map(|t| t.join().unwrap())
+        .sum::<usize>();
+    assert_eq!(inserted, 50);
+    assert_eq!(ok!(x.get::<Drain<usize>>()).len(), 50);
+}
@@ -0,0 +1,136 @@
+#![allow(unused)]
+
+use restor::{
+    err, ok, Drain, DynamicStorage, ErrorDesc, MutexStorage, Pop, RwLockStorage, UnitError,
+};
+use std::sync::Arc;
+use std::thread::spawn;
+
+#[derive(Debug, Clone, PartialEq)]
+struct Job {
+    priority: u8,
+    name: &'static str,
+}
+
+fn job(priority: u8, name: &'static str) -> Job {
+    Job { priority, name }
+}
+
+#[test]
+fn ordered() {
+    let mut x = DynamicStorage::new();
+    x.allocate_sorted::<usize>();
+    x.insert(3usize).unwrap();
+    x.insert_many(vec![5usize, 1, 4]).unwrap();
+    x.insert(2usize).unwrap();
+    assert_eq!(&*ok!(x.get::<&[usize]>()), &[1, 2, 3, 4, 5]);
+    ok!(x.get::<Box<usize>>(), 5);
+    ok!(x.get::<Pop<usize>>(), 4);
+    ok!(x.get::<Drain<usize>>(), vec![1, 2, 3]);
+    err!(x.get::<Pop<usize>>(), ErrorDesc::Unit(UnitError::IsNope));
+    x.insert(0usize).unwrap();
+    ok!(x.get::<Pop<usize>>(), 0);
+}
+
+#[test]
+fn by_key() {
+    let mut x = RwLockStorage::new();
+    x.allocate_sorted_by_key::<Job, _>(|job| job.priority);
+    x.insert(job(1, "a")).unwrap();
+    x.insert(job(3, "b")).unwrap();
+    x.insert(job(1, "c")).unwrap();
+    x.insert(job(2, "d")).unwrap();
+    let names =
+        ok!(x.run_for::<Job, _, _>(|jobs| { jobs.iter().map(|job| job.name).collect::<Vec<_>>() }));
+    assert_eq!(names, vec!["c", "a", "d", "b"]);
+    ok!(x.get::<Box<Job>>(), job(3, "b"));
+    ok!(x.get::<Pop<Job>>(), job(2, "d"));
+    ok!(x.get::<Pop<Job>>(), job(1, "a"));
+}
+
+#[test]
+fn ties() {
+    let mut x = DynamicStorage::new();
+    x.allocate_sorted_by_key::<Job, _>(|job| job.priority);
+    x.insert(job(1, "a")).unwrap();
+    x.insert(job(2, "b")).unwrap();
+    x.insert_many(vec![job(1, "c"), job(2, "d"), job(1, "e")])
+        .unwrap();
+    x.insert(job(2, "f")).unwrap();
+    let names = (0..6)
+        .map(|_| ok!(x.get::<Pop<Job>>()).name)
+        .collect::<Vec<_>>();
+    assert_eq!(names, vec!["b", "d", "f", "a", "c", "e"]);
+    x.insert_many(vec![job(0, "g"), job(0, "h")]).unwrap();
+    x.insert(job(0, "i")).unwrap();
+    let names = ok!(x.get::<Drain<Job>>())
+        .into_iter()
+        .map(|job| job.name)
+        .collect::<Vec<_>>();
+    assert_eq!(names, vec!["i", "h", "g"]);
+}
+
+#[test]
+fn not_mutable() {
+    let mut x = DynamicStorage::new();
+    x.allocate_sorted::<usize>();
+    x.insert_many(vec![1usize, 0]).unwrap();
+    err!(
+        x.get::<&mut [usize]>(),
+        ErrorDesc::Unit(UnitError::Unsupported)
+    );
+    err!(x.get::<&usize>(), ErrorDesc::Unit(UnitError::Unsupported));
+    x.insert(2usize).unwrap();
+    assert_eq!(&*ok!(x.get::<&[usize]>()), &[0, 1, 2]);
+}
+
+#[test]
+fn already_allocated() {
+    let mut x = DynamicStorage::new();
+    x.allocate_for::<usize>();
+    x.allocate_sorted::<usize>();
+    x.insert_many(vec![1usize, 0]).unwrap();
+    assert_eq!(&*ok!(x.get::<&[usize]>()), &[1, 0]);
+}
+
+#[test]
+fn merged() {
+    let mut x = DynamicStorage::new();
+    x.allocate_sorted::<usize>();
+    x.insert_many(vec![4usize, 0]).unwrap();
+    let mut y = DynamicStorage::new();
+    y.allocate_for::<usize>();
+    y.insert_many(vec![3usize, 1]).unwrap();
+    x.merge_from(y).unwrap();
+    assert_eq!(&*ok!(x.get::<&[usize]>()), &[0, 1, 3, 4]);
+}
+
+#[test]
+fn clone() {
+    let mut x = RwLockStorage::new();
+    x.allocate_sorted_by_key::<usize, _>(|x| std::cmp::Reverse(*x));
+    x.allocate_for_clone::<usize>();
+    x.insert_many(vec![0usize, 2, 1]).unwrap();
+    let y = x.try_clone().unwrap();
+    y.insert(3usize).unwrap();
+    assert_eq!(&*ok!(y.get::<&[usize]>()), &[3, 2, 1, 0]);
+}
+
+#[test]
+fn work_queue() {
+    let mut x = MutexStorage::new();
+    x.allocate_sorted::<usize>();
+    x.insert_many(vec![2usize, 0, 1]).unwrap();
+    let x = Arc::new(x);
+    let xc = x.clone();
+    let handle = spawn(move || {
+        (0..4)
+            .map(|_| xc.waiting_take::<usize>().unwrap())
+            .collect::<Vec<_>>()
+    });
+    x.waiting_insert(3usize).unwrap();
+    let mut taken = handle.join().unwrap();
+    assert!(taken[0] >= 2);
+    taken.sort();
+    assert_eq!(taken, vec![0, 1, 2, 3]);
+}
@@ -0,0 +1,111 @@
+#![allow(unused)]
+
+use restor::{
+    err, make_storage, ok, DynamicStorage, ErrorDesc, MixedStorage, MutexStorage, RwLockStorage,
+    UnitError,
+};
+use std::sync::Arc;
+use std::thread::{sleep, spawn};
+use std::time::{Duration, Instant};
+
+#[test]
+fn try_take() {
+    let x = make_storage!(DynamicStorage: usize);
+    err!(x.try_take::<usize>(), ErrorDesc::Unit(UnitError::IsNope));
+    err!(
+        x.try_take_many::<usize>(),
+        ErrorDesc::Unit(UnitError::IsNope)
+    );
+    x.insert_many(vec![0usize, 1, 2]).unwrap();
+    ok!(x.try_take::<usize>(), 0);
+    ok!(x.try_take_many::<usize>(), vec![1, 2]);
+    err!(x.try_take::<usize>(), ErrorDesc::Unit(UnitError::IsNope));
+}
+
+#[test]
+fn waits_for_value() {
+    let x = make_storage!(Arc RwLockStorage: usize);
+    let xc = x.clone();
+    let producer = spawn(move || {
+        sleep(Duration::from_millis(50));
+        xc.waiting_insert(5usize).unwrap();
+    });
+    ok!(x.waiting_take::<usize>(), 5);
+    producer.join().unwrap();
+}
+
+#[test]
+fn take_many() {
+    let x = make_storage!(Arc MutexStorage: usize);
+    let xc = x.clone();
+    let producer = spawn(move || {
+        sleep(Duration::from_millis(50));
+        xc.waiting_insert_many(vec![1usize, 2, 3]).unwrap();
+    });
+    ok!(x.waiting_take_many::<usize>(), vec![1, 2, 3]);
+    producer.join().unwrap();
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn timeout() {
+    let x = make_storage!(RwLockStorage: usize);
+    let start = Instant::now();
+    err!(
+        x.waiting_take_timeout::<usize>(Duration::from_millis(20)),
+        ErrorDesc::Unit(UnitError::IsNope)
+    );
+    assert!(start.elapsed() >= Duration::from_millis(20));
+    x.insert(1usize).unwrap();
+    ok!(
+        x.waiting_take_many_timeout::<usize>(Duration::from_millis(20)),
+        vec![1]
+    );
+}
+
+#[test]
+fn producer_consumer() {
+    let mut x = MixedStorage::new();
+    x.allocate_queue::<usize>();
+    let x = Arc::new(x);
+    let producers = (0..4usize)
+        .map(|i| {
+            let xc = x.clone();
+            spawn(move || {
+                for j in 0..50 {
+                    xc.waiting_insert(i * 50 + j).unwrap();
+                }
+            })
+        })
+        .collect::<Vec<_>>();
+    let mut received = (0..200)
+        .map(|_| x.waiting_take::<usize>().unwrap())
+        .collect::<Vec<_>>();
+    for t in producers {
+        t.join().unwrap();
+    }
+    received.sort();
+    assert_eq!(received, (0..200).collect::<Vec<_>>());
+}
+
+#[test]
+fn transfer_wakes() {
+    let x = make_storage!(Arc RwLockStorage: usize);
+    let y = make_storage!(DynamicStorage: usize);
+    y.insert(3usize).unwrap();
+    let xc = x.clone();
+    let consumer = spawn(move || xc.waiting_take::<usize>().unwrap());
+    sleep(Duration::from_millis(50));
+    ok!(y.transfer::<usize, _>(&*x));
+    assert_eq!(consumer.join().unwrap(), 3);
+}
+
+#[test]
+fn unsupported() {
+    let mut x = RwLockStorage::new();
+    x.allocate_atomic::<usize>();
+    err!(
+        x.waiting_take::<usize>(),
+        ErrorDesc::Unit(UnitError::Unsupported)
+    );
+}
@@ -0,0 +1,222 @@
+#![cfg(feature = "tracing")]
+#![allow(unused)]
+
+use restor::{make_storage, ok, DynamicStorage, ErrorDesc, RwLockStorage, Storage};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use tracing::field::{Field, Visit};
+use tracing::span::{Attributes, Id, Record};
+use tracing::subscriber::with_default;
+use tracing::{Event, Metadata, Subscriber};
+
+type Fields = BTreeMap<String, String>;
+
+// Records the fields of every span and event, along with the span name or
+// event message under `name`.
+#[derive(Clone, Default)]
+struct Recorder(Arc<Mutex<Vec<Fields>>>);
+
+struct Visitor<'a>(&'a mut Fields);
+
+impl Visit for Visitor<'_> {
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        let name = match field.name() {
+            "message" => "name",
+            name => name,
+        };
+        self.0.insert(name.to_string(), format!("{:?}", value));
+    }
+
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.0.insert(field.name().to_string(), value.to_string());
+    }
+}
+
+impl Subscriber for Recorder {
+    fn enabled(&self, _: &Metadata) -> bool {
+        true
+    }
+
+    fn new_span(&self, span: &Attributes) -> Id {
+        let mut fields = Fields::new();
+        fields.insert("name".to_string(), span.metadata().name().to_string());
+        span.record(&mut Visitor(&mut fields));
+        let mut records = self.0.lock().unwrap();
+        records.push(fields);
+        Id::from_u64(records.len() as u64)
+    }
+
+    fn record(&self, _: &Id, _: &Record) {}
+
+    fn record_follows_from(&self, _: &Id, _: &Id) {}
+
+    fn event(&self, event: &Event) {
+        assert_eq!(event.metadata().target(), "restor");
+        let mut fields = Fields::new();
+        event.record(&mut Visitor(&mut fields));
+        self.0.lock().unwrap().push(fields);
+    }
+
+    fn enter(&self, _: &Id) {}
+
+    fn exit(&self, _: &Id) {}
+}
+
+fn record(f: impl FnOnce()) -> Vec<Fields> {
+    let recorder = Recorder::default();
+    with_default(recorder.clone(), f);
+    let records = recorder.0.lock().unwrap().clone();
+    records
+}
+
+fn fields(pairs: &[(&str, &str)]) -> Fields {
+    pairs
+        .iter()
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect()
+}
+
+#[test]
+fn allocate_and_insert() {
+    let records = record(|| {
+        let mut x = DynamicStorage::new();
+        x.allocate_for::<usize>();
+        x.allocate_queue::<u8>();
+        x.allocate_for::<usize>();
+        x.insert(0usize).unwrap();
+        x.insert_many(vec![1usize, 2]).unwrap();
+        x.insert(0u8).unwrap();
+    });
+    assert_eq!(
+        records,
+        vec![
+            fields(&[
+                ("name", "allocated a unit"),
+                ("unit", "usize"),
+                ("state", "Nope")
+            ]),
+            fields(&[
+                ("name", "allocated a unit"),
+                ("unit", "u8"),
+                ("state", "Queue(0)")
+            ]),
+            fields(&[
+                ("name", "inserted values"),
+                ("unit", "usize"),
+                ("count", "1"),
+                ("state", "One")
+            ]),
+            fields(&[
+                ("name", "inserted values"),
+                ("unit", "usize"),
+                ("count", "2"),
+                ("state", "Many(3)")
+            ]),
+            fields(&[
+                ("name", "inserted values"),
+                ("unit", "u8"),
+                ("count", "1"),
+                ("state", "Queue(1)")
+            ]),
+        ]
+    );
+}
+
+#[test]
+fn failures() {
+    let x = make_storage!(DynamicStorage: usize, String);
+    x.insert(0usize).unwrap();
+    x.insert(String::new()).unwrap();
+    let records = record(|| {
+        let held = ok!(x.get::<&mut usize>());
+        assert!(x.insert(1usize).is_err());
+        assert!(x.get::<(&usize, &String)>().is_err());
+        drop(held);
+        assert!(x.get::<&[usize]>().is_err());
+    });
+    assert_eq!(records.len(), 3);
+    assert_eq!(records[0]["name"], "failed to insert values");
+    assert_eq!(records[0]["state"], "borrowed");
+    assert!(records[0]["error"].starts_with("Borrowed"));
+    assert_eq!(records[1]["name"], "failed to fetch");
+    assert_eq!(records[1]["fetch"], "(&usize, &alloc::string::String)");
+    assert_eq!(
+        records[1]["units"],
+        "usize: borrowed, alloc::string::String: One"
+    );
+    assert_eq!(records[2]["units"], "usize: One");
+    assert_eq!(records[2]["error"], "Unit(IsNotMany)");
+}
+
+#[test]
+fn extract() {
+    let mut x = DynamicStorage::new();
+    x.allocate_for::<usize>();
+    x.allocate_queue::<u8>();
+    x.insert_many(vec![0usize, 1, 2]).unwrap();
+    x.insert_many(vec![0u8, 1]).unwrap();
+    let records = record(|| {
+        ok!(x.get::<(Box<usize>, &[usize])>());
+        ok!(x.try_take::<u8>());
+        ok!(x.try_take_many::<u8>());
+        assert!(x.try_take::<u8>().is_err());
+    });
+    assert_eq!(
+        records,
+        vec![
+            fields(&[
+                ("name", "took values out of a unit"),
+                ("unit", "usize"),
+                ("state", "Many(2)")
+            ]),
+            fields(&[
+                ("name", "took values out of a unit"),
+                ("unit", "u8"),
+                ("state", "Queue(1)")
+            ]),
+            fields(&[
+                ("name", "took values out of a unit"),
+                ("unit", "u8"),
+                ("state", "Queue(0)")
+            ]),
+            fields(&[
+                ("name", "failed to take values"),
+                ("unit", "u8"),
+                ("state", "Queue(0)"),
+                ("error", "Unit(IsNope)")
+            ]),
+        ]
+    );
+}
+
+#[test]
+fn waiting_spans() {
+    let mut x = RwLockStorage::new();
+    x.allocate_for::<usize>();
+    x.allocate_queue::<u8>();
+    x.insert(0usize).unwrap();
+    let records = record(|| {
+        ok!(x.waiting_get::<&usize>());
+        x.waiting_insert(1u8).unwrap();
+        ok!(x.waiting_take::<u8>());
+        x.black_box()
+            .waiting_run_for::<usize, _, _>(|_| ())
+            .unwrap_err();
+    });
+    let names = records.iter().map(|x| &x["name"][..]).collect::<Vec<_>>();
+    assert_eq!(
+        names,
+        vec![
+            "waiting_get",
+            "waiting_insert",
+            "inserted values",
+            "waiting_take",
+            "took values out of a unit",
+            "waiting_run_for"
+        ]
+    );
+    assert_eq!(records[0]["fetch"], "&usize");
+    assert_eq!(records[1]["unit"], "u8");
+    assert_eq!(records[5]["unit"], "usize");
+}
@@ -119,3 +119,98 @@ fn ind_mut() {
         ok!(y, 10, [0]);
     }
 }
+
+#[test]
+fn debug() {
+    let mut x = DynamicStorage::new();
+    x.allocate_for_debug::<usize>();
+    x.allocate_for_debug::<String>();
+    x.allocate_for::<isize>();
+    x.insert_many(vec![1usize, 2]).unwrap();
+    x.insert(String::from("abc")).unwrap();
+    assert_eq!(
+        format!("{:?}", x),
+        "DynamicStorage { alloc::string::String: One(\"abc\"), usize: Many([1, 2]), .. }"
+    );
+    let _guard = x.get::<&mut String>().unwrap();
+    assert_eq!(
+        format!("{:?}", x),
+        "DynamicStorage { alloc::string::String: <borrowed>, usize: Many([1, 2]), .. }"
+    );
+}
+
+#[test]
+fn try_clone() {
+    let mut x = DynamicStorage::new();
+    x.allocate_for_clone::<usize>();
+    x.allocate_for_clone::<String>();
+    x.insert_many(vec![1usize, 2]).unwrap();
+    x.insert(String::from("abc")).unwrap();
+    let y = ok!(x.try_clone());
+    y.insert(3usize).unwrap();
+    ok!(x.get::<&[usize]>(), [1, 2], *);
+    ok!(y.get::<&[usize]>(), [1, 2, 3], *);
+    ok!(y.get::<&String>(), "abc", *);
+}
+
+#[test]
+fn try_clone_partial() {
+    let mut x = DynamicStorage::new();
+    x.allocate_for_clone::<usize>();
+    x.allocate_for::<isize>();
+    x.insert(1usize).unwrap();
+    x.insert(1isize).unwrap();
+    let (y, failed) = err!(x.try_clone());
+    assert_eq!(failed, vec![("isize", ErrorDesc::NotCloneable)]);
+    ok!(y.get::<&usize>(), 1, *);
+    err!(
+        y.get::<&isize>(),
+        ErrorDesc::Unit(restor::UnitError::IsNotOne)
+    );
+
+    let _guard = x.get::<&mut usize>().unwrap();
+    let (_, failed) = err!(x.try_clone());
+    assert_eq!(
+        failed,
+        vec![
+            ("isize", ErrorDesc::NotCloneable),
+            ("usize", ErrorDesc::BorrowedIncompatibly)
+        ]
+    );
+}
+
+#[test]
+fn merge_from() {
+    let mut x = DynamicStorage::new();
+    x.allocate_for::<usize>();
+    x.allocate_for::<String>();
+    x.insert(0usize).unwrap();
+    let mut y = DynamicStorage::new();
+    y.allocate_for::<usize>();
+    y.allocate_for::<isize>();
+    y.allocate_for::<String>();
+    y.insert_many(vec![1usize, 2]).unwrap();
+    y.insert(-1isize).unwrap();
+    y.insert(String::from("abc")).unwrap();
+    x.merge_from(y).unwrap();
+    ok!(x.get::<&[usize]>(), [0, 1, 2], *);
+    ok!(x.get::<&isize>(), -1, *);
+    ok!(x.get::<&String>(), "abc", *);
+}
+
+#[test]
+fn transfer() {
+    let mut x = DynamicStorage::new();
+    x.allocate_for::<usize>();
+    x.insert(0usize).unwrap();
+    let mut y = DynamicStorage::new();
+    y.allocate_for::<usize>();
+    y.insert(5usize).unwrap();
+    let guard = y.get::<&usize>();
+    err!(x.transfer::<usize, _>(&y), ErrorDesc::BorrowedIncompatibly);
+    drop(guard);
+    x.transfer::<usize, _>(&y).unwrap();
+    ok!(y.get::<&[usize]>(), [5, 0], *);
+    err!(x.get::<&usize>());
+    err!(x.transfer::<isize, _>(&y), ErrorDesc::NoAllocatedUnit);
+}